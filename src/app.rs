@@ -2,20 +2,41 @@ use crate::app::events::EventHandler;
 use crate::app::state::AppState;
 use crate::aws::AWS;
 use crate::aws::s3_client::S3Client;
+use crate::config::Config;
 use crate::ui;
 use color_eyre::Result;
 use ratatui::DefaultTerminal;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub mod actions;
+#[cfg(test)]
+mod actions_test;
 pub mod events;
+pub mod external;
+#[cfg(test)]
+mod external_test;
 pub mod state;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AppMode {
     BucketList,
     ObjectList,
+    GlobalSearch,
+    Grep,
+    Jobs,
+    Rename,
+    Tags,
+    Metadata,
+    Share,
+    Download,
+    Sync,
+    Verify,
+    BucketAdmin,
+    Policy,
+    Lifecycle,
+    Usage,
+    Archive,
 }
 
 #[derive(Debug)]
@@ -25,11 +46,23 @@ pub struct App {
 }
 
 impl App {
-    pub async fn new() -> Result<Self> {
+    pub async fn new(read_only: bool) -> Result<Self> {
         let aws_config = AWS::new().await;
         let s3_client = S3Client::new(&aws_config.config);
 
-        let mut state = AppState::new(aws_config, Arc::new(s3_client));
+        // A broken config file should not stop the app, so report it and use the defaults
+        let (config, config_error) = match Config::load() {
+            Ok(config) => (config, None),
+            Err(e) => (Config::default(), Some(e)),
+        };
+
+        config.apply_limits(s3_client.limits());
+
+        let mut state = AppState::new(aws_config, config, Arc::new(s3_client), read_only);
+        if let Some(error) = config_error {
+            state.status_message = Some(error);
+            state.status_message_time = Some(Instant::now());
+        }
 
         state.s3_bucket.load_buckets().await?;
         Ok(Self {
@@ -43,7 +76,12 @@ impl App {
             self.update();
             terminal.draw(|frame| ui::render(self, frame))?;
 
-            if self.event_handler.handle_events(&mut self.state).await? {
+            let redraw = self.event_handler.handle_events(&mut self.state).await?;
+            if let Some(request) = self.state.external_request.take() {
+                external::handle_request(request, &mut self.state, terminal).await?;
+                continue;
+            }
+            if redraw {
                 // If true was returned, we need to redraw immediately
                 continue;
             }
@@ -55,12 +93,32 @@ impl App {
     }
 
     fn update(&mut self) {
+        self.state.global_search.poll();
+        self.state.grep.poll();
+        self.state.usage.poll();
+        if let Some(message) = self.state.jobs.poll().pop() {
+            self.state.status_message = Some(message);
+            self.state.status_message_time = Some(Instant::now());
+        }
+
+        // Surface the latest notification from the object view or bucket dialogs
+        if let Some(notification) = self
+            .state
+            .s3_object
+            .notification
+            .take()
+            .or_else(|| self.state.bucket_admin.notification.take())
+        {
+            self.state.status_message = Some(notification.status_message);
+            self.state.status_message_time = Some(notification.status_message_time);
+        }
+
         // Clear status message after 3 seconds
-        if let Some(time) = self.state.status_message_time {
-            if time.elapsed() > Duration::from_secs(3) {
-                self.state.status_message = None;
-                self.state.status_message_time = None;
-            }
+        if let Some(time) = self.state.status_message_time
+            && time.elapsed() > Duration::from_secs(3)
+        {
+            self.state.status_message = None;
+            self.state.status_message_time = None;
         }
     }
 }
@@ -22,6 +22,11 @@ pub enum AppActions {
     StartFilterSearch, // ? (for local filtering)
     SearchInput(char),
     SearchDelete,
+    Complete,            // Tab while typing a path
+    ToggleSearchRanking, // Ctrl+r
+    CycleSearchMode,     // Ctrl+t
+    StartGlobalSearch,   // S
+    StartGrep,           // Ctrl+g
     // SearchSubmit,
     Enter,
     // SearchCancel,
@@ -30,16 +35,53 @@ pub enum AppActions {
     // General actions
     Exit, // q
     // SelectItem, // Enter
-    GoBack,   // Esc
-    LoadMore, // Space
-    Download, // d
-    Refresh,  // r
+    GoBack,         // Esc
+    LoadMore,       // Space
+    Download,       // d
+    ToggleMark,     // m
+    ClearMarks,     // M
+    Yank,           // y
+    Cut,            // x
+    Paste,          // p
+    ToggleJobs,     // J
+    Rename,         // R
+    RenameFolder,   // F
+    EditTags,       // T
+    ShowInfo,       // i
+    EditMetadata,   // H
+    EditObject,     // e
+    OpenWith,       // O
+    Share,          // u
+    DownloadFolder, // W
+    Sync,           // Y
+    Verify,         // V
+    SetLimits,      // L
+    BucketPolicy,   // P
+    Lifecycle,      // C
+    Usage,          // U
+    Sort,           // t
+    Archive,        // A
+    Add,            // a
+    Delete,         // D
+    Save,           // Ctrl+s
+    Refresh,        // r
+    PauseJob,       // p in the jobs panel
+    CancelJob,      // c in the jobs panel
 
     // Special
     NoAction,
 }
 
 impl AppActions {
+    /// The jobs panel's meaning of keys that do something else elsewhere
+    pub fn in_jobs_panel(self) -> Self {
+        match self {
+            Self::Paste => Self::PauseJob,
+            Self::ClearSearch => Self::CancelJob,
+            action => action,
+        }
+    }
+
     pub fn from_key_event(key_event: KeyEvent, search_active: bool) -> Self {
         use crossterm::event::{KeyCode, KeyModifiers};
 
@@ -55,9 +97,11 @@ impl AppActions {
                 }
             }
 
-            // Search activation
-            (KeyCode::Char('/'), KeyModifiers::NONE) => Self::StartSearch,
-            (KeyCode::Char('?'), KeyModifiers::SHIFT) => Self::StartFilterSearch,
+            // Search activation ('/' and '?' are literal input while searching, e.g. in paths and globs)
+            (KeyCode::Char('/'), KeyModifiers::NONE) if !search_active => Self::StartSearch,
+            (KeyCode::Char('?'), KeyModifiers::SHIFT) if !search_active => Self::StartFilterSearch,
+            (KeyCode::Char('r'), KeyModifiers::CONTROL) => Self::ToggleSearchRanking,
+            (KeyCode::Char('t'), KeyModifiers::CONTROL) => Self::CycleSearchMode,
 
             // If search is active, most characters become search input
             _ if search_active => match (key_event.code, key_event.modifiers) {
@@ -66,6 +110,7 @@ impl AppActions {
                     Self::SearchInput(c.to_uppercase().next().unwrap())
                 }
                 (KeyCode::Char(c), KeyModifiers::SHIFT) => Self::SearchInput(c),
+                (KeyCode::Tab, _) => Self::Complete,
                 _ => Self::NoAction,
             },
 
@@ -98,6 +143,40 @@ impl AppActions {
                 // File operations
                 (KeyCode::Char('s'), KeyModifiers::NONE) => Self::Download, // Save/download
                 (KeyCode::Char('w'), KeyModifiers::NONE) => Self::Download, // Write (alternative)
+                (KeyCode::Char('W'), KeyModifiers::SHIFT) => Self::DownloadFolder,
+                (KeyCode::Char('Y'), KeyModifiers::SHIFT) => Self::Sync,
+                (KeyCode::Char('V'), KeyModifiers::SHIFT) => Self::Verify,
+                (KeyCode::Char('L'), KeyModifiers::SHIFT) => Self::SetLimits,
+                (KeyCode::Char('P'), KeyModifiers::SHIFT) => Self::BucketPolicy,
+                (KeyCode::Char('C'), KeyModifiers::SHIFT) => Self::Lifecycle,
+                (KeyCode::Char('U'), KeyModifiers::SHIFT) => Self::Usage,
+                (KeyCode::Char('A'), KeyModifiers::SHIFT) => Self::Archive,
+                (KeyCode::Char('t'), KeyModifiers::NONE) => Self::Sort,
+
+                // Search every bucket, or the contents of objects under a prefix
+                (KeyCode::Char('S'), KeyModifiers::SHIFT) => Self::StartGlobalSearch,
+                (KeyCode::Char('g'), KeyModifiers::CONTROL) => Self::StartGrep,
+
+                // Marking and yank/paste
+                (KeyCode::Char('m'), KeyModifiers::NONE) => Self::ToggleMark,
+                (KeyCode::Char('M'), KeyModifiers::SHIFT) => Self::ClearMarks,
+                (KeyCode::Char('y'), KeyModifiers::NONE) => Self::Yank,
+                (KeyCode::Char('x'), KeyModifiers::NONE) => Self::Cut,
+                (KeyCode::Char('p'), KeyModifiers::NONE) => Self::Paste,
+                (KeyCode::Char('J'), KeyModifiers::SHIFT) => Self::ToggleJobs,
+                (KeyCode::Char('R'), KeyModifiers::SHIFT) => Self::Rename,
+                (KeyCode::Char('F'), KeyModifiers::SHIFT) => Self::RenameFolder,
+
+                // Tag editing
+                (KeyCode::Char('T'), KeyModifiers::SHIFT) => Self::EditTags,
+                (KeyCode::Char('i'), KeyModifiers::NONE) => Self::ShowInfo,
+                (KeyCode::Char('H'), KeyModifiers::SHIFT) => Self::EditMetadata,
+                (KeyCode::Char('e'), KeyModifiers::NONE) => Self::EditObject,
+                (KeyCode::Char('O'), KeyModifiers::SHIFT) => Self::OpenWith,
+                (KeyCode::Char('u'), KeyModifiers::NONE) => Self::Share,
+                (KeyCode::Char('a'), KeyModifiers::NONE) => Self::Add,
+                (KeyCode::Char('D'), KeyModifiers::SHIFT) => Self::Delete,
+                (KeyCode::Char('s'), KeyModifiers::CONTROL) => Self::Save,
 
                 // General actions
                 (KeyCode::Char('q'), KeyModifiers::NONE) => Self::Exit,
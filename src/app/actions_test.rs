@@ -0,0 +1,60 @@
+use crate::app::actions::AppActions;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(c: char, modifiers: KeyModifiers, search_active: bool) -> AppActions {
+        AppActions::from_key_event(KeyEvent::new(KeyCode::Char(c), modifiers), search_active)
+    }
+
+    #[test]
+    fn test_search_keys_start_searches() {
+        assert!(matches!(
+            action('/', KeyModifiers::NONE, false),
+            AppActions::StartSearch
+        ));
+        assert!(matches!(
+            action('?', KeyModifiers::SHIFT, false),
+            AppActions::StartFilterSearch
+        ));
+    }
+
+    #[test]
+    fn test_search_keys_are_input_while_searching() {
+        assert!(matches!(
+            action('/', KeyModifiers::NONE, true),
+            AppActions::SearchInput('/')
+        ));
+        assert!(matches!(
+            action('?', KeyModifiers::SHIFT, true),
+            AppActions::SearchInput('?')
+        ));
+        assert!(matches!(
+            action('D', KeyModifiers::SHIFT, true),
+            AppActions::SearchInput('D')
+        ));
+    }
+
+    #[test]
+    fn test_jobs_panel_keys() {
+        assert!(matches!(
+            action('p', KeyModifiers::NONE, false).in_jobs_panel(),
+            AppActions::PauseJob
+        ));
+        assert!(matches!(
+            action('c', KeyModifiers::NONE, false).in_jobs_panel(),
+            AppActions::CancelJob
+        ));
+        assert!(matches!(
+            action('r', KeyModifiers::NONE, false).in_jobs_panel(),
+            AppActions::Refresh
+        ));
+        // Outside the panel the keys keep their usual meaning
+        assert!(matches!(
+            action('p', KeyModifiers::NONE, false),
+            AppActions::Paste
+        ));
+    }
+}
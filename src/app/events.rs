@@ -1,8 +1,14 @@
 use crate::app::AppMode;
 use crate::app::actions::AppActions;
+use crate::app::external::ExternalRequest;
 use crate::app::state::AppState;
+use crate::app::state::download::DownloadSource;
+use crate::app::state::jobs::JobKind;
+use crate::app::state::rename::RenameSource;
+use crate::safety::Mutation;
 use color_eyre::Result;
 use crossterm::event::{self, Event, KeyEventKind};
+use std::path::Path;
 use std::time::Duration;
 
 /// Handles all event processing for the application
@@ -20,12 +26,34 @@ impl EventHandler {
         if event::poll(Duration::from_millis(100))? {
             match event::read()? {
                 Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
-                    let search_active = match state.mode {
-                        AppMode::BucketList => state.s3_bucket.search_bar.active,
-                        AppMode::ObjectList => state.s3_object.search_bar.active,
-                    };
+                    let search_active = state.safety.is_confirming()
+                        || match state.mode {
+                            AppMode::BucketList => state.s3_bucket.search_bar.active,
+                            AppMode::ObjectList => {
+                                state.s3_object.search_bar.active
+                                    || state.s3_object.filter_bar.active
+                            }
+                            AppMode::GlobalSearch => state.global_search.prompt.active,
+                            AppMode::Grep => state.grep.prompt.active,
+                            AppMode::Jobs => state.jobs.limits_prompt.active,
+                            AppMode::Rename => state.rename.prompt.active,
+                            AppMode::Tags => state.tags.prompt.active,
+                            AppMode::Metadata => state.metadata.prompt.active,
+                            AppMode::Share => state.share.prompt.active,
+                            AppMode::Download => state.download.prompt.active,
+                            AppMode::Sync => state.sync.prompt.active,
+                            AppMode::Verify => state.verify.prompt.active,
+                            AppMode::BucketAdmin => state.bucket_admin.prompt.active,
+                            AppMode::Policy => false,
+                            AppMode::Lifecycle => state.lifecycle.prompt.active,
+                            AppMode::Usage => false,
+                            AppMode::Archive => state.archive.prompt.active,
+                        };
 
-                    let action = AppActions::from_key_event(key_event, search_active);
+                    let action = match AppActions::from_key_event(key_event, search_active) {
+                        action if state.mode == AppMode::Jobs => action.in_jobs_panel(),
+                        action => action,
+                    };
                     self.handle_key_event(action, state).await?;
                     return Ok(true);
                 }
@@ -35,8 +63,26 @@ impl EventHandler {
         Ok(false)
     }
 
-    /// Handle keyboard input events
+    /// Handle keyboard input events, passing actions that change S3 through the safety checks
     async fn handle_key_event(&self, action: AppActions, state: &mut AppState) -> Result<()> {
+        if state.safety.is_confirming() {
+            if let Some(action) = state.safety.handle_confirm(action) {
+                self.dispatch(action, state).await?;
+            }
+            return Ok(());
+        }
+
+        let action = match mutation(&action, state) {
+            Some(mutation) => match state.safety.check(action, mutation) {
+                Some(action) => action,
+                None => return Ok(()),
+            },
+            None => action,
+        };
+        self.dispatch(action, state).await
+    }
+
+    async fn dispatch(&self, action: AppActions, state: &mut AppState) -> Result<()> {
         match action {
             AppActions::Exit => {
                 state.exit = true;
@@ -50,6 +96,378 @@ impl EventHandler {
                     AppMode::ObjectList => {
                         state.s3_object.search_bar.toggle();
                     }
+                    AppMode::GlobalSearch => {
+                        state.global_search.open();
+                    }
+                    AppMode::Grep => {
+                        state.grep.prompt.active = true;
+                    }
+                    AppMode::Jobs
+                    | AppMode::Rename
+                    | AppMode::Tags
+                    | AppMode::Metadata
+                    | AppMode::Share
+                    | AppMode::Download
+                    | AppMode::Sync
+                    | AppMode::Verify
+                    | AppMode::BucketAdmin
+                    | AppMode::Policy
+                    | AppMode::Lifecycle
+                    | AppMode::Usage
+                    | AppMode::Archive => {}
+                }
+                return Ok(());
+            }
+            AppActions::Add if state.mode == AppMode::BucketList => {
+                state.bucket_admin.open_create();
+                state.mode = AppMode::BucketAdmin;
+                return Ok(());
+            }
+            AppActions::Delete if state.mode == AppMode::BucketList => {
+                if let Some(name) = state
+                    .s3_bucket
+                    .bucket_list
+                    .selected_item()
+                    .and_then(|bucket| bucket.name.clone())
+                {
+                    state.bucket_admin.open_delete(&name).await;
+                    state.mode = AppMode::BucketAdmin;
+                }
+                return Ok(());
+            }
+            AppActions::BucketPolicy if state.mode == AppMode::BucketList => {
+                if let Some(name) = state
+                    .s3_bucket
+                    .bucket_list
+                    .selected_item()
+                    .and_then(|bucket| bucket.name.clone())
+                {
+                    state.policy.open(&name).await;
+                    state.mode = AppMode::Policy;
+                }
+                return Ok(());
+            }
+            AppActions::EditObject if state.mode == AppMode::Policy => {
+                state.external_request = Some(ExternalRequest::EditPolicy);
+                return Ok(());
+            }
+            AppActions::Lifecycle if state.mode == AppMode::BucketList => {
+                if let Some(name) = state
+                    .s3_bucket
+                    .bucket_list
+                    .selected_item()
+                    .and_then(|bucket| bucket.name.clone())
+                {
+                    state.lifecycle.open(&name).await;
+                    state.mode = AppMode::Lifecycle;
+                }
+                return Ok(());
+            }
+            AppActions::EditObject
+                if state.mode == AppMode::Lifecycle && !state.lifecycle.editing =>
+            {
+                state.external_request = Some(ExternalRequest::EditLifecycle);
+                return Ok(());
+            }
+            AppActions::StartGlobalSearch if state.mode == AppMode::BucketList => {
+                state.global_search.open();
+                state.mode = AppMode::GlobalSearch;
+                return Ok(());
+            }
+            AppActions::Paste
+                if state.mode == AppMode::ObjectList && !state.s3_object.preview_object =>
+            {
+                if let Some((kind, description, items)) = state.s3_object.take_paste() {
+                    state.jobs.spawn_transfer(kind, description, items);
+                }
+                return Ok(());
+            }
+            AppActions::Delete
+                if state.mode == AppMode::ObjectList && !state.s3_object.preview_object =>
+            {
+                if let Some(keys) = state.s3_object.take_delete() {
+                    let bucket = state.s3_object.current_bucket.clone();
+                    state.jobs.spawn_delete(bucket, keys);
+                }
+                return Ok(());
+            }
+            AppActions::Rename
+                if state.mode == AppMode::ObjectList && !state.s3_object.preview_object =>
+            {
+                if let Some(key) = state
+                    .s3_object
+                    .object_list
+                    .selected_item()
+                    .and_then(|object| object.key())
+                {
+                    let source = RenameSource::Key(key.to_string());
+                    state.rename.open(&state.s3_object.current_bucket, source);
+                    state.mode = AppMode::Rename;
+                }
+                return Ok(());
+            }
+            AppActions::RenameFolder
+                if state.mode == AppMode::ObjectList && !state.s3_object.preview_object =>
+            {
+                let folder = state.s3_object.current_folder();
+                if folder.is_empty() {
+                    state.s3_object.update_status(
+                        "Filter by a prefix containing '/' to choose the folder to rename"
+                            .to_string(),
+                    );
+                } else {
+                    let source = RenameSource::Prefix(folder);
+                    state.rename.open(&state.s3_object.current_bucket, source);
+                    state.mode = AppMode::Rename;
+                }
+                return Ok(());
+            }
+            AppActions::EditTags
+                if state.mode == AppMode::ObjectList && !state.s3_object.preview_object =>
+            {
+                let keys = state.s3_object.marked_or_selected_keys();
+                if !keys.is_empty() {
+                    state.tags.open(&state.s3_object.current_bucket, keys).await;
+                    state.mode = AppMode::Tags;
+                }
+                return Ok(());
+            }
+            AppActions::EditMetadata
+                if state.mode == AppMode::ObjectList && !state.s3_object.preview_object =>
+            {
+                let keys = state.s3_object.marked_or_selected_keys();
+                if !keys.is_empty() {
+                    state
+                        .metadata
+                        .open(&state.s3_object.current_bucket, keys)
+                        .await;
+                    state.mode = AppMode::Metadata;
+                }
+                return Ok(());
+            }
+            AppActions::EditObject | AppActions::OpenWith if state.mode == AppMode::ObjectList => {
+                let s3_object = &state.s3_object;
+                let key = if s3_object.preview_object {
+                    s3_object.current_object.clone()
+                } else {
+                    s3_object
+                        .object_list
+                        .selected_item()
+                        .and_then(|object| object.key())
+                        .map(String::from)
+                };
+                if let Some(key) = key {
+                    let bucket = s3_object.current_bucket.clone();
+                    state.external_request = Some(match action {
+                        AppActions::OpenWith => ExternalRequest::OpenWith { bucket, key },
+                        _ => ExternalRequest::Edit { bucket, key },
+                    });
+                }
+                return Ok(());
+            }
+            AppActions::Archive if state.mode == AppMode::ObjectList => {
+                let s3_object = &state.s3_object;
+                let keys = match (&s3_object.current_object, s3_object.preview_object) {
+                    (Some(key), true) => vec![key.clone()],
+                    _ => s3_object.marked_or_selected_keys(),
+                };
+                if !keys.is_empty() {
+                    let objects = keys
+                        .into_iter()
+                        .map(|key| {
+                            let class = s3_object
+                                .object_list
+                                .items
+                                .iter()
+                                .find(|object| object.key() == Some(key.as_str()))
+                                .and_then(|object| object.storage_class())
+                                .map(|class| class.as_str().to_string());
+                            (key, class)
+                        })
+                        .collect();
+                    let bucket = s3_object.current_bucket.clone();
+                    state.archive.open(&bucket, objects).await;
+                    state.mode = AppMode::Archive;
+                }
+                return Ok(());
+            }
+            AppActions::Share if state.mode == AppMode::ObjectList => {
+                let s3_object = &state.s3_object;
+                let key = if s3_object.preview_object {
+                    s3_object.current_object.clone()
+                } else {
+                    s3_object
+                        .object_list
+                        .selected_item()
+                        .and_then(|object| object.key())
+                        .map(String::from)
+                };
+                if let Some(key) = key {
+                    let expiry = state.config.presign_expiry();
+                    state.share.open(&s3_object.current_bucket, &key, expiry);
+                    state.mode = AppMode::Share;
+                }
+                return Ok(());
+            }
+            AppActions::Download if state.mode == AppMode::ObjectList => {
+                let s3_object = &state.s3_object;
+                let source = if s3_object.preview_object {
+                    s3_object.current_object.clone().map(DownloadSource::Key)
+                } else if s3_object.marked.is_empty() {
+                    s3_object
+                        .object_list
+                        .selected_item()
+                        .and_then(|object| object.key())
+                        .map(|key| DownloadSource::Key(key.to_string()))
+                } else {
+                    Some(DownloadSource::Keys {
+                        keys: s3_object.marked_or_selected_keys(),
+                        folder: s3_object.current_folder(),
+                    })
+                };
+                if let Some(source) = source {
+                    let bucket = s3_object.current_bucket.clone();
+                    state
+                        .download
+                        .open(&bucket, source, &state.config.download_dir());
+                    state.mode = AppMode::Download;
+                }
+                return Ok(());
+            }
+            AppActions::DownloadFolder
+                if state.mode == AppMode::ObjectList && !state.s3_object.preview_object =>
+            {
+                let prefix = state.s3_object.listing_prefix().unwrap_or_default();
+                let bucket = state.s3_object.current_bucket.clone();
+                state.download.open(
+                    &bucket,
+                    DownloadSource::Prefix(prefix),
+                    &state.config.download_dir(),
+                );
+                state.mode = AppMode::Download;
+                return Ok(());
+            }
+            AppActions::Sync
+                if state.mode == AppMode::ObjectList && !state.s3_object.preview_object =>
+            {
+                let bucket = state.s3_object.current_bucket.clone();
+                let folder = state.s3_object.current_folder();
+                let default_dir = state.config.download_dir().join(&bucket).join(&folder);
+                state.sync.open(&bucket, folder, &default_dir);
+                state.mode = AppMode::Sync;
+                return Ok(());
+            }
+            AppActions::Verify
+                if state.mode == AppMode::ObjectList && !state.s3_object.preview_object =>
+            {
+                let bucket = state.s3_object.current_bucket.clone();
+                if let Some(key) = state
+                    .s3_object
+                    .object_list
+                    .selected_item()
+                    .and_then(|object| object.key())
+                    .filter(|key| !key.ends_with('/'))
+                {
+                    let file_name = key.rsplit('/').next().unwrap_or(key);
+                    let dir = state
+                        .download
+                        .recent_dir(&bucket)
+                        .map_or_else(|| state.config.download_dir(), Path::to_path_buf);
+                    state.verify.open(&bucket, key, &dir.join(file_name));
+                    state.mode = AppMode::Verify;
+                }
+                return Ok(());
+            }
+            AppActions::ToggleJobs if state.mode != AppMode::Jobs => {
+                state.jobs.return_mode = state.mode;
+                state.mode = AppMode::Jobs;
+                return Ok(());
+            }
+            AppActions::Usage if state.mode == AppMode::BucketList => {
+                if let Some(name) = state
+                    .s3_bucket
+                    .bucket_list
+                    .selected_item()
+                    .and_then(|bucket| bucket.name.clone())
+                {
+                    state.usage.open(&name, "", state.mode);
+                    state.mode = AppMode::Usage;
+                }
+                return Ok(());
+            }
+            AppActions::Usage
+                if state.mode == AppMode::ObjectList && !state.s3_object.preview_object =>
+            {
+                let prefix = state.s3_object.listing_prefix().unwrap_or_default();
+                state
+                    .usage
+                    .open(&state.s3_object.current_bucket, &prefix, state.mode);
+                state.mode = AppMode::Usage;
+                return Ok(());
+            }
+            AppActions::StartGrep
+                if state.mode == AppMode::ObjectList && !state.s3_object.preview_object =>
+            {
+                let prefix = state.s3_object.listing_prefix();
+                state.grep.open(&state.s3_object.current_bucket, prefix);
+                state.mode = AppMode::Grep;
+                return Ok(());
+            }
+            AppActions::ToggleSearchRanking => {
+                match state.mode {
+                    AppMode::BucketList => {
+                        state.s3_bucket.search_bar.toggle_ranking();
+                        state.s3_bucket.apply_current_search();
+                    }
+                    AppMode::ObjectList => {
+                        state.s3_object.search_bar.toggle_ranking();
+                        state.s3_object.apply_current_search();
+                    }
+                    AppMode::GlobalSearch
+                    | AppMode::Grep
+                    | AppMode::Jobs
+                    | AppMode::Rename
+                    | AppMode::Tags
+                    | AppMode::Metadata
+                    | AppMode::Share
+                    | AppMode::Download
+                    | AppMode::Sync
+                    | AppMode::Verify
+                    | AppMode::BucketAdmin
+                    | AppMode::Policy
+                    | AppMode::Lifecycle
+                    | AppMode::Usage
+                    | AppMode::Archive => {}
+                }
+                return Ok(());
+            }
+            AppActions::CycleSearchMode => {
+                match state.mode {
+                    AppMode::BucketList => {
+                        state.s3_bucket.search_bar.cycle_mode();
+                        state.s3_bucket.apply_current_search();
+                    }
+                    // The metadata filter bar has no match modes of its own
+                    AppMode::ObjectList if !state.s3_object.filter_bar.active => {
+                        state.s3_object.search_bar.cycle_mode();
+                        state.s3_object.apply_current_search();
+                    }
+                    AppMode::ObjectList => {}
+                    AppMode::GlobalSearch => state.global_search.prompt.cycle_mode(),
+                    AppMode::Grep
+                    | AppMode::Jobs
+                    | AppMode::Rename
+                    | AppMode::Tags
+                    | AppMode::Metadata
+                    | AppMode::Share
+                    | AppMode::Download
+                    | AppMode::Sync
+                    | AppMode::Verify
+                    | AppMode::BucketAdmin
+                    | AppMode::Policy
+                    | AppMode::Lifecycle
+                    | AppMode::Usage
+                    | AppMode::Archive => {}
                 }
                 return Ok(());
             }
@@ -60,8 +478,78 @@ impl EventHandler {
                         .handle_bucket_action(action, &mut state.mode, &mut state.s3_object)
                         .await?;
                 }
+                AppMode::GlobalSearch => {
+                    state
+                        .global_search
+                        .handle_action(
+                            action,
+                            &mut state.mode,
+                            &state.s3_bucket,
+                            &mut state.s3_object,
+                        )
+                        .await?;
+                }
+                AppMode::Jobs => state.jobs.handle_action(action, &mut state.mode),
+                AppMode::Usage => state.usage.handle_action(action, &mut state.mode),
+                AppMode::Archive => state.archive.handle_action(
+                    action,
+                    &mut state.mode,
+                    &mut state.s3_object,
+                    &mut state.jobs,
+                ),
+                AppMode::Tags => {
+                    state
+                        .tags
+                        .handle_action(action, &mut state.mode, &mut state.jobs)
+                        .await?;
+                }
+                AppMode::Metadata => {
+                    state
+                        .metadata
+                        .handle_action(action, &mut state.mode, &mut state.jobs)
+                        .await?;
+                }
+                AppMode::Share => state.share.handle_action(action, &mut state.mode).await,
+                AppMode::Verify => state.verify.handle_action(action, &mut state.mode).await,
+                AppMode::Policy => state.policy.handle_action(action, &mut state.mode).await,
+                AppMode::Lifecycle => state.lifecycle.handle_action(action, &mut state.mode).await,
+                AppMode::BucketAdmin => {
+                    state
+                        .bucket_admin
+                        .handle_action(
+                            action,
+                            &mut state.mode,
+                            &mut state.s3_bucket,
+                            &mut state.jobs,
+                        )
+                        .await?;
+                }
+                AppMode::Sync => {
+                    state
+                        .sync
+                        .handle_action(action, &mut state.mode, &mut state.jobs)
+                        .await?;
+                }
+                AppMode::Download => {
+                    state
+                        .download
+                        .handle_action(action, &mut state.mode, &mut state.jobs)
+                        .await?;
+                }
+                AppMode::Rename => {
+                    state
+                        .rename
+                        .handle_action(action, &mut state.mode, &mut state.jobs)
+                        .await?;
+                }
+                AppMode::Grep => {
+                    state
+                        .grep
+                        .handle_action(action, &mut state.mode, &mut state.s3_object)
+                        .await?;
+                }
                 AppMode::ObjectList => {
-                    if state.s3_object.preview_object == true {
+                    if state.s3_object.preview_object {
                         state.s3_object.handle_preview_action(action).await?;
                     } else {
                         state
@@ -75,3 +563,81 @@ impl EventHandler {
         Ok(())
     }
 }
+
+/// What `action` would change in S3 in the current mode, if anything
+fn mutation(action: &AppActions, state: &AppState) -> Option<Mutation> {
+    let s3_object = &state.s3_object;
+    let in_list = state.mode == AppMode::ObjectList && !s3_object.preview_object;
+    let here = || vec![s3_object.current_bucket.clone()];
+    let mutation = match (state.mode, action) {
+        (AppMode::BucketList, AppActions::Add) => {
+            Mutation::new("create a bucket", Vec::new(), false)
+        }
+        (AppMode::BucketList, AppActions::Delete) => {
+            let bucket = state.s3_bucket.bucket_list.selected_item()?.name.clone()?;
+            Mutation::new("delete the bucket", vec![bucket], true)
+        }
+        (AppMode::ObjectList, AppActions::Paste) if in_list => {
+            let yank = s3_object.yank.as_ref()?;
+            let mut buckets = here();
+            if yank.kind == JobKind::Move {
+                // Moves delete from the source bucket too
+                buckets.push(yank.bucket.clone());
+                Mutation::new("move objects here", buckets, true)
+            } else {
+                Mutation::new("paste objects here", buckets, true)
+            }
+        }
+        (AppMode::ObjectList, AppActions::Delete) if in_list => {
+            Mutation::new("delete objects", here(), s3_object.delete_armed())
+        }
+        (AppMode::ObjectList, AppActions::Rename | AppActions::RenameFolder) if in_list => {
+            Mutation::new("rename objects", here(), true)
+        }
+        (AppMode::ObjectList, AppActions::EditTags) if in_list => {
+            Mutation::new("edit tags", here(), false)
+        }
+        (AppMode::ObjectList, AppActions::EditMetadata) if in_list => {
+            Mutation::new("rewrite object metadata", here(), true)
+        }
+        (AppMode::ObjectList, AppActions::EditObject) => {
+            Mutation::new("edit and overwrite an object", here(), true)
+        }
+        (AppMode::ObjectList, AppActions::Archive) => Mutation::new(
+            "restore objects or change their storage class",
+            here(),
+            true,
+        ),
+        (AppMode::Sync, AppActions::Enter) => {
+            let bucket = state.sync.upload_bucket()?.to_string();
+            Mutation::new("sync local files up", vec![bucket], true)
+        }
+        (AppMode::Policy, AppActions::EditObject) => Mutation::new(
+            "edit the bucket policy",
+            vec![state.policy.bucket.clone()],
+            false,
+        ),
+        (AppMode::Policy, AppActions::Enter) if state.policy.pending.is_some() => Mutation::new(
+            "replace the bucket policy",
+            vec![state.policy.bucket.clone()],
+            true,
+        ),
+        (AppMode::Share, AppActions::Enter) if state.share.creates_upload_url() => Mutation::new(
+            "create a presigned upload URL",
+            vec![state.share.bucket.clone()],
+            true,
+        ),
+        (AppMode::Jobs, AppActions::Refresh) => state.jobs.retry_mutation()?,
+        (AppMode::Lifecycle, AppActions::Save)
+            if !state.lifecycle.prompt.active && !state.lifecycle.editing =>
+        {
+            Mutation::new(
+                "replace the lifecycle rules",
+                vec![state.lifecycle.bucket.clone()],
+                true,
+            )
+        }
+        _ => return None,
+    };
+    Some(mutation)
+}
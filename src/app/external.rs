@@ -0,0 +1,323 @@
+use crate::app::state::AppState;
+use crate::aws::s3_client::TransferProgress;
+use crate::config::Config;
+use crate::util::{create_private_file, format_size, local_path_for, split_shell_words};
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use crossterm::execute;
+use crossterm::terminal::{EnterAlternateScreen, enable_raw_mode};
+use ratatui::DefaultTerminal;
+use ratatui::layout::Rect;
+use ratatui::widgets::Paragraph;
+use std::fs;
+use std::io::stdout;
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Objects larger than this are not opened in the editor
+const MAX_EDIT_SIZE: i64 = 10 * 1024 * 1024;
+
+/// Work that needs the real terminal, carried out by `App::run` between frames
+#[derive(Debug, Clone)]
+pub enum ExternalRequest {
+    /// Edit an object in `$VISUAL`/`$EDITOR` and upload it if it changed
+    Edit { bucket: String, key: String },
+    /// Download an object and open it with the program configured for its type
+    OpenWith { bucket: String, key: String },
+    /// Edit the open bucket policy, then review it before it is saved
+    EditPolicy,
+    /// Edit the open lifecycle rules as JSON
+    EditLifecycle,
+}
+
+pub async fn handle_request(
+    request: ExternalRequest,
+    state: &mut AppState,
+    terminal: &mut DefaultTerminal,
+) -> Result<()> {
+    let result = match request {
+        ExternalRequest::Edit { bucket, key } => edit_object(state, terminal, &bucket, &key).await,
+        ExternalRequest::OpenWith { bucket, key } => {
+            open_with(state, terminal, &bucket, &key).await
+        }
+        ExternalRequest::EditPolicy => edit_policy(state, terminal),
+        ExternalRequest::EditLifecycle => edit_lifecycle(state, terminal),
+    };
+    let message = result.unwrap_or_else(|e| e.to_string());
+
+    state.status_message = Some(message);
+    state.status_message_time = Some(Instant::now());
+    Ok(())
+}
+
+/// Leave the TUI, run `command` in the foreground, then take the terminal back
+fn run_suspended(terminal: &mut DefaultTerminal, command: &mut Command) -> Result<ExitStatus> {
+    ratatui::restore();
+    let status = command.status();
+    enable_raw_mode()?;
+    execute!(stdout(), EnterAlternateScreen)?;
+    terminal.clear()?;
+    Ok(status?)
+}
+
+/// Start a GUI program in the background, detached from the terminal
+fn spawn_detached(command: &mut Command) -> std::io::Result<()> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // Its own process group keeps Ctrl+C in the terminal from reaching it
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+    let mut child = command.spawn()?;
+    // Wait for it off the main thread so it does not linger as a zombie once it exits
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// `$VISUAL`, then `$EDITOR`, then `vi`, with any arguments they carry (e.g. `code -w`),
+/// quoted as in a shell
+fn editor_command(path: &Path) -> Command {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+
+    let parts = split_shell_words(&editor).unwrap_or_default();
+    let mut parts = parts.iter();
+    let mut command = Command::new(parts.next().map_or("vi", String::as_str));
+    command.args(parts).arg(path);
+    command
+}
+
+/// Refuses gzip and binary bodies, which an editor would corrupt
+pub fn check_editable(body: &[u8]) -> Result<()> {
+    if body.starts_with(&[0x1f, 0x8b]) || body.iter().take(8192).any(|&b| b == 0) {
+        return Err(eyre!("Only uncompressed text objects can be edited"));
+    }
+    Ok(())
+}
+
+/// Download an object, edit it, and upload it back only if it is unchanged in S3 meanwhile
+async fn edit_object(
+    state: &mut AppState,
+    terminal: &mut DefaultTerminal,
+    bucket: &str,
+    key: &str,
+) -> Result<String> {
+    let head = state.s3_client.head_object(bucket, key).await?;
+    if head.content_length().unwrap_or(0) > MAX_EDIT_SIZE {
+        return Err(eyre!("{} is too large to edit", key));
+    }
+    let (original, etag) = state.s3_client.download_object(bucket, key).await?;
+    let etag = etag.ok_or_else(|| eyre!("{} has no ETag to guard the upload", key))?;
+    check_editable(&original)?;
+
+    // Keep the file name so the editor can pick a syntax from the extension
+    let file_name = key
+        .rsplit('/')
+        .next()
+        .filter(|n| !n.is_empty())
+        .unwrap_or("object");
+    let path = create_private_file(&std::env::temp_dir(), file_name, &original)?;
+
+    let status = run_suspended(terminal, &mut editor_command(&path));
+    let edited = match status {
+        Ok(status) if status.success() => fs::read(&path),
+        Ok(status) => {
+            let _ = fs::remove_file(&path);
+            return Ok(format!("Editor exited with {}; nothing uploaded", status));
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&path);
+            return Err(eyre!("Could not run the editor: {}", e));
+        }
+    }?;
+
+    if edited == original {
+        let _ = fs::remove_file(&path);
+        return Ok(format!("No changes to {}", key));
+    }
+
+    state
+        .s3_client
+        .replace_object_body(bucket, key, edited, &etag)
+        .await
+        .map_err(|e| eyre!("{}; your edit is kept at {}", e, path.display()))?;
+    let _ = fs::remove_file(&path);
+
+    let s3_object = &mut state.s3_object;
+    if s3_object.preview_object
+        && s3_object.current_bucket == bucket
+        && s3_object.current_object.as_deref() == Some(key)
+    {
+        s3_object.select_object().await?;
+    }
+    Ok(format!("Uploaded changes to {}", key))
+}
+
+/// Edit the bucket policy as a file and hand the result to the policy view for review
+fn edit_policy(state: &mut AppState, terminal: &mut DefaultTerminal) -> Result<String> {
+    let policy = &mut state.policy;
+    let original = policy.edit_text();
+    let path = create_private_file(
+        &std::env::temp_dir(),
+        &format!("{}-policy.json", policy.bucket),
+        original.as_bytes(),
+    )?;
+
+    let status = run_suspended(terminal, &mut editor_command(&path));
+    let edited = match status {
+        Ok(status) if status.success() => fs::read_to_string(&path),
+        Ok(status) => {
+            let _ = fs::remove_file(&path);
+            return Ok(format!("Editor exited with {}; policy unchanged", status));
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&path);
+            return Err(eyre!("Could not run the editor: {}", e));
+        }
+    };
+    let _ = fs::remove_file(&path);
+
+    let edited = edited?;
+    if edited == original && policy.pending.is_none() && policy.draft.is_none() {
+        return Ok("No changes to the policy".to_string());
+    }
+    Ok(policy.review(edited))
+}
+
+/// Edit the lifecycle rules as a JSON file and load them back into the rule list
+fn edit_lifecycle(state: &mut AppState, terminal: &mut DefaultTerminal) -> Result<String> {
+    let lifecycle = &mut state.lifecycle;
+    let original = lifecycle.edit_text();
+    let path = create_private_file(
+        &std::env::temp_dir(),
+        &format!("{}-lifecycle.json", lifecycle.bucket),
+        original.as_bytes(),
+    )?;
+
+    let status = run_suspended(terminal, &mut editor_command(&path));
+    let edited = match status {
+        Ok(status) if status.success() => fs::read_to_string(&path),
+        Ok(status) => {
+            let _ = fs::remove_file(&path);
+            return Ok(format!(
+                "Editor exited with {}; lifecycle rules unchanged",
+                status
+            ));
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&path);
+            return Err(eyre!("Could not run the editor: {}", e));
+        }
+    };
+    let _ = fs::remove_file(&path);
+
+    let edited = edited?;
+    if edited == original && lifecycle.json_draft.is_none() {
+        return Ok("No changes to the lifecycle rules".to_string());
+    }
+    Ok(lifecycle.apply_json(edited))
+}
+
+/// Bytes of an open-with download so far
+#[derive(Debug, Default)]
+struct DownloadProgress(AtomicU64);
+
+impl TransferProgress for DownloadProgress {
+    async fn advance(&self, bytes: u64) -> Result<()> {
+        self.0.fetch_add(bytes, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Stream an object to `path`, drawing how much has arrived while it downloads
+async fn download_with_progress(
+    state: &AppState,
+    terminal: &mut DefaultTerminal,
+    bucket: &str,
+    key: &str,
+    path: &Path,
+    size: i64,
+) -> Result<()> {
+    let progress = DownloadProgress::default();
+    let download = state
+        .s3_client
+        .download_to_file(bucket, key, path, &progress);
+    tokio::pin!(download);
+    let mut redraw = tokio::time::interval(Duration::from_millis(200));
+    loop {
+        tokio::select! {
+            result = &mut download => return result.map(|_| ()),
+            _ = redraw.tick() => {
+                let line = format!(
+                    "Downloading {}: {} of {}",
+                    key,
+                    format_size(progress.0.load(Ordering::Relaxed) as i64),
+                    format_size(size)
+                );
+                terminal.draw(|frame| {
+                    let area = frame.area();
+                    let row = Rect::new(area.x, area.y + area.height / 2, area.width, 1);
+                    frame.render_widget(Paragraph::new(line).centered(), row);
+                })?;
+            }
+        }
+    }
+}
+
+/// Download an object to the cache directory and open it with the first matching rule
+async fn open_with(
+    state: &mut AppState,
+    terminal: &mut DefaultTerminal,
+    bucket: &str,
+    key: &str,
+) -> Result<String> {
+    let head = state.s3_client.head_object(bucket, key).await?;
+    let rule = state
+        .config
+        .open_with_rule(key, head.content_type())
+        .cloned()
+        .ok_or_else(|| {
+            eyre!(
+                "No open-with rule matches {}; add one to {}",
+                key,
+                Config::path().map_or("the config file".to_string(), |p| p.display().to_string())
+            )
+        })?;
+
+    // The whole key keeps objects with the same name in different folders apart
+    let dir = dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("aws_tui_toolkit")
+        .join(bucket);
+    let path =
+        local_path_for(&dir, key).ok_or_else(|| eyre!("{} is not a safe local path", key))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let size = head.content_length().unwrap_or(0);
+    download_with_progress(state, terminal, bucket, key, &path, size).await?;
+
+    let command_line = rule
+        .command_line(&path.to_string_lossy())
+        .map_err(|e| eyre!(e))?;
+    let Some((program, args)) = command_line.split_first() else {
+        return Err(eyre!("The open-with rule for {} has an empty command", key));
+    };
+    let mut command = Command::new(program);
+    command.args(args);
+
+    if rule.terminal {
+        let status = run_suspended(terminal, &mut command)
+            .map_err(|e| eyre!("Could not run {}: {}", program, e))?;
+        Ok(format!("{} exited with {}", program, status))
+    } else {
+        spawn_detached(&mut command).map_err(|e| eyre!("Could not start {}: {}", program, e))?;
+        Ok(format!("Opened {} with {}", key, program))
+    }
+}
@@ -0,0 +1,24 @@
+use crate::app::external::check_editable;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_is_editable() {
+        assert!(check_editable(b"name,size\nreport.csv,12\n").is_ok());
+        assert!(check_editable("héllo wörld".as_bytes()).is_ok());
+        assert!(check_editable(b"").is_ok());
+    }
+
+    #[test]
+    fn test_gzip_and_binary_are_refused() {
+        assert!(check_editable(&[0x1f, 0x8b, 0x08, 0x00]).is_err());
+        assert!(check_editable(b"PK\x03\x04\x00\x00binary").is_err());
+
+        // Only the start of the body is sniffed for NUL bytes
+        let mut late_nul = vec![b'a'; 8192];
+        late_nul.push(0);
+        assert!(check_editable(&late_nul).is_ok());
+    }
+}
@@ -1,13 +1,57 @@
 use crate::app::AppMode;
+use crate::app::external::ExternalRequest;
+use crate::app::state::archive::ArchiveState;
+use crate::app::state::bucket_admin::BucketAdminState;
+use crate::app::state::download::DownloadState;
+use crate::app::state::global_search::GlobalSearchState;
+use crate::app::state::grep::GrepState;
+use crate::app::state::jobs::JobsState;
+use crate::app::state::lifecycle::LifecycleState;
+use crate::app::state::metadata::MetadataState;
+use crate::app::state::policy::PolicyState;
+use crate::app::state::rename::RenameState;
 use crate::app::state::s3_bucket::S3BucketState;
 use crate::app::state::s3_object::S3ObjectState;
+use crate::app::state::safety::SafetyState;
+use crate::app::state::share::ShareState;
+use crate::app::state::sync::SyncState;
+use crate::app::state::tags::TagsState;
+use crate::app::state::usage::UsageState;
+use crate::app::state::verify::VerifyState;
 use crate::aws::AWS;
 use crate::aws::s3_client::S3Client;
+use crate::config::Config;
 use std::sync::Arc;
 use std::time::Instant;
 
+pub mod archive;
+pub mod bucket_admin;
+pub mod download;
+mod global_search;
+mod grep;
+#[cfg(test)]
+mod grep_test;
+pub mod jobs;
+#[cfg(test)]
+mod jobs_test;
+pub mod lifecycle;
+pub mod metadata;
+#[cfg(test)]
+mod metadata_test;
+pub mod policy;
+pub mod rename;
+#[cfg(test)]
+mod rename_test;
 mod s3_bucket;
 mod s3_object;
+pub mod safety;
+pub mod share;
+pub mod sync;
+pub mod tags;
+#[cfg(test)]
+mod tags_test;
+pub mod usage;
+pub mod verify;
 
 #[derive(Debug)]
 pub enum LoadingState {
@@ -18,32 +62,71 @@ pub enum LoadingState {
 }
 
 #[derive(Debug)]
-struct NotificationMessage {
-    status_message: String,
-    status_message_time: Instant,
+pub struct NotificationMessage {
+    pub status_message: String,
+    pub status_message_time: Instant,
 }
 
 /// Represents the complete state of the application
 #[derive(Debug)]
 pub struct AppState {
     pub aws_config: AWS,
+    pub config: Config,
     pub mode: AppMode,
     pub s3_client: Arc<S3Client>,
     pub s3_bucket: S3BucketState,
     pub s3_object: S3ObjectState,
+    pub global_search: GlobalSearchState,
+    pub grep: GrepState,
+    pub jobs: JobsState,
+    pub rename: RenameState,
+    pub tags: TagsState,
+    pub metadata: MetadataState,
+    pub share: ShareState,
+    pub download: DownloadState,
+    pub sync: SyncState,
+    pub verify: VerifyState,
+    pub bucket_admin: BucketAdminState,
+    pub policy: PolicyState,
+    pub lifecycle: LifecycleState,
+    pub usage: UsageState,
+    pub archive: ArchiveState,
+    pub safety: SafetyState,
+    /// Set by an action that needs to suspend the TUI
+    pub external_request: Option<ExternalRequest>,
     pub status_message: Option<String>,
     pub status_message_time: Option<Instant>,
     pub exit: bool,
 }
 
 impl AppState {
-    pub fn new(aws_config: AWS, s3_client: Arc<S3Client>) -> Self {
+    pub fn new(aws_config: AWS, config: Config, s3_client: Arc<S3Client>, read_only: bool) -> Self {
+        let profile = AWS::profile_name();
+        let safety = config.safety(&profile, read_only);
         Self {
             aws_config,
+            config,
             mode: AppMode::BucketList,
             status_message: None,
+            external_request: None,
             s3_bucket: S3BucketState::new(s3_client.clone()),
             s3_object: S3ObjectState::new(s3_client.clone()),
+            global_search: GlobalSearchState::new(s3_client.clone()),
+            grep: GrepState::new(s3_client.clone()),
+            jobs: JobsState::new(s3_client.clone()),
+            rename: RenameState::new(s3_client.clone()),
+            tags: TagsState::new(s3_client.clone()),
+            metadata: MetadataState::new(s3_client.clone()),
+            share: ShareState::new(s3_client.clone()),
+            download: DownloadState::new(s3_client.clone()),
+            sync: SyncState::new(s3_client.clone()),
+            verify: VerifyState::new(s3_client.clone()),
+            bucket_admin: BucketAdminState::new(s3_client.clone()),
+            policy: PolicyState::new(s3_client.clone()),
+            lifecycle: LifecycleState::new(s3_client.clone()),
+            usage: UsageState::new(s3_client.clone()),
+            archive: ArchiveState::new(s3_client.clone()),
+            safety: SafetyState::new(safety, profile),
             s3_client,
             exit: false,
             status_message_time: None,
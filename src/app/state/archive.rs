@@ -0,0 +1,258 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::app::state::jobs::JobsState;
+use crate::app::state::s3_object::S3ObjectState;
+use crate::archive::{
+    RestoreRequest, RestoreStatus, STORAGE_CLASSES, is_archived, parse_restore_days,
+};
+use crate::aws::s3_client::S3Client;
+use crate::search::SearchBar;
+use crate::ui::ScrollableList;
+use std::sync::Arc;
+
+/// Rows of the storage class dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveField {
+    Days,
+    Tier,
+    Restore,
+    StorageClass,
+    ChangeClass,
+}
+
+impl ArchiveField {
+    const ALL: [ArchiveField; 5] = [
+        ArchiveField::Days,
+        ArchiveField::Tier,
+        ArchiveField::Restore,
+        ArchiveField::StorageClass,
+        ArchiveField::ChangeClass,
+    ];
+}
+
+/// Dialog for restoring archived objects and moving objects to another storage class
+#[derive(Debug)]
+pub struct ArchiveState {
+    pub fields: ScrollableList<ArchiveField>,
+    /// Edits the number of days a restored copy is kept
+    pub prompt: SearchBar,
+    pub bucket: String,
+    /// The keys the dialog acts on, with their storage class from the listing
+    pub objects: Vec<(String, Option<String>)>,
+    pub request: RestoreRequest,
+    /// Index into `STORAGE_CLASSES` of the class to change to
+    pub class_index: usize,
+    s3_client: Arc<S3Client>,
+}
+
+impl ArchiveState {
+    pub fn new(s3_client: Arc<S3Client>) -> Self {
+        Self {
+            fields: ScrollableList::new("Storage Class"),
+            prompt: SearchBar::default(),
+            bucket: String::new(),
+            objects: Vec::new(),
+            request: RestoreRequest::default(),
+            class_index: 0,
+            s3_client,
+        }
+    }
+
+    /// Open the dialog for `objects`, reading the class and restore status of a single one
+    pub async fn open(&mut self, bucket: &str, objects: Vec<(String, Option<String>)>) {
+        self.bucket = bucket.to_string();
+        self.objects = objects;
+        self.request = RestoreRequest::default();
+        self.prompt.active = false;
+        self.prompt.clear();
+
+        let title = match self.objects.as_slice() {
+            [(key, _)] => format!("Storage Class: {}", key),
+            objects => format!("Storage Class: {} objects", objects.len()),
+        };
+        self.fields = ScrollableList::new(title);
+        self.fields.append_items(ArchiveField::ALL.to_vec());
+        self.fields.filtered_indices = (0..ArchiveField::ALL.len()).collect();
+        self.fields.set_has_more(false);
+        self.fields.first();
+
+        self.fields.summary = Some(match self.objects.clone().as_slice() {
+            [(key, _)] => match self.s3_client.head_object(bucket, key).await {
+                Ok(head) => {
+                    let class = head.storage_class().map(|class| class.as_str());
+                    self.objects[0].1 = class.map(String::from);
+                    if is_archived(class) {
+                        format!(
+                            "{}, {}",
+                            class.unwrap_or_default(),
+                            RestoreStatus::parse(head.restore())
+                        )
+                    } else {
+                        class.unwrap_or("STANDARD").to_string()
+                    }
+                }
+                Err(e) => e.to_string(),
+            },
+            objects => format!(
+                "{} objects, {} archived",
+                objects.len(),
+                self.archived_keys().len()
+            ),
+        });
+
+        let current = match self.objects.as_slice() {
+            [(_, class)] => class.as_deref().unwrap_or("STANDARD"),
+            _ => "STANDARD",
+        };
+        self.class_index = STORAGE_CLASSES
+            .iter()
+            .position(|class| *class == current)
+            .unwrap_or(0);
+    }
+
+    fn archived_keys(&self) -> Vec<String> {
+        self.objects
+            .iter()
+            .filter(|(_, class)| is_archived(class.as_deref()))
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// The slowest archive class among the objects, which sets the restore time
+    fn archive_class(&self) -> &str {
+        let deep = self
+            .objects
+            .iter()
+            .any(|(_, class)| class.as_deref() == Some("DEEP_ARCHIVE"));
+        if deep { "DEEP_ARCHIVE" } else { "GLACIER" }
+    }
+
+    pub fn label(&self, field: ArchiveField) -> String {
+        match field {
+            ArchiveField::Days => format!("Keep the restored copy for: {} days", self.request.days),
+            ArchiveField::Tier => format!(
+                "Retrieval tier: {} ({})",
+                self.request.tier,
+                self.request.tier.typical_time(self.archive_class())
+            ),
+            ArchiveField::Restore => {
+                format!("Restore {} archived object(s)", self.archived_keys().len())
+            }
+            ArchiveField::StorageClass => {
+                format!("New storage class: {}", STORAGE_CLASSES[self.class_index])
+            }
+            ArchiveField::ChangeClass => format!(
+                "Change the storage class of {} object(s) (self-copy)",
+                self.objects.len()
+            ),
+        }
+    }
+
+    fn restore(&mut self, s3_object: &mut S3ObjectState, jobs: &mut JobsState) -> bool {
+        let keys = self.archived_keys();
+        if keys.is_empty() {
+            self.fields.summary =
+                Some("None of the objects is in GLACIER or DEEP_ARCHIVE".to_string());
+            return false;
+        }
+        if let Err(e) = self.request.tier.check(self.archive_class()) {
+            self.fields.summary = Some(e);
+            return false;
+        }
+        s3_object.update_status(format!(
+            "Requesting a restore of {} object(s) (J: Jobs, i: Info shows the status)",
+            keys.len()
+        ));
+        jobs.spawn_restore(self.bucket.clone(), keys, self.request);
+        true
+    }
+
+    fn change_class(&mut self, s3_object: &mut S3ObjectState, jobs: &mut JobsState) -> bool {
+        let class = STORAGE_CLASSES[self.class_index];
+        let keys: Vec<String> = self
+            .objects
+            .iter()
+            .filter(|(_, current)| current.as_deref().unwrap_or("STANDARD") != class)
+            .map(|(key, _)| key.clone())
+            .collect();
+        if keys.is_empty() {
+            self.fields.summary = Some(format!("The objects are already in {}", class));
+            return false;
+        }
+        s3_object.update_status(format!(
+            "Changing {} object(s) to {} (J: Jobs)",
+            keys.len(),
+            class
+        ));
+        jobs.spawn_storage_class(self.bucket.clone(), keys, class.to_string());
+        true
+    }
+
+    pub fn handle_action(
+        &mut self,
+        action: AppActions,
+        app_mode: &mut AppMode,
+        s3_object: &mut S3ObjectState,
+        jobs: &mut JobsState,
+    ) {
+        if self.prompt.active {
+            match action {
+                AppActions::SearchInput(c) => {
+                    self.prompt.input(c);
+                    self.prompt.error = None;
+                }
+                AppActions::SearchDelete => {
+                    self.prompt.delete();
+                    self.prompt.error = None;
+                }
+                AppActions::Enter => match parse_restore_days(&self.prompt.query) {
+                    Ok(days) => {
+                        self.request.days = days;
+                        self.prompt.active = false;
+                        self.prompt.clear();
+                    }
+                    Err(e) => self.prompt.error = Some(e),
+                },
+                AppActions::GoBack => {
+                    self.prompt.active = false;
+                    self.prompt.clear();
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match action {
+            AppActions::MoveDown => self.fields.next(),
+            AppActions::MoveUp => self.fields.previous(),
+            AppActions::MoveToTop => self.fields.first(),
+            AppActions::MoveToBottom => self.fields.last(),
+            AppActions::Enter => {
+                let done = match self.fields.selected_item().copied() {
+                    Some(ArchiveField::Days) => {
+                        self.prompt.set_query(&self.request.days.to_string());
+                        self.prompt.error = None;
+                        self.prompt.active = true;
+                        false
+                    }
+                    Some(ArchiveField::Tier) => {
+                        self.request.tier = self.request.tier.next();
+                        false
+                    }
+                    Some(ArchiveField::Restore) => self.restore(s3_object, jobs),
+                    Some(ArchiveField::StorageClass) => {
+                        self.class_index = (self.class_index + 1) % STORAGE_CLASSES.len();
+                        false
+                    }
+                    Some(ArchiveField::ChangeClass) => self.change_class(s3_object, jobs),
+                    None => false,
+                };
+                if done {
+                    *app_mode = AppMode::ObjectList;
+                }
+            }
+            AppActions::GoBack => *app_mode = AppMode::ObjectList,
+            _ => {}
+        }
+    }
+}
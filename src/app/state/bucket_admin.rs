@@ -0,0 +1,380 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::app::state::NotificationMessage;
+use crate::app::state::jobs::JobsState;
+use crate::app::state::s3_bucket::S3BucketState;
+use crate::aws::s3_client::S3Client;
+use crate::bucket::{BucketSettings, Encryption, REGIONS, validate_bucket_name};
+use crate::search::SearchBar;
+use crate::ui::ScrollableList;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Rows of the create and delete bucket dialogs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BucketField {
+    Name,
+    Region,
+    Ownership,
+    Versioning,
+    Encryption,
+    BlockPublicAccess,
+    Create,
+    EmptyFirst,
+    Delete,
+}
+
+/// What the prompt is editing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PromptFor {
+    Name,
+    Encryption,
+    /// The bucket name, typed again before creating or deleting it
+    Confirm,
+}
+
+/// Dialogs for creating a bucket with its settings, and for deleting one, optionally
+/// emptying it first
+#[derive(Debug)]
+pub struct BucketAdminState {
+    pub fields: ScrollableList<BucketField>,
+    /// Shown instead of the fields while a region is picked
+    pub regions: ScrollableList<&'static str>,
+    pub picking_region: bool,
+    pub prompt: SearchBar,
+    pub settings: BucketSettings,
+    /// The bucket the delete dialog is for
+    pub delete_bucket: Option<String>,
+    /// Whether the bucket to delete has no objects, versions or delete markers
+    pub is_empty: bool,
+    pub empty_first: bool,
+    pub notification: Option<NotificationMessage>,
+    prompt_for: PromptFor,
+    s3_client: Arc<S3Client>,
+}
+
+impl BucketAdminState {
+    pub fn new(s3_client: Arc<S3Client>) -> Self {
+        Self {
+            fields: ScrollableList::new("Create Bucket"),
+            regions: ScrollableList::new("Region"),
+            picking_region: false,
+            prompt: SearchBar::default(),
+            settings: BucketSettings::new("us-east-1"),
+            delete_bucket: None,
+            is_empty: true,
+            empty_first: false,
+            notification: None,
+            prompt_for: PromptFor::Name,
+            s3_client,
+        }
+    }
+
+    fn show_fields(&mut self, title: String, fields: Vec<BucketField>) {
+        self.fields = ScrollableList::new(title);
+        self.fields.filtered_indices = (0..fields.len()).collect();
+        self.fields.append_items(fields);
+        self.fields.set_has_more(false);
+        self.fields.first();
+        self.picking_region = false;
+    }
+
+    /// Open the create dialog with the console's defaults in the configured region,
+    /// starting with the name
+    pub fn open_create(&mut self) {
+        let region = self
+            .s3_client
+            .region()
+            .unwrap_or_else(|| "us-east-1".to_string());
+        self.settings = BucketSettings::new(&region);
+        self.delete_bucket = None;
+        self.show_fields(
+            "Create Bucket".to_string(),
+            vec![
+                BucketField::Name,
+                BucketField::Region,
+                BucketField::Ownership,
+                BucketField::Versioning,
+                BucketField::Encryption,
+                BucketField::BlockPublicAccess,
+                BucketField::Create,
+            ],
+        );
+        self.start_prompt(PromptFor::Name, "");
+    }
+
+    /// Open the delete dialog for `bucket`, offering to empty it if it has anything in it
+    pub async fn open_delete(&mut self, bucket: &str) {
+        self.delete_bucket = Some(bucket.to_string());
+        self.empty_first = false;
+        self.prompt.active = false;
+        self.prompt.clear();
+
+        let (is_empty, summary) = match self.s3_client.bucket_is_empty(bucket).await {
+            Ok(true) => (true, "The bucket is empty".to_string()),
+            Ok(false) => (
+                false,
+                "The bucket has objects or versions; S3 only deletes empty buckets".to_string(),
+            ),
+            Err(e) => (false, e.to_string()),
+        };
+        self.is_empty = is_empty;
+        let fields = if is_empty {
+            vec![BucketField::Delete]
+        } else {
+            vec![BucketField::EmptyFirst, BucketField::Delete]
+        };
+        self.show_fields(format!("Delete Bucket {}", bucket), fields);
+        self.fields.summary = Some(summary);
+    }
+
+    pub fn label(&self, field: BucketField) -> String {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        let settings = &self.settings;
+        match field {
+            BucketField::Name if settings.name.is_empty() => "Name: (not set)".to_string(),
+            BucketField::Name => format!("Name: {}", settings.name),
+            BucketField::Region => format!("Region: {}", settings.region),
+            BucketField::Ownership => format!("Object ownership: {}", settings.ownership),
+            BucketField::Versioning => format!("Versioning: {}", on_off(settings.versioning)),
+            BucketField::Encryption => format!("Default encryption: {}", settings.encryption),
+            BucketField::BlockPublicAccess => format!(
+                "Block all public access: {}",
+                on_off(settings.block_public_access)
+            ),
+            BucketField::Create => "Create bucket".to_string(),
+            BucketField::EmptyFirst => format!(
+                "Empty it first (deletes every object and version): {}",
+                on_off(self.empty_first)
+            ),
+            BucketField::Delete => "Delete bucket".to_string(),
+        }
+    }
+
+    pub fn prompt_help(&self) -> String {
+        match self.prompt_for {
+            PromptFor::Name => {
+                "Type the bucket name: 3-63 lowercase letters, digits, '.' and '-'  Enter: Apply  Esc: Cancel"
+                    .to_string()
+            }
+            PromptFor::Encryption => {
+                "Type sse-s3, kms (the aws/s3 key) or kms:KEY_ID  Enter: Apply  Esc: Cancel"
+                    .to_string()
+            }
+            PromptFor::Confirm => format!(
+                "Type {} to confirm  Enter: {}  Esc: Cancel",
+                self.confirm_name(),
+                if self.delete_bucket.is_some() {
+                    "Delete"
+                } else {
+                    "Create"
+                }
+            ),
+        }
+    }
+
+    fn confirm_name(&self) -> &str {
+        self.delete_bucket.as_deref().unwrap_or(&self.settings.name)
+    }
+
+    fn start_prompt(&mut self, prompt_for: PromptFor, initial: &str) {
+        self.prompt_for = prompt_for;
+        self.prompt.set_query(initial);
+        self.prompt.error = None;
+        self.prompt.active = true;
+    }
+
+    fn notify(&mut self, message: String) {
+        self.notification = Some(NotificationMessage {
+            status_message: message,
+            status_message_time: Instant::now(),
+        });
+    }
+
+    fn open_region_picker(&mut self) {
+        self.regions = ScrollableList::new("Region");
+        self.regions.append_items(REGIONS.to_vec());
+        self.regions.filtered_indices = (0..REGIONS.len()).collect();
+        self.regions.set_has_more(false);
+        self.regions.first();
+        if let Some(index) = REGIONS.iter().position(|r| *r == self.settings.region) {
+            self.regions.select(Some(index));
+        }
+        self.picking_region = true;
+    }
+
+    fn select(&mut self, field: BucketField) {
+        let settings = &mut self.settings;
+        match field {
+            BucketField::Name => {
+                let name = settings.name.clone();
+                self.start_prompt(PromptFor::Name, &name);
+            }
+            BucketField::Region => self.open_region_picker(),
+            BucketField::Ownership => settings.ownership = settings.ownership.next(),
+            BucketField::Versioning => settings.versioning = !settings.versioning,
+            BucketField::Encryption => {
+                let current = match &settings.encryption {
+                    Encryption::S3 => "sse-s3".to_string(),
+                    Encryption::Kms(None) => "kms".to_string(),
+                    Encryption::Kms(Some(key)) => format!("kms:{}", key),
+                };
+                self.start_prompt(PromptFor::Encryption, &current);
+            }
+            BucketField::BlockPublicAccess => {
+                settings.block_public_access = !settings.block_public_access
+            }
+            BucketField::Create => match validate_bucket_name(&settings.name) {
+                Ok(()) => self.start_prompt(PromptFor::Confirm, ""),
+                Err(e) => self.fields.summary = Some(format!("Bucket name {}", e)),
+            },
+            BucketField::EmptyFirst => self.empty_first = !self.empty_first,
+            BucketField::Delete => self.start_prompt(PromptFor::Confirm, ""),
+        }
+    }
+
+    async fn commit_prompt(
+        &mut self,
+        app_mode: &mut AppMode,
+        s3_bucket: &mut S3BucketState,
+        jobs: &mut JobsState,
+    ) -> color_eyre::Result<()> {
+        let input = self.prompt.query.trim().to_string();
+        match self.prompt_for {
+            PromptFor::Name => {
+                if let Err(e) = validate_bucket_name(&input) {
+                    self.prompt.error = Some(e);
+                    return Ok(());
+                }
+                self.settings.name = input;
+                self.fields.summary = None;
+            }
+            PromptFor::Encryption => match Encryption::parse(&input) {
+                Ok(encryption) => self.settings.encryption = encryption,
+                Err(e) => {
+                    self.prompt.error = Some(e);
+                    return Ok(());
+                }
+            },
+            PromptFor::Confirm => {
+                if input != self.confirm_name() {
+                    self.prompt.error = Some(format!("type {} exactly", self.confirm_name()));
+                    return Ok(());
+                }
+                self.prompt.active = false;
+                self.prompt.clear();
+                return match self.delete_bucket.clone() {
+                    Some(bucket) => self.delete(bucket, app_mode, s3_bucket, jobs).await,
+                    None => self.create(app_mode, s3_bucket).await,
+                };
+            }
+        }
+        self.prompt.active = false;
+        self.prompt.clear();
+        Ok(())
+    }
+
+    async fn create(
+        &mut self,
+        app_mode: &mut AppMode,
+        s3_bucket: &mut S3BucketState,
+    ) -> color_eyre::Result<()> {
+        if let Err(e) = self.s3_client.create_bucket(&self.settings).await {
+            self.fields.summary = Some(e.to_string());
+            return Ok(());
+        }
+        self.notify(format!(
+            "Created bucket {} in {}",
+            self.settings.name, self.settings.region
+        ));
+        *app_mode = AppMode::BucketList;
+        s3_bucket.reload_buckets().await
+    }
+
+    async fn delete(
+        &mut self,
+        bucket: String,
+        app_mode: &mut AppMode,
+        s3_bucket: &mut S3BucketState,
+        jobs: &mut JobsState,
+    ) -> color_eyre::Result<()> {
+        if self.empty_first && !self.is_empty {
+            self.notify(format!(
+                "Emptying and deleting {} in the background (J: Jobs)",
+                bucket
+            ));
+            jobs.spawn_delete_bucket(bucket);
+            *app_mode = AppMode::BucketList;
+            return Ok(());
+        }
+
+        if let Err(e) = self.s3_client.delete_bucket(&bucket).await {
+            self.fields.summary = Some(e.to_string());
+            return Ok(());
+        }
+        self.notify(format!("Deleted bucket {}", bucket));
+        *app_mode = AppMode::BucketList;
+        s3_bucket.reload_buckets().await
+    }
+
+    pub async fn handle_action(
+        &mut self,
+        action: AppActions,
+        app_mode: &mut AppMode,
+        s3_bucket: &mut S3BucketState,
+        jobs: &mut JobsState,
+    ) -> color_eyre::Result<()> {
+        if self.prompt.active {
+            match action {
+                AppActions::SearchInput(c) => {
+                    self.prompt.input(c);
+                    self.prompt.error = None;
+                }
+                AppActions::SearchDelete => {
+                    self.prompt.delete();
+                    self.prompt.error = None;
+                }
+                AppActions::Enter => self.commit_prompt(app_mode, s3_bucket, jobs).await?,
+                AppActions::GoBack => {
+                    self.prompt.active = false;
+                    self.prompt.clear();
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        if self.picking_region {
+            match action {
+                AppActions::MoveDown => self.regions.next(),
+                AppActions::MoveUp => self.regions.previous(),
+                AppActions::MoveToTop => self.regions.first(),
+                AppActions::MoveToBottom => self.regions.last(),
+                AppActions::Enter => {
+                    if let Some(region) = self.regions.selected_item() {
+                        self.settings.region = region.to_string();
+                    }
+                    self.picking_region = false;
+                }
+                AppActions::GoBack => self.picking_region = false,
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match action {
+            AppActions::MoveDown => self.fields.next(),
+            AppActions::MoveUp => self.fields.previous(),
+            AppActions::MoveToTop => self.fields.first(),
+            AppActions::MoveToBottom => self.fields.last(),
+            AppActions::Enter => {
+                if let Some(field) = self.fields.selected_item().copied() {
+                    self.select(field);
+                }
+            }
+            AppActions::GoBack => *app_mode = AppMode::BucketList,
+            _ => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::app::state::jobs::JobsState;
+use crate::aws::s3_client::{S3Client, list_error_reason};
+use crate::config::RecentDirs;
+use crate::search::SearchBar;
+use crate::ui::ScrollableList;
+use crate::util::{complete_dir_path, expand_tilde, local_path_for};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// What is being downloaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadSource {
+    /// One object, saved into the destination directory or to the typed file path
+    Key(String),
+    /// Marked keys, laid out relative to the folder they were marked in
+    Keys { keys: Vec<String>, folder: String },
+    /// Every key under a prefix, laid out relative to the prefix's folder
+    Prefix(String),
+}
+
+/// What to do when a local file already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    Skip,
+    Overwrite,
+    /// Save next to it as `name (1).ext`
+    Rename,
+}
+
+impl ConflictPolicy {
+    fn next(self) -> Self {
+        match self {
+            Self::Skip => Self::Overwrite,
+            Self::Overwrite => Self::Rename,
+            Self::Rename => Self::Skip,
+        }
+    }
+
+    fn previous(self) -> Self {
+        self.next().next()
+    }
+}
+
+impl fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Skip => write!(f, "skip"),
+            Self::Overwrite => write!(f, "overwrite"),
+            Self::Rename => write!(f, "keep both"),
+        }
+    }
+}
+
+/// One object to download and where it goes
+#[derive(Debug, Clone)]
+pub struct DownloadItem {
+    pub key: String,
+    pub path: PathBuf,
+    /// Object size, when it was listed
+    pub size: Option<u64>,
+}
+
+/// One line of the download plan
+#[derive(Debug, Clone)]
+pub struct DownloadEntry {
+    pub key: String,
+    /// `None` when the key cannot be mapped to a file inside the destination
+    pub path: Option<PathBuf>,
+    pub exists: bool,
+    pub size: Option<u64>,
+}
+
+/// Destination prompt and plan for downloading many objects as a background job
+#[derive(Debug)]
+pub struct DownloadState {
+    pub prompt: SearchBar,
+    pub bucket: String,
+    pub source: Option<DownloadSource>,
+    pub plan: ScrollableList<DownloadEntry>,
+    pub policy: ConflictPolicy,
+    recent_dirs: RecentDirs,
+    s3_client: Arc<S3Client>,
+}
+
+impl DownloadState {
+    pub fn new(s3_client: Arc<S3Client>) -> Self {
+        Self {
+            prompt: SearchBar::default(),
+            bucket: String::new(),
+            source: None,
+            plan: ScrollableList::new("Download Plan"),
+            policy: ConflictPolicy::Skip,
+            recent_dirs: RecentDirs::load(),
+            s3_client,
+        }
+    }
+
+    /// Open the destination prompt, pre-filled with the directory last used for this bucket.
+    /// Otherwise single objects go to `download_dir`, and many to a folder named after the
+    /// bucket inside it.
+    pub fn open(&mut self, bucket: &str, source: DownloadSource, download_dir: &Path) {
+        let default_dir = match (self.recent_dirs.get(bucket), &source) {
+            (Some(dir), _) => dir.to_path_buf(),
+            (None, DownloadSource::Key(_)) => download_dir.to_path_buf(),
+            (None, _) => download_dir.join(bucket),
+        };
+        let mut default_dir = default_dir.to_string_lossy().to_string();
+        if !default_dir.ends_with('/') {
+            default_dir.push('/');
+        }
+
+        self.bucket = bucket.to_string();
+        self.source = Some(source);
+        self.prompt.active = true;
+        self.prompt.set_query(&default_dir);
+        self.prompt.error = None;
+        self.policy = ConflictPolicy::Skip;
+        self.plan = ScrollableList::new("Download Plan");
+        self.plan.set_has_more(false);
+    }
+
+    /// The directory last downloaded to from `bucket`
+    pub fn recent_dir(&self, bucket: &str) -> Option<&Path> {
+        self.recent_dirs.get(bucket)
+    }
+
+    fn destination(&self) -> PathBuf {
+        expand_tilde(self.prompt.query.trim())
+    }
+
+    /// List the keys to download and where each one would be written
+    async fn build_plan(&mut self) -> color_eyre::Result<()> {
+        let Some(source) = self.source.clone() else {
+            return Ok(());
+        };
+        let destination = self.destination();
+
+        let (keys, folder): (Vec<(String, Option<u64>)>, String) = match source {
+            DownloadSource::Key(key) => (vec![(key, None)], String::new()),
+            DownloadSource::Keys { keys, folder } => {
+                (keys.into_iter().map(|key| (key, None)).collect(), folder)
+            }
+            DownloadSource::Prefix(prefix) => {
+                let folder = prefix.rfind('/').map_or("", |end| &prefix[..=end]);
+                let objects = self
+                    .s3_client
+                    .list_all_objects(&self.bucket, &prefix)
+                    .await?;
+                let keys = objects
+                    .iter()
+                    .filter_map(|object| {
+                        let size = object.size().map(|size| size as u64);
+                        object.key().map(|key| (key.to_string(), size))
+                    })
+                    .collect();
+                (keys, folder.to_string())
+            }
+        };
+
+        let entries: Vec<DownloadEntry> = keys
+            .into_iter()
+            // Zero-byte "folder" markers become directories anyway
+            .filter(|(key, _)| !key.ends_with('/'))
+            .map(|(key, size)| {
+                let relative = key
+                    .strip_prefix(&folder)
+                    .unwrap_or_else(|| key.rsplit_once('/').map_or(key.as_str(), |(_, name)| name));
+                let path = local_path_for(&destination, relative);
+                DownloadEntry {
+                    exists: path.as_ref().is_some_and(|path| path.exists()),
+                    path,
+                    key,
+                    size,
+                }
+            })
+            .collect();
+
+        self.plan = ScrollableList::new(format!(
+            "Download {} object(s) from {} to {}",
+            entries.len(),
+            self.bucket,
+            destination.display()
+        ));
+        self.plan.set_has_more(false);
+        self.plan.append_items(entries);
+        self.plan.filtered_indices = (0..self.plan.items.len()).collect();
+        self.plan.first();
+        self.update_summary();
+        Ok(())
+    }
+
+    /// Save one object straight away, keeping both files if the name is taken
+    fn start_single(&mut self, key: &str, jobs: &mut JobsState) {
+        let destination = self.destination();
+        let file_name = key.rsplit('/').next().unwrap_or(key);
+        // A directory keeps the object's name; anything else is the file to write
+        let path = if destination.is_dir() || self.prompt.query.trim().ends_with('/') {
+            destination.join(file_name)
+        } else {
+            destination
+        };
+        if file_name.is_empty() {
+            self.prompt.error = Some("folder markers have no content to download".to_string());
+            return;
+        }
+
+        if let Some(dir) = path.parent() {
+            self.recent_dirs.remember(&self.bucket, dir);
+        }
+        let description = format!("Download {} to {}", key, path.display());
+        let item = DownloadItem {
+            key: key.to_string(),
+            path,
+            size: None,
+        };
+        jobs.spawn_download(
+            description,
+            self.bucket.clone(),
+            vec![item],
+            ConflictPolicy::Rename,
+        );
+        self.prompt.active = false;
+        self.source = None;
+    }
+
+    /// Complete the directory being typed, listing the candidates when it is ambiguous
+    fn complete(&mut self) {
+        let (completed, candidates) = complete_dir_path(&self.prompt.query);
+        self.prompt.set_query(&completed);
+        self.prompt.error = None;
+        self.plan.summary = (!candidates.is_empty()).then(|| candidates.join("  "));
+    }
+
+    fn update_summary(&mut self) {
+        let unsafe_keys = self.plan.items.iter().filter(|e| e.path.is_none()).count();
+        let existing = self.plan.items.iter().filter(|e| e.exists).count();
+        self.plan.summary = Some(format!(
+            "{} exist locally ({}), {} skipped as unsafe paths. h/l: Existing Files  Enter: Download  Esc: Edit",
+            existing, self.policy, unsafe_keys
+        ));
+    }
+
+    pub async fn handle_action(
+        &mut self,
+        action: AppActions,
+        app_mode: &mut AppMode,
+        jobs: &mut JobsState,
+    ) -> color_eyre::Result<()> {
+        if self.prompt.active {
+            match action {
+                AppActions::SearchInput(c) => {
+                    self.prompt.input(c);
+                    self.prompt.error = None;
+                }
+                AppActions::SearchDelete => {
+                    self.prompt.delete();
+                    self.prompt.error = None;
+                }
+                AppActions::Complete => self.complete(),
+                AppActions::Enter => {
+                    let destination = self.destination();
+                    if self.prompt.query.trim().is_empty() {
+                        self.prompt.error = Some("enter a directory".to_string());
+                    } else if let Some(DownloadSource::Key(key)) = self.source.clone() {
+                        self.start_single(&key, jobs);
+                        if self.source.is_none() {
+                            *app_mode = AppMode::ObjectList;
+                        }
+                    } else if destination.exists() && !destination.is_dir() {
+                        self.prompt.error = Some("not a directory".to_string());
+                    } else {
+                        self.prompt.active = false;
+                        if let Err(e) = self.build_plan().await {
+                            self.prompt.active = true;
+                            self.prompt.error = Some(list_error_reason(&e));
+                        }
+                    }
+                }
+                AppActions::GoBack => {
+                    self.source = None;
+                    *app_mode = AppMode::ObjectList;
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match action {
+            AppActions::MoveDown => self.plan.next(),
+            AppActions::MoveUp => self.plan.previous(),
+            AppActions::MoveToTop => self.plan.first(),
+            AppActions::MoveToBottom => self.plan.last(),
+            AppActions::MoveRight => {
+                self.policy = self.policy.next();
+                self.update_summary();
+            }
+            AppActions::MoveLeft => {
+                self.policy = self.policy.previous();
+                self.update_summary();
+            }
+            AppActions::GoBack => {
+                // Back to editing the destination
+                self.prompt.active = true;
+                self.plan = ScrollableList::new("Download Plan");
+                self.plan.set_has_more(false);
+            }
+            AppActions::Enter => {
+                let skip_existing = self.policy == ConflictPolicy::Skip;
+                let items: Vec<DownloadItem> = self
+                    .plan
+                    .items
+                    .iter()
+                    .filter(|entry| !(skip_existing && entry.exists))
+                    .filter_map(|entry| {
+                        entry.path.clone().map(|path| DownloadItem {
+                            key: entry.key.clone(),
+                            path,
+                            size: entry.size,
+                        })
+                    })
+                    .collect();
+
+                if !items.is_empty() {
+                    self.recent_dirs.remember(&self.bucket, &self.destination());
+                    let description = format!(
+                        "Download {} object(s) from {} to {} ({} existing)",
+                        items.len(),
+                        self.bucket,
+                        self.destination().display(),
+                        self.policy
+                    );
+                    jobs.spawn_download(description, self.bucket.clone(), items, self.policy);
+                }
+                self.source = None;
+                *app_mode = AppMode::ObjectList;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
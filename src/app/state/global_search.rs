@@ -0,0 +1,328 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::app::state::s3_bucket::S3BucketState;
+use crate::app::state::s3_object::S3ObjectState;
+use crate::aws::s3_client::{S3Client, is_access_error, list_error_reason};
+use crate::search::{SearchBar, SearchMode};
+use crate::ui::ScrollableList;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{Semaphore, mpsc};
+use tokio::task::{JoinHandle, JoinSet};
+
+/// Buckets listed at the same time
+const MAX_CONCURRENT_BUCKETS: usize = 8;
+/// Time allowed for listing a single bucket before it counts as failed
+const BUCKET_TIMEOUT: Duration = Duration::from_secs(30);
+/// Stop collecting once this many keys have matched
+const MAX_HITS: usize = 5000;
+
+#[derive(Debug, Clone)]
+pub struct GlobalSearchHit {
+    pub bucket: String,
+    pub key: String,
+    pub size: i64,
+}
+
+#[derive(Debug)]
+enum GlobalSearchEvent {
+    Hit(GlobalSearchHit),
+    BucketDone,
+    /// The bucket may not be listed
+    BucketSkipped {
+        bucket: String,
+        reason: String,
+    },
+    /// Throttling, network errors and time-outs, which may go away on another try
+    BucketFailed {
+        bucket: String,
+        reason: String,
+    },
+}
+
+/// Searches every loaded bucket for keys matching a prefix or pattern
+#[derive(Debug)]
+pub struct GlobalSearchState {
+    pub results: ScrollableList<GlobalSearchHit>,
+    pub prompt: SearchBar,
+    pub buckets_total: usize,
+    pub buckets_done: usize,
+    pub skipped: Vec<(String, String)>,
+    pub failed: Vec<(String, String)>,
+    query: String,
+    receiver: Option<mpsc::UnboundedReceiver<GlobalSearchEvent>>,
+    task: Option<JoinHandle<()>>,
+    s3_client: Arc<S3Client>,
+}
+
+impl GlobalSearchState {
+    pub fn new(s3_client: Arc<S3Client>) -> Self {
+        Self {
+            results: ScrollableList::new("Search All Buckets"),
+            prompt: SearchBar::default(),
+            buckets_total: 0,
+            buckets_done: 0,
+            skipped: Vec::new(),
+            failed: Vec::new(),
+            query: String::new(),
+            receiver: None,
+            task: None,
+            s3_client,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// Open the prompt for a new search
+    pub fn open(&mut self) {
+        self.prompt.active = true;
+    }
+
+    /// Start searching the given buckets in the background, replacing any previous search
+    pub fn start(&mut self, buckets: Vec<String>) {
+        self.cancel();
+
+        let query = self.prompt.query.clone();
+        let default_mode = self.prompt.default_mode;
+        self.results = ScrollableList::new("Search All Buckets");
+        self.results.set_has_more(false);
+        self.query = query.clone();
+        self.buckets_total = buckets.len();
+        self.buckets_done = 0;
+        self.skipped.clear();
+        self.failed.clear();
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let s3_client = self.s3_client.clone();
+        self.receiver = Some(receiver);
+        self.task = Some(tokio::spawn(async move {
+            let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_BUCKETS));
+            let hits = Arc::new(AtomicUsize::new(0));
+            let mut tasks = JoinSet::new();
+
+            for bucket in buckets {
+                let Ok(permit) = semaphore.clone().acquire_owned().await else {
+                    break;
+                };
+                let s3_client = s3_client.clone();
+                let sender = sender.clone();
+                let query = query.clone();
+                let hits = hits.clone();
+
+                tasks.spawn(async move {
+                    let _permit = permit;
+                    let outcome = tokio::time::timeout(
+                        BUCKET_TIMEOUT,
+                        search_bucket(&s3_client, &bucket, &query, default_mode, &sender, &hits),
+                    )
+                    .await;
+
+                    let event = match outcome {
+                        Ok(Ok(())) => GlobalSearchEvent::BucketDone,
+                        Ok(Err(e)) if is_access_error(&e) => GlobalSearchEvent::BucketSkipped {
+                            bucket,
+                            reason: list_error_reason(&e),
+                        },
+                        Ok(Err(e)) => GlobalSearchEvent::BucketFailed {
+                            bucket,
+                            reason: list_error_reason(&e),
+                        },
+                        Err(_) => GlobalSearchEvent::BucketFailed {
+                            bucket,
+                            reason: "timed out".to_string(),
+                        },
+                    };
+                    let _ = sender.send(event);
+                });
+            }
+
+            while tasks.join_next().await.is_some() {}
+        }));
+    }
+
+    /// Stop the running search, if any
+    pub fn cancel(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+        self.receiver = None;
+    }
+
+    /// Drain results streamed from the background search
+    pub fn poll(&mut self) {
+        let Some(receiver) = self.receiver.as_mut() else {
+            return;
+        };
+
+        loop {
+            match receiver.try_recv() {
+                Ok(GlobalSearchEvent::Hit(hit)) => {
+                    self.results.append_items(vec![hit]);
+                    self.results
+                        .filtered_indices
+                        .push(self.results.items.len() - 1);
+                    if self.results.selected_index().is_none() {
+                        self.results.first();
+                    }
+                }
+                Ok(GlobalSearchEvent::BucketDone) => self.buckets_done += 1,
+                Ok(GlobalSearchEvent::BucketSkipped { bucket, reason }) => {
+                    self.buckets_done += 1;
+                    self.skipped.push((bucket, reason));
+                }
+                Ok(GlobalSearchEvent::BucketFailed { bucket, reason }) => {
+                    self.buckets_done += 1;
+                    self.failed.push((bucket, reason));
+                }
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    self.receiver = None;
+                    self.task = None;
+                    break;
+                }
+            }
+        }
+
+        self.results.set_loading(self.is_running());
+        self.results.title = format!(
+            "Search All Buckets: {} ({}/{} buckets, {} matches)",
+            self.query,
+            self.buckets_done,
+            self.buckets_total,
+            self.results.items.len()
+        );
+        let list = |buckets: &[(String, String)]| {
+            buckets
+                .iter()
+                .map(|(bucket, reason)| format!("{} ({})", bucket, reason))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut summary = Vec::new();
+        if !self.failed.is_empty() {
+            summary.push(format!("Failed: {}", list(&self.failed)));
+        }
+        if !self.skipped.is_empty() {
+            summary.push(format!("Skipped (no access): {}", list(&self.skipped)));
+        }
+        self.results.summary = (!summary.is_empty()).then(|| summary.join("  "));
+    }
+
+    pub async fn handle_action(
+        &mut self,
+        action: AppActions,
+        app_mode: &mut AppMode,
+        s3_bucket_state: &S3BucketState,
+        s3_object_state: &mut S3ObjectState,
+    ) -> color_eyre::Result<()> {
+        if self.prompt.active {
+            match action {
+                AppActions::SearchInput(c) => self.prompt.input(c),
+                AppActions::SearchDelete => self.prompt.delete(),
+                AppActions::Enter if !self.prompt.query.is_empty() => {
+                    self.prompt.active = false;
+                    let buckets = s3_bucket_state
+                        .bucket_list
+                        .items
+                        .iter()
+                        .filter_map(|bucket| bucket.name.clone())
+                        .collect();
+                    self.start(buckets);
+                }
+                AppActions::GoBack => {
+                    self.prompt.active = false;
+                    if !self.is_running() && self.results.is_empty() {
+                        *app_mode = AppMode::BucketList;
+                    }
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match action {
+            AppActions::GoBack => {
+                self.cancel();
+                *app_mode = AppMode::BucketList;
+            }
+            AppActions::StartSearch => self.prompt.active = true,
+            AppActions::MoveDown => self.results.next(),
+            AppActions::MoveUp => self.results.previous(),
+            AppActions::MoveToTop => self.results.first(),
+            AppActions::MoveToBottom => self.results.last(),
+            AppActions::PageDown => {
+                for _ in 0..10 {
+                    self.results.next();
+                }
+            }
+            AppActions::PageUp => {
+                for _ in 0..10 {
+                    self.results.previous();
+                }
+            }
+            AppActions::Enter => {
+                if let Some(hit) = self.results.selected_item().cloned() {
+                    *app_mode = AppMode::ObjectList;
+                    s3_object_state.open_at_key(&hit.bucket, &hit.key).await?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// List one bucket and stream every matching key
+async fn search_bucket(
+    s3_client: &S3Client,
+    bucket: &str,
+    query: &str,
+    default_mode: SearchMode,
+    sender: &mpsc::UnboundedSender<GlobalSearchEvent>,
+    hits: &AtomicUsize,
+) -> color_eyre::Result<()> {
+    let mut matcher = SearchBar::default();
+    matcher.default_mode = default_mode;
+    matcher.set_query(query);
+
+    // Fuzzy matching every key in every bucket is too noisy, so a fuzzy query is a key prefix
+    let (prefix, filter) = match matcher.mode_and_pattern() {
+        (SearchMode::Fuzzy, pattern) => (Some(pattern.to_string()), false),
+        _ => (matcher.listing_prefix(), true),
+    };
+
+    let mut continuation_token = None;
+    loop {
+        let (objects, next_token) = s3_client
+            .list_objects(bucket, continuation_token, prefix.clone(), 1000)
+            .await?;
+
+        for object in objects {
+            let Some(key) = object.key() else { continue };
+            if filter && !matcher.matches(key) {
+                continue;
+            }
+            if hits.fetch_add(1, Ordering::Relaxed) >= MAX_HITS {
+                return Ok(());
+            }
+            let hit = GlobalSearchHit {
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+                size: object.size().unwrap_or(0),
+            };
+            if sender.send(GlobalSearchEvent::Hit(hit)).is_err() {
+                // The search was cancelled
+                return Ok(());
+            }
+        }
+
+        match next_token {
+            Some(token) => continuation_token = Some(token),
+            None => return Ok(()),
+        }
+    }
+}
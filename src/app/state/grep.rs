@@ -0,0 +1,418 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::app::state::s3_object::S3ObjectState;
+use crate::aws::s3_client::{S3Client, list_error_reason};
+use crate::search::SearchBar;
+use crate::ui::ScrollableList;
+use crate::util::{decompress_if_gzip_within, parse_size};
+use regex::Regex;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::{Semaphore, mpsc};
+use tokio::task::{JoinHandle, JoinSet};
+
+/// Objects downloaded and searched at the same time
+const MAX_CONCURRENT_OBJECTS: usize = 8;
+/// Objects larger than this are skipped unless `max=` says otherwise
+const DEFAULT_MAX_SIZE: i64 = 50 * 1024 * 1024;
+/// Stop collecting once this many lines have matched
+const MAX_HITS: usize = 2000;
+/// Lines shown before and after each match
+const CONTEXT_LINES: usize = 1;
+
+/// A parsed grep prompt: `<regex> [ext=log,csv] [max=10M]`
+#[derive(Debug, Clone)]
+pub struct GrepQuery {
+    pub regex: Regex,
+    pub extensions: Vec<String>,
+    pub max_size: i64,
+}
+
+impl GrepQuery {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut extensions = Vec::new();
+        let mut max_size = DEFAULT_MAX_SIZE;
+
+        // Options are trailing tokens so the pattern itself may contain spaces
+        let mut pattern = input.trim_end();
+        while let Some((rest, last)) = pattern.rsplit_once(' ') {
+            if let Some(exts) = last.strip_prefix("ext=") {
+                extensions.extend(
+                    exts.split(',')
+                        .filter(|ext| !ext.is_empty())
+                        .map(|ext| ext.trim_start_matches('.').to_string()),
+                );
+            } else if let Some(size) = last.strip_prefix("max=") {
+                max_size = parse_size(size)?;
+            } else {
+                break;
+            }
+            pattern = rest.trim_end();
+        }
+
+        if pattern.is_empty() {
+            return Err("missing pattern".to_string());
+        }
+        let regex = Regex::new(pattern).map_err(|e| format!("invalid regex: {}", e))?;
+
+        Ok(Self {
+            regex,
+            extensions,
+            max_size,
+        })
+    }
+
+    /// Whether `key` has one of the wanted extensions, looking through a trailing `.gz`
+    pub fn wants_key(&self, key: &str) -> bool {
+        if self.extensions.is_empty() {
+            return true;
+        }
+        let uncompressed = key.strip_suffix(".gz").unwrap_or(key);
+        self.extensions.iter().any(|ext| {
+            key.ends_with(&format!(".{}", ext)) || uncompressed.ends_with(&format!(".{}", ext))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GrepHit {
+    pub key: String,
+    /// 1-based line number in the (decompressed) object
+    pub line_number: usize,
+    pub line: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+#[derive(Debug)]
+enum GrepEvent {
+    Listed(usize),
+    Hit(GrepHit),
+    Scanned,
+    Skipped,
+    Failed(String),
+}
+
+/// Searches the contents of every object under a prefix for a regex
+#[derive(Debug)]
+pub struct GrepState {
+    pub results: ScrollableList<GrepHit>,
+    pub prompt: SearchBar,
+    pub bucket: String,
+    pub prefix: Option<String>,
+    pub objects_listed: usize,
+    pub objects_scanned: usize,
+    pub objects_skipped: usize,
+    pub errors: Vec<String>,
+    receiver: Option<mpsc::UnboundedReceiver<GrepEvent>>,
+    task: Option<JoinHandle<()>>,
+    s3_client: Arc<S3Client>,
+}
+
+impl GrepState {
+    pub fn new(s3_client: Arc<S3Client>) -> Self {
+        Self {
+            results: ScrollableList::new("Grep"),
+            prompt: SearchBar::default(),
+            bucket: String::new(),
+            prefix: None,
+            objects_listed: 0,
+            objects_scanned: 0,
+            objects_skipped: 0,
+            errors: Vec::new(),
+            receiver: None,
+            task: None,
+            s3_client,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// Open the prompt to grep under `prefix` in `bucket`
+    pub fn open(&mut self, bucket: &str, prefix: Option<String>) {
+        if self.bucket != bucket || self.prefix != prefix {
+            self.cancel();
+            self.results = ScrollableList::new("Grep");
+        }
+        self.bucket = bucket.to_string();
+        self.prefix = prefix;
+        self.prompt.active = true;
+    }
+
+    /// Start searching in the background, replacing any previous run
+    pub fn start(&mut self, query: GrepQuery) {
+        self.cancel();
+
+        self.results = ScrollableList::new("Grep");
+        self.results.set_has_more(false);
+        self.objects_listed = 0;
+        self.objects_scanned = 0;
+        self.objects_skipped = 0;
+        self.errors.clear();
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let s3_client = self.s3_client.clone();
+        let bucket = self.bucket.clone();
+        let prefix = self.prefix.clone();
+        self.receiver = Some(receiver);
+        self.task = Some(tokio::spawn(async move {
+            let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_OBJECTS));
+            let hits = Arc::new(AtomicUsize::new(0));
+            let query = Arc::new(query);
+            let mut tasks = JoinSet::new();
+            let mut continuation_token = None;
+
+            loop {
+                let (objects, next_token) = match s3_client
+                    .list_objects(&bucket, continuation_token, prefix.clone(), 1000)
+                    .await
+                {
+                    Ok(page) => page,
+                    Err(e) => {
+                        let _ = sender.send(GrepEvent::Failed(list_error_reason(&e)));
+                        break;
+                    }
+                };
+                let _ = sender.send(GrepEvent::Listed(objects.len()));
+
+                for object in objects {
+                    let Some(key) = object.key().map(String::from) else {
+                        continue;
+                    };
+                    if !query.wants_key(&key) || object.size().unwrap_or(0) > query.max_size {
+                        let _ = sender.send(GrepEvent::Skipped);
+                        continue;
+                    }
+                    if hits.load(Ordering::Relaxed) >= MAX_HITS {
+                        break;
+                    }
+
+                    let Ok(permit) = semaphore.clone().acquire_owned().await else {
+                        break;
+                    };
+                    let s3_client = s3_client.clone();
+                    let sender = sender.clone();
+                    let bucket = bucket.clone();
+                    let query = query.clone();
+                    let hits = hits.clone();
+
+                    tasks.spawn(async move {
+                        let _permit = permit;
+                        let bytes = match s3_client.download_object_bytes(&bucket, &key).await {
+                            Ok(bytes) => bytes,
+                            Err(e) => {
+                                let _ = sender.send(GrepEvent::Failed(format!("{}: {}", key, e)));
+                                return;
+                            }
+                        };
+                        // Decompressing and matching is CPU bound, so keep it off the async workers
+                        let scan = {
+                            let key = key.clone();
+                            tokio::task::spawn_blocking(move || {
+                                grep_object(&key, bytes, &query, &hits)
+                            })
+                        };
+                        let event = match scan.await {
+                            Ok(Some(found)) => {
+                                for hit in found {
+                                    let _ = sender.send(GrepEvent::Hit(hit));
+                                }
+                                GrepEvent::Scanned
+                            }
+                            Ok(None) => GrepEvent::Skipped,
+                            Err(e) => GrepEvent::Failed(format!("{}: {}", key, e)),
+                        };
+                        let _ = sender.send(event);
+                    });
+                }
+
+                match next_token {
+                    Some(token) if hits.load(Ordering::Relaxed) < MAX_HITS => {
+                        continuation_token = Some(token)
+                    }
+                    _ => break,
+                }
+            }
+
+            while tasks.join_next().await.is_some() {}
+        }));
+    }
+
+    /// Stop the running search, if any
+    pub fn cancel(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+        self.receiver = None;
+    }
+
+    /// Drain hits streamed from the background search
+    pub fn poll(&mut self) {
+        let Some(receiver) = self.receiver.as_mut() else {
+            return;
+        };
+
+        loop {
+            match receiver.try_recv() {
+                Ok(GrepEvent::Listed(count)) => self.objects_listed += count,
+                Ok(GrepEvent::Hit(hit)) => {
+                    self.results.append_items(vec![hit]);
+                    self.results
+                        .filtered_indices
+                        .push(self.results.items.len() - 1);
+                    if self.results.selected_index().is_none() {
+                        self.results.first();
+                    }
+                }
+                Ok(GrepEvent::Scanned) => self.objects_scanned += 1,
+                Ok(GrepEvent::Skipped) => self.objects_skipped += 1,
+                Ok(GrepEvent::Failed(reason)) => self.errors.push(reason),
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    self.receiver = None;
+                    self.task = None;
+                    break;
+                }
+            }
+        }
+
+        self.results.set_loading(self.is_running());
+        self.results.title = format!(
+            "Grep {}/{}: {} hits ({} scanned, {} skipped of {} listed)",
+            self.bucket,
+            self.prefix.as_deref().unwrap_or(""),
+            self.results.items.len(),
+            self.objects_scanned,
+            self.objects_skipped,
+            self.objects_listed
+        );
+        self.results.summary = self
+            .errors
+            .last()
+            .map(|e| format!("{} errors, last: {}", self.errors.len(), e));
+    }
+
+    pub async fn handle_action(
+        &mut self,
+        action: AppActions,
+        app_mode: &mut AppMode,
+        s3_object_state: &mut S3ObjectState,
+    ) -> color_eyre::Result<()> {
+        if self.prompt.active {
+            match action {
+                AppActions::SearchInput(c) => {
+                    self.prompt.input(c);
+                    self.validate_prompt();
+                }
+                AppActions::SearchDelete => {
+                    self.prompt.delete();
+                    self.validate_prompt();
+                }
+                AppActions::Enter => {
+                    if let Ok(query) = GrepQuery::parse(&self.prompt.query) {
+                        self.prompt.active = false;
+                        self.start(query);
+                    }
+                }
+                AppActions::GoBack => {
+                    self.prompt.active = false;
+                    if !self.is_running() && self.results.is_empty() {
+                        *app_mode = AppMode::ObjectList;
+                    }
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match action {
+            AppActions::GoBack => {
+                self.cancel();
+                *app_mode = AppMode::ObjectList;
+            }
+            AppActions::StartSearch => self.prompt.active = true,
+            AppActions::MoveDown => self.results.next(),
+            AppActions::MoveUp => self.results.previous(),
+            AppActions::MoveToTop => self.results.first(),
+            AppActions::MoveToBottom => self.results.last(),
+            AppActions::PageDown => {
+                for _ in 0..10 {
+                    self.results.next();
+                }
+            }
+            AppActions::PageUp => {
+                for _ in 0..10 {
+                    self.results.previous();
+                }
+            }
+            AppActions::Enter => {
+                if let Some(hit) = self.results.selected_item().cloned() {
+                    *app_mode = AppMode::ObjectList;
+                    s3_object_state.open_at_key(&self.bucket, &hit.key).await?;
+                    let found = s3_object_state
+                        .object_list
+                        .selected_item()
+                        .is_some_and(|object| object.key() == Some(hit.key.as_str()));
+                    if found {
+                        s3_object_state.pending_preview_line = Some(hit.line_number);
+                        s3_object_state.select_object().await?;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Report an invalid pattern or option inline while typing
+    fn validate_prompt(&mut self) {
+        self.prompt.error = GrepQuery::parse(&self.prompt.query).err();
+    }
+}
+
+/// Search one object body line by line, returning its hits with context. Returns `None`,
+/// so the object counts as skipped, for binary content and for gzip that decompresses to
+/// more than `max=`.
+pub fn grep_object(
+    key: &str,
+    bytes: Vec<u8>,
+    query: &GrepQuery,
+    hits: &AtomicUsize,
+) -> Option<Vec<GrepHit>> {
+    let bytes = decompress_if_gzip_within(bytes, query.max_size.max(0) as u64)?;
+
+    // Skip binary content rather than matching garbage
+    if bytes.iter().take(8192).any(|&b| b == 0) {
+        return None;
+    }
+
+    let text = String::from_utf8_lossy(&bytes);
+    let lines: Vec<&str> = text.lines().collect();
+    let mut found = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        if !query.regex.is_match(line) {
+            continue;
+        }
+        if hits.fetch_add(1, Ordering::Relaxed) >= MAX_HITS {
+            break;
+        }
+
+        found.push(GrepHit {
+            key: key.to_string(),
+            line_number: idx + 1,
+            line: line.to_string(),
+            before: lines[idx.saturating_sub(CONTEXT_LINES)..idx]
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+            after: lines[idx + 1..(idx + 1 + CONTEXT_LINES).min(lines.len())]
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+        });
+    }
+    Some(found)
+}
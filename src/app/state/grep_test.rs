@@ -0,0 +1,74 @@
+use crate::app::state::grep::{GrepQuery, grep_object};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::io::Write;
+use std::sync::atomic::AtomicUsize;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = GrepQuery::parse("user id=\\d+ ext=log,.csv max=1M").unwrap();
+        assert_eq!(query.regex.as_str(), "user id=\\d+");
+        assert_eq!(query.extensions, vec!["log", "csv"]);
+        assert_eq!(query.max_size, 1024 * 1024);
+
+        // Options only count at the end, and the default cap applies without one
+        let query = GrepQuery::parse("ext=log ERROR").unwrap();
+        assert_eq!(query.regex.as_str(), "ext=log ERROR");
+        assert_eq!(query.max_size, 50 * 1024 * 1024);
+
+        assert!(GrepQuery::parse("").is_err());
+        assert!(GrepQuery::parse("  ext=log").is_err());
+        assert!(GrepQuery::parse("(unclosed").is_err());
+        assert!(GrepQuery::parse("ERROR max=lots").is_err());
+    }
+
+    #[test]
+    fn test_wants_key() {
+        let query = GrepQuery::parse("ERROR ext=log").unwrap();
+        assert!(query.wants_key("app/server.log"));
+        assert!(query.wants_key("app/server.log.gz"));
+        assert!(!query.wants_key("app/server.csv"));
+        assert!(!query.wants_key("app/catalog"));
+        assert!(GrepQuery::parse("ERROR").unwrap().wants_key("anything"));
+    }
+
+    #[test]
+    fn test_grep_object_with_context() {
+        let query = GrepQuery::parse("ERROR").unwrap();
+        let body = b"start\nERROR one\nmiddle\nok\nERROR two".to_vec();
+        let hits = grep_object("app.log", body, &query, &AtomicUsize::new(0)).unwrap();
+
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].line_number, 2);
+        assert_eq!(hits[0].before, vec!["start"]);
+        assert_eq!(hits[0].after, vec!["middle"]);
+        assert_eq!(hits[1].line, "ERROR two");
+        assert!(hits[1].after.is_empty());
+    }
+
+    #[test]
+    fn test_grep_object_skips_binary_and_large_gzip() {
+        let query = GrepQuery::parse("ERROR max=1K").unwrap();
+        let hits = AtomicUsize::new(0);
+
+        let found = grep_object("app.log.gz", gzip(b"ERROR zipped\n"), &query, &hits).unwrap();
+        assert_eq!(found[0].line, "ERROR zipped");
+
+        assert!(grep_object("blob.bin", b"ERROR\0\x01".to_vec(), &query, &hits).is_none());
+
+        // Small compressed, but past max= once decompressed
+        let bomb = gzip(&vec![b'a'; 64 * 1024]);
+        assert!(bomb.len() < 1024);
+        assert!(grep_object("bomb.gz", bomb, &query, &hits).is_none());
+    }
+}
@@ -0,0 +1,1039 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::app::state::download::{ConflictPolicy, DownloadItem};
+use crate::app::state::metadata::HeaderUpdate;
+use crate::app::state::tags::TagEdit;
+use crate::archive::RestoreRequest;
+use crate::aws::s3_client::{S3Client, TransferProgress};
+use crate::safety::Mutation;
+use crate::search::SearchBar;
+use crate::sync::{SyncAction, SyncActionKind, SyncTarget};
+use crate::throttle::{Bandwidth, LimitChanges, format_rate};
+use crate::ui::ScrollableList;
+use crate::util::create_unique_filepath;
+use color_eyre::eyre::eyre;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinSet;
+
+/// Jobs running at the same time; later ones wait in the queue
+pub const MAX_RUNNING_JOBS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    Copy,
+    Move,
+    Delete,
+    Tag,
+    Metadata,
+    Download,
+    Sync,
+    Restore,
+    StorageClass,
+}
+
+impl fmt::Display for JobKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Copy => write!(f, "Copy"),
+            Self::Move => write!(f, "Move"),
+            Self::Delete => write!(f, "Delete"),
+            Self::Tag => write!(f, "Tag"),
+            Self::Metadata => write!(f, "Metadata"),
+            Self::Download => write!(f, "Download"),
+            Self::Sync => write!(f, "Sync"),
+            Self::Restore => write!(f, "Restore"),
+            Self::StorageClass => write!(f, "Storage Class"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Paused,
+    Completed,
+    /// Finished, but some items failed
+    Failed,
+    Cancelled,
+}
+
+impl fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Queued => write!(f, "Queued"),
+            Self::Running => write!(f, "Running"),
+            Self::Paused => write!(f, "Paused"),
+            Self::Completed => write!(f, "Done"),
+            Self::Failed => write!(f, "Failed"),
+            Self::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+/// One object to copy (and, for moves, delete afterwards)
+#[derive(Debug, Clone)]
+pub struct TransferItem {
+    pub src_bucket: String,
+    pub src_key: String,
+    pub dst_bucket: String,
+    pub dst_key: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Signal {
+    Run,
+    Pause,
+    Cancel,
+}
+
+/// Shared by a job's workers and the panel: whether to go on, the job's own bandwidth cap
+/// and the bytes moved so far
+#[derive(Debug)]
+struct JobControl {
+    signal: watch::Sender<Signal>,
+    bandwidth: Bandwidth,
+    bytes: AtomicU64,
+    /// Items left alone on purpose, e.g. downloads onto files that exist with the skip policy
+    skipped: AtomicUsize,
+}
+
+impl JobControl {
+    fn new(bandwidth: u64) -> Self {
+        Self {
+            signal: watch::Sender::new(Signal::Run),
+            bandwidth: Bandwidth::new(bandwidth),
+            bytes: AtomicU64::new(0),
+            skipped: AtomicUsize::new(0),
+        }
+    }
+
+    fn signal(&self) -> Signal {
+        *self.signal.borrow()
+    }
+
+    fn set(&self, signal: Signal) {
+        self.signal.send_replace(signal);
+    }
+
+    /// Waits while the job is paused, and fails once it is cancelled
+    async fn checkpoint(&self) -> color_eyre::Result<()> {
+        let signal = match self.signal() {
+            Signal::Pause => {
+                *self
+                    .signal
+                    .subscribe()
+                    .wait_for(|signal| *signal != Signal::Pause)
+                    .await?
+            }
+            signal => signal,
+        };
+        match signal {
+            Signal::Cancel => Err(eyre!("cancelled")),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Transfers keep to the job's cap, and stop between chunks when it is paused or cancelled
+impl TransferProgress for JobControl {
+    async fn advance(&self, bytes: u64) -> color_eyre::Result<()> {
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.bandwidth.take(bytes).await;
+        self.checkpoint().await
+    }
+
+    async fn checkpoint(&self) -> color_eyre::Result<()> {
+        JobControl::checkpoint(self).await
+    }
+}
+
+/// The items of a job, kept so that the ones that did not succeed can be retried
+#[derive(Debug, Clone)]
+enum JobWork {
+    Transfer {
+        kind: JobKind,
+        items: Vec<TransferItem>,
+    },
+    Delete {
+        bucket: String,
+        keys: Vec<String>,
+    },
+    Tag {
+        bucket: String,
+        keys: Vec<String>,
+        edit: TagEdit,
+    },
+    Metadata {
+        bucket: String,
+        updates: Vec<HeaderUpdate>,
+    },
+    Download {
+        bucket: String,
+        items: Vec<DownloadItem>,
+        policy: ConflictPolicy,
+    },
+    Sync {
+        target: SyncTarget,
+        actions: Vec<SyncAction>,
+    },
+    DeleteBucket {
+        bucket: String,
+        steps: Vec<BucketStep>,
+    },
+    Restore {
+        bucket: String,
+        keys: Vec<String>,
+        request: RestoreRequest,
+    },
+    StorageClass {
+        bucket: String,
+        keys: Vec<String>,
+        class: String,
+    },
+}
+
+/// A step in emptying and deleting a bucket; the steps run in order
+#[derive(Debug, Clone)]
+enum BucketStep {
+    /// Every object version and delete marker, listed and deleted a page at a time
+    Versions,
+    Bucket,
+}
+
+impl JobWork {
+    fn kind(&self) -> JobKind {
+        match self {
+            Self::Transfer { kind, .. } => *kind,
+            Self::Delete { .. } | Self::DeleteBucket { .. } => JobKind::Delete,
+            Self::Tag { .. } => JobKind::Tag,
+            Self::Metadata { .. } => JobKind::Metadata,
+            Self::Download { .. } => JobKind::Download,
+            Self::Sync { .. } => JobKind::Sync,
+            Self::Restore { .. } => JobKind::Restore,
+            Self::StorageClass { .. } => JobKind::StorageClass,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Transfer { items, .. } => items.len(),
+            Self::Delete { keys, .. }
+            | Self::Tag { keys, .. }
+            | Self::Restore { keys, .. }
+            | Self::StorageClass { keys, .. } => keys.len(),
+            Self::Metadata { updates, .. } => updates.len(),
+            Self::Download { items, .. } => items.len(),
+            Self::Sync { actions, .. } => actions.len(),
+            Self::DeleteBucket { steps, .. } => steps.len(),
+        }
+    }
+
+    /// Bytes to transfer, when every item's size is known up front
+    fn total_bytes(&self) -> Option<u64> {
+        match self {
+            Self::Download { items, .. } => items.iter().map(|item| item.size).sum(),
+            Self::Sync { actions, .. } => Some(
+                actions
+                    .iter()
+                    .filter(|action| action.kind.transfers())
+                    .map(|action| action.size)
+                    .sum(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Items run at once: up to the request limit (read as each item starts) for deletes,
+    /// downloads, syncs and restores, otherwise one at a time
+    fn concurrency(&self, s3_client: &S3Client) -> usize {
+        match self {
+            Self::Delete { .. }
+            | Self::Download { .. }
+            | Self::Sync { .. }
+            | Self::Restore { .. } => s3_client.limits().requests.limit(),
+            _ => 1,
+        }
+    }
+
+    /// The same work limited to the items at `indices`
+    fn select(&self, indices: &[usize]) -> Self {
+        fn pick<T: Clone>(items: &[T], indices: &[usize]) -> Vec<T> {
+            indices.iter().map(|&index| items[index].clone()).collect()
+        }
+        let mut work = self.clone();
+        match &mut work {
+            Self::Transfer { items, .. } => *items = pick(items, indices),
+            Self::Delete { keys, .. }
+            | Self::Tag { keys, .. }
+            | Self::Restore { keys, .. }
+            | Self::StorageClass { keys, .. } => *keys = pick(keys, indices),
+            Self::Metadata { updates, .. } => *updates = pick(updates, indices),
+            Self::Download { items, .. } => *items = pick(items, indices),
+            Self::Sync { actions, .. } => *actions = pick(actions, indices),
+            Self::DeleteBucket { steps, .. } => *steps = pick(steps, indices),
+        }
+        work
+    }
+
+    /// What failure messages are about
+    fn label(&self, index: usize) -> String {
+        match self {
+            Self::Transfer { items, .. } => items[index].src_key.clone(),
+            Self::Delete { keys, .. }
+            | Self::Tag { keys, .. }
+            | Self::Restore { keys, .. }
+            | Self::StorageClass { keys, .. } => keys[index].clone(),
+            Self::Metadata { updates, .. } => updates[index].key.clone(),
+            Self::Download { items, .. } => items[index].key.clone(),
+            Self::Sync { actions, .. } => {
+                format!("{} {}", actions[index].kind, actions[index].relative)
+            }
+            Self::DeleteBucket { bucket, steps } => match &steps[index] {
+                BucketStep::Versions => format!("versions in {}", bucket),
+                BucketStep::Bucket => format!("bucket {}", bucket),
+            },
+        }
+    }
+
+    /// What running the work changes in S3, for the safety checks; downloads change nothing
+    fn mutation(&self) -> Option<Mutation> {
+        let (buckets, destructive) = match self {
+            Self::Transfer { kind, items } => {
+                let mut buckets: Vec<String> = items.iter().map(|i| i.dst_bucket.clone()).collect();
+                if *kind == JobKind::Move {
+                    buckets.extend(items.iter().map(|i| i.src_bucket.clone()));
+                }
+                buckets.sort();
+                buckets.dedup();
+                (buckets, true)
+            }
+            Self::Tag { bucket, .. } => (vec![bucket.clone()], false),
+            Self::Delete { bucket, .. }
+            | Self::Metadata { bucket, .. }
+            | Self::DeleteBucket { bucket, .. }
+            | Self::Restore { bucket, .. }
+            | Self::StorageClass { bucket, .. } => (vec![bucket.clone()], true),
+            Self::Sync { target, actions } => {
+                let uploads = actions.iter().any(|action| {
+                    matches!(
+                        action.kind,
+                        SyncActionKind::Upload | SyncActionKind::DeleteRemote
+                    )
+                });
+                if !uploads {
+                    return None;
+                }
+                (vec![target.bucket.clone()], true)
+            }
+            Self::Download { .. } => return None,
+        };
+        let description = format!("retry a {} job", self.kind().to_string().to_lowercase());
+        Some(Mutation::new(&description, buckets, destructive))
+    }
+
+    async fn run_item(
+        &self,
+        s3_client: &S3Client,
+        index: usize,
+        control: &JobControl,
+    ) -> color_eyre::Result<()> {
+        match self {
+            Self::Transfer { kind, items } => {
+                let item = &items[index];
+                // Pasting never overwrites; the item fails and a move keeps its source
+                if s3_client
+                    .object_exists(&item.dst_bucket, &item.dst_key)
+                    .await?
+                {
+                    return Err(eyre!(
+                        "{}/{} already exists; skipped",
+                        item.dst_bucket,
+                        item.dst_key
+                    ));
+                }
+                s3_client
+                    .copy_object(
+                        &item.src_bucket,
+                        &item.src_key,
+                        &item.dst_bucket,
+                        &item.dst_key,
+                        control,
+                    )
+                    .await?;
+                // Only delete the source once the copy is known to exist
+                if *kind == JobKind::Move {
+                    s3_client
+                        .delete_object(&item.src_bucket, &item.src_key)
+                        .await?;
+                }
+                Ok(())
+            }
+            Self::Delete { bucket, keys } => s3_client.delete_object(bucket, &keys[index]).await,
+            Self::Tag { bucket, keys, edit } => {
+                // Read-modify-write so tags outside the edit are kept
+                let mut tags = s3_client.get_object_tagging(bucket, &keys[index]).await?;
+                edit.apply(&mut tags).map_err(|e| eyre!(e))?;
+                s3_client
+                    .put_object_tagging(bucket, &keys[index], &tags)
+                    .await
+            }
+            Self::Metadata { bucket, updates } => {
+                let update = &updates[index];
+                s3_client
+                    .replace_object_headers(
+                        bucket,
+                        &update.key,
+                        &update.headers,
+                        update.etag.as_deref(),
+                    )
+                    .await
+            }
+            Self::Download {
+                bucket,
+                items,
+                policy,
+            } => download_item(s3_client, bucket, &items[index], *policy, control).await,
+            Self::Sync { target, actions } => target.run(s3_client, &actions[index], control).await,
+            Self::DeleteBucket { bucket, steps } => match &steps[index] {
+                BucketStep::Versions => empty_bucket(s3_client, bucket, control).await,
+                BucketStep::Bucket => s3_client.delete_bucket(bucket).await,
+            },
+            Self::Restore {
+                bucket,
+                keys,
+                request,
+            } => {
+                s3_client
+                    .restore_object(bucket, &keys[index], request)
+                    .await
+            }
+            Self::StorageClass {
+                bucket,
+                keys,
+                class,
+            } => {
+                s3_client
+                    .change_storage_class(bucket, &keys[index], class)
+                    .await
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Job {
+    pub id: usize,
+    pub kind: JobKind,
+    pub description: String,
+    pub total: usize,
+    pub done: usize,
+    pub failures: Vec<String>,
+    /// Items counted as done without doing anything
+    pub skipped: usize,
+    pub status: JobStatus,
+    /// Times the job was retried from the panel
+    pub retries: usize,
+    pub bytes_done: u64,
+    pub bytes_total: Option<u64>,
+    work: Arc<JobWork>,
+    /// Items of `work` that succeeded in this run
+    succeeded: Vec<bool>,
+    control: Arc<JobControl>,
+    started: bool,
+    /// Time spent running so far, leaving out time queued or paused
+    active: Duration,
+    active_since: Option<Instant>,
+}
+
+impl Job {
+    fn new(id: usize, description: String, work: JobWork) -> Self {
+        let mut job = Self {
+            id,
+            kind: work.kind(),
+            description,
+            total: 0,
+            done: 0,
+            failures: Vec::new(),
+            skipped: 0,
+            status: JobStatus::Queued,
+            retries: 0,
+            bytes_done: 0,
+            bytes_total: None,
+            work: Arc::new(work),
+            succeeded: Vec::new(),
+            control: Arc::new(JobControl::new(0)),
+            started: false,
+            active: Duration::ZERO,
+            active_since: None,
+        };
+        job.reset();
+        job
+    }
+
+    /// Start counting afresh for the items in `work`
+    fn reset(&mut self) {
+        self.total = self.work.len();
+        self.done = 0;
+        self.failures.clear();
+        self.skipped = 0;
+        self.status = JobStatus::Queued;
+        self.bytes_done = 0;
+        self.bytes_total = self.work.total_bytes();
+        self.succeeded = vec![false; self.total];
+        // A retry keeps the job's bandwidth cap
+        self.control = Arc::new(JobControl::new(self.control.bandwidth.limit()));
+        self.started = false;
+        self.active = Duration::ZERO;
+        self.active_since = None;
+    }
+
+    fn set_active(&mut self, active: bool) {
+        match (active, self.active_since) {
+            (true, None) => self.active_since = Some(Instant::now()),
+            (false, Some(since)) => {
+                self.active += since.elapsed();
+                self.active_since = None;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.active
+            + self
+                .active_since
+                .map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    /// Average bytes per second while running
+    pub fn rate(&self) -> Option<f64> {
+        let seconds = self.elapsed().as_secs_f64();
+        (self.bytes_done > 0 && seconds > 0.0).then(|| self.bytes_done as f64 / seconds)
+    }
+
+    /// Time left at the pace so far, by bytes when the total is known, otherwise by items
+    pub fn eta(&self) -> Option<Duration> {
+        if self.status != JobStatus::Running {
+            return None;
+        }
+        let progress = match self.bytes_total {
+            Some(total) if total > 0 => self.bytes_done as f64 / total as f64,
+            _ if self.total > 0 => (self.done + self.failures.len()) as f64 / self.total as f64,
+            _ => return None,
+        };
+        let seconds = self.elapsed().as_secs_f64();
+        (progress > 0.0 && progress < 1.0)
+            .then(|| Duration::from_secs_f64(seconds * (1.0 - progress) / progress))
+    }
+
+    /// The job's own bandwidth cap in bytes per second; 0 is unlimited
+    pub fn bandwidth_limit(&self) -> u64 {
+        self.control.bandwidth.limit()
+    }
+
+    /// Cancelled, with items still winding down
+    pub fn is_cancelling(&self) -> bool {
+        self.control.signal() == Signal::Cancel
+            && matches!(self.status, JobStatus::Running | JobStatus::Paused)
+    }
+}
+
+#[derive(Debug)]
+enum JobEvent {
+    ItemDone { id: usize, index: usize },
+    ItemFailed { id: usize, message: String },
+    Finished { id: usize },
+}
+
+/// Queued and running background jobs, and the panel that lists them
+#[derive(Debug)]
+pub struct JobsState {
+    pub jobs: ScrollableList<Job>,
+    /// Mode to go back to when the panel is closed
+    pub return_mode: AppMode,
+    /// Changes the global and selected job's bandwidth and the request limit
+    pub limits_prompt: SearchBar,
+    next_id: usize,
+    sender: mpsc::UnboundedSender<JobEvent>,
+    receiver: mpsc::UnboundedReceiver<JobEvent>,
+    s3_client: Arc<S3Client>,
+}
+
+impl JobsState {
+    pub fn new(s3_client: Arc<S3Client>) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut jobs = ScrollableList::new("Jobs");
+        jobs.set_has_more(false);
+        Self {
+            jobs,
+            return_mode: AppMode::BucketList,
+            limits_prompt: SearchBar::default(),
+            next_id: 1,
+            sender,
+            receiver,
+            s3_client,
+        }
+    }
+
+    /// Queue a job, starting it straight away when there is room
+    fn add_job(&mut self, description: String, work: JobWork) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.jobs
+            .append_items(vec![Job::new(id, description, work)]);
+        self.jobs.filtered_indices.push(self.jobs.items.len() - 1);
+        if self.jobs.selected_index().is_none() {
+            self.jobs.first();
+        }
+        self.start_queued();
+    }
+
+    /// Start queued jobs, oldest first, while fewer than `MAX_RUNNING_JOBS` run
+    fn start_queued(&mut self) {
+        let mut running = self
+            .jobs
+            .items
+            .iter()
+            .filter(|job| job.status == JobStatus::Running)
+            .count();
+        for job in self.jobs.items.iter_mut() {
+            if running >= MAX_RUNNING_JOBS {
+                break;
+            }
+            if job.status != JobStatus::Queued {
+                continue;
+            }
+            job.status = JobStatus::Running;
+            job.started = true;
+            job.set_active(true);
+            running += 1;
+            tokio::spawn(run_job(
+                job.id,
+                job.work.clone(),
+                job.control.clone(),
+                self.s3_client.clone(),
+                self.sender.clone(),
+            ));
+        }
+    }
+
+    /// Copy (or move) `items` in the background
+    pub fn spawn_transfer(&mut self, kind: JobKind, description: String, items: Vec<TransferItem>) {
+        self.add_job(description, JobWork::Transfer { kind, items });
+    }
+
+    /// Delete `keys` in the background
+    pub fn spawn_delete(&mut self, bucket: String, keys: Vec<String>) {
+        let description = format!("Delete {} object(s) in {}", keys.len(), bucket);
+        self.add_job(description, JobWork::Delete { bucket, keys });
+    }
+
+    /// Delete every version in `bucket`, then the bucket itself
+    pub fn spawn_delete_bucket(&mut self, bucket: String) {
+        let description = format!("Empty and delete bucket {}", bucket);
+        let steps = vec![BucketStep::Versions, BucketStep::Bucket];
+        self.add_job(description, JobWork::DeleteBucket { bucket, steps });
+    }
+
+    /// Apply `edit` to the existing tags of every key in the background
+    pub fn spawn_tagging(&mut self, bucket: String, keys: Vec<String>, edit: TagEdit) {
+        let description = format!("Tag {} object(s) in {}: {}", keys.len(), bucket, edit);
+        self.add_job(description, JobWork::Tag { bucket, keys, edit });
+    }
+
+    /// Request a restore of each archived object in the background
+    pub fn spawn_restore(&mut self, bucket: String, keys: Vec<String>, request: RestoreRequest) {
+        let description = format!(
+            "Restore {} object(s) in {} for {} days ({})",
+            keys.len(),
+            bucket,
+            request.days,
+            request.tier
+        );
+        self.add_job(
+            description,
+            JobWork::Restore {
+                bucket,
+                keys,
+                request,
+            },
+        );
+    }
+
+    /// Move each object to `class` with a self-copy in the background
+    pub fn spawn_storage_class(&mut self, bucket: String, keys: Vec<String>, class: String) {
+        let description = format!("Change {} object(s) in {} to {}", keys.len(), bucket, class);
+        self.add_job(
+            description,
+            JobWork::StorageClass {
+                bucket,
+                keys,
+                class,
+            },
+        );
+    }
+
+    /// Rewrite the headers of each object in the background
+    pub fn spawn_header_updates(&mut self, bucket: String, updates: Vec<HeaderUpdate>) {
+        let description = format!(
+            "Update metadata of {} object(s) in {}",
+            updates.len(),
+            bucket
+        );
+        self.add_job(description, JobWork::Metadata { bucket, updates });
+    }
+
+    /// Download `items` in the background, a few at a time, resolving local files that
+    /// already exist with `policy`
+    pub fn spawn_download(
+        &mut self,
+        description: String,
+        bucket: String,
+        items: Vec<DownloadItem>,
+        policy: ConflictPolicy,
+    ) {
+        self.add_job(
+            description,
+            JobWork::Download {
+                bucket,
+                items,
+                policy,
+            },
+        );
+    }
+
+    /// Carry out a reviewed sync plan in the background, a few actions at a time
+    pub fn spawn_sync(
+        &mut self,
+        description: String,
+        target: SyncTarget,
+        actions: Vec<SyncAction>,
+    ) {
+        self.add_job(description, JobWork::Sync { target, actions });
+    }
+
+    /// Apply progress from running jobs and start queued ones, returning a message for each
+    /// job that finished
+    pub fn poll(&mut self) -> Vec<String> {
+        let mut finished = Vec::new();
+        while let Ok(event) = self.receiver.try_recv() {
+            let id = match &event {
+                JobEvent::ItemDone { id, .. }
+                | JobEvent::ItemFailed { id, .. }
+                | JobEvent::Finished { id } => *id,
+            };
+            let Some(job) = self.jobs.items.iter_mut().find(|job| job.id == id) else {
+                continue;
+            };
+
+            match event {
+                JobEvent::ItemDone { index, .. } => {
+                    job.done += 1;
+                    job.succeeded[index] = true;
+                }
+                JobEvent::ItemFailed { message, .. } => job.failures.push(message),
+                JobEvent::Finished { .. } => {
+                    job.set_active(false);
+                    job.status = if job.done == job.total {
+                        JobStatus::Completed
+                    } else if job.control.signal() == Signal::Cancel {
+                        JobStatus::Cancelled
+                    } else {
+                        JobStatus::Failed
+                    };
+                    job.skipped = job.control.skipped.load(Ordering::Relaxed);
+                    finished.push(format!(
+                        "{} {}: {} of {} done{}, {} failed",
+                        job.description,
+                        match job.status {
+                            JobStatus::Cancelled => "cancelled",
+                            _ => "finished",
+                        },
+                        job.done,
+                        job.total,
+                        skipped_note(job.skipped),
+                        job.failures.len()
+                    ));
+                }
+            }
+        }
+
+        for job in self.jobs.items.iter_mut() {
+            job.bytes_done = job.control.bytes.load(Ordering::Relaxed);
+            job.skipped = job.control.skipped.load(Ordering::Relaxed);
+        }
+        self.start_queued();
+
+        self.jobs.title = format!("Jobs ({})", self.s3_client.limits());
+        self.jobs.summary = self
+            .jobs
+            .selected_item()
+            .and_then(|job| job.failures.last())
+            .map(|failure| format!("Last failure: {}", failure));
+        finished
+    }
+
+    pub fn has_running(&self) -> bool {
+        self.jobs
+            .items
+            .iter()
+            .any(|job| job.status == JobStatus::Running)
+    }
+
+    fn selected_job(&mut self) -> Option<&mut Job> {
+        let index = self.jobs.selected_index()?;
+        self.jobs.items.get_mut(index)
+    }
+
+    /// Pause a queued or running job, or let a paused one go on
+    fn toggle_pause(&mut self) {
+        let Some(job) = self.selected_job() else {
+            return;
+        };
+        if job.is_cancelling() {
+            return;
+        }
+        match job.status {
+            JobStatus::Queued | JobStatus::Running => {
+                job.control.set(Signal::Pause);
+                job.set_active(false);
+                job.status = JobStatus::Paused;
+            }
+            JobStatus::Paused if job.started => {
+                job.control.set(Signal::Run);
+                job.set_active(true);
+                job.status = JobStatus::Running;
+            }
+            JobStatus::Paused => {
+                job.control.set(Signal::Run);
+                job.status = JobStatus::Queued;
+            }
+            _ => {}
+        }
+    }
+
+    /// Stop a job; items in flight are interrupted and left for a retry
+    fn cancel(&mut self) {
+        let Some(job) = self.selected_job() else {
+            return;
+        };
+        match job.status {
+            JobStatus::Queued | JobStatus::Paused if !job.started => {
+                job.control.set(Signal::Cancel);
+                job.status = JobStatus::Cancelled;
+            }
+            JobStatus::Running | JobStatus::Paused => job.control.set(Signal::Cancel),
+            _ => {}
+        }
+    }
+
+    /// What `retry` would change in S3, so it goes through the same checks as the action
+    /// that started the job
+    pub fn retry_mutation(&self) -> Option<Mutation> {
+        let job = self.jobs.selected_item()?;
+        if !matches!(job.status, JobStatus::Failed | JobStatus::Cancelled) {
+            return None;
+        }
+        job.work.mutation()
+    }
+
+    /// Queue the items of a failed or cancelled job that did not succeed
+    fn retry(&mut self) {
+        let Some(job) = self.selected_job() else {
+            return;
+        };
+        if !matches!(job.status, JobStatus::Failed | JobStatus::Cancelled) {
+            return;
+        }
+        let remaining: Vec<usize> = (0..job.total).filter(|&i| !job.succeeded[i]).collect();
+        if remaining.is_empty() {
+            return;
+        }
+        job.work = Arc::new(job.work.select(&remaining));
+        job.retries += 1;
+        job.reset();
+        self.start_queued();
+    }
+
+    /// Open the limits prompt with the current values
+    fn open_limits(&mut self) {
+        let limits = self.s3_client.limits();
+        let mut query = format!(
+            "global={} requests={}",
+            format_rate(limits.bandwidth.limit()),
+            limits.requests.limit()
+        );
+        if let Some(job) = self.jobs.selected_item() {
+            query.push_str(&format!(" job={}", format_rate(job.bandwidth_limit())));
+        }
+        self.limits_prompt.set_query(&query.replace("/s", ""));
+        self.limits_prompt.error = None;
+        self.limits_prompt.active = true;
+    }
+
+    /// Apply the limits typed into the prompt; running transfers pick them up straight away
+    fn apply_limits(&mut self) {
+        let changes = match LimitChanges::parse(&self.limits_prompt.query) {
+            Ok(changes) => changes,
+            Err(e) => {
+                self.limits_prompt.error = Some(e);
+                return;
+            }
+        };
+        let limits = self.s3_client.limits();
+        if let Some(global) = changes.global {
+            limits.bandwidth.set_limit(global);
+        }
+        if let Some(requests) = changes.requests {
+            limits.requests.set_limit(requests);
+        }
+        if let Some(cap) = changes.job
+            && let Some(job) = self.selected_job()
+        {
+            job.control.bandwidth.set_limit(cap);
+        }
+        self.limits_prompt.active = false;
+    }
+
+    pub fn handle_action(&mut self, action: AppActions, app_mode: &mut AppMode) {
+        if self.limits_prompt.active {
+            match action {
+                AppActions::SearchInput(c) => {
+                    self.limits_prompt.input(c);
+                    self.limits_prompt.error = None;
+                }
+                AppActions::SearchDelete => {
+                    self.limits_prompt.delete();
+                    self.limits_prompt.error = None;
+                }
+                AppActions::Enter => self.apply_limits(),
+                AppActions::GoBack => self.limits_prompt.active = false,
+                _ => {}
+            }
+            return;
+        }
+
+        match action {
+            AppActions::SetLimits => self.open_limits(),
+            AppActions::GoBack | AppActions::ToggleJobs => *app_mode = self.return_mode,
+            AppActions::MoveDown => self.jobs.next(),
+            AppActions::MoveUp => self.jobs.previous(),
+            AppActions::MoveToTop => self.jobs.first(),
+            AppActions::MoveToBottom => self.jobs.last(),
+            AppActions::PauseJob => self.toggle_pause(),
+            AppActions::CancelJob => self.cancel(),
+            AppActions::Refresh => self.retry(),
+            _ => {}
+        }
+    }
+}
+
+/// ` (n skipped)` when items were skipped, otherwise nothing
+pub fn skipped_note(skipped: usize) -> String {
+    if skipped > 0 {
+        format!(" ({} skipped)", skipped)
+    } else {
+        String::new()
+    }
+}
+
+/// Run every item of `work`, a few at a time, reporting each outcome
+async fn run_job(
+    id: usize,
+    work: Arc<JobWork>,
+    control: Arc<JobControl>,
+    s3_client: Arc<S3Client>,
+    sender: mpsc::UnboundedSender<JobEvent>,
+) {
+    let mut running = JoinSet::new();
+    for index in 0..work.len() {
+        while running.len() >= work.concurrency(&s3_client) {
+            running.join_next().await;
+        }
+        // Once cancelled, the items not started yet stay undone
+        if control.checkpoint().await.is_err() {
+            break;
+        }
+
+        let work = work.clone();
+        let control = control.clone();
+        let s3_client = s3_client.clone();
+        let sender = sender.clone();
+        running.spawn(async move {
+            let event = match work.run_item(&s3_client, index, &control).await {
+                Ok(()) => JobEvent::ItemDone { id, index },
+                // Interrupted by the cancel rather than failed
+                Err(_) if control.signal() == Signal::Cancel => return,
+                Err(e) => JobEvent::ItemFailed {
+                    id,
+                    message: format!("{}: {}", work.label(index), e),
+                },
+            };
+            let _ = sender.send(event);
+        });
+    }
+    while running.join_next().await.is_some() {}
+    let _ = sender.send(JobEvent::Finished { id });
+}
+
+/// Delete every object version and delete marker in `bucket` as it is listed, so only one
+/// page is held at a time. Pages that fail are reported once the rest are done.
+async fn empty_bucket(
+    s3_client: &S3Client,
+    bucket: &str,
+    control: &JobControl,
+) -> color_eyre::Result<()> {
+    let mut marker = None;
+    let mut failed = None;
+    loop {
+        control.checkpoint().await?;
+        let (page, next) = s3_client.list_versions_page(bucket, marker).await?;
+        if !page.is_empty()
+            && let Err(e) = s3_client.delete_versions(bucket, &page).await
+        {
+            failed = Some(e);
+        }
+        match next {
+            Some(next) => marker = Some(next),
+            None => return failed.map_or(Ok(()), Err),
+        }
+    }
+}
+
+async fn download_item(
+    s3_client: &S3Client,
+    bucket: &str,
+    item: &DownloadItem,
+    policy: ConflictPolicy,
+    control: &JobControl,
+) -> color_eyre::Result<()> {
+    let parent = item
+        .path
+        .parent()
+        .ok_or_else(|| eyre!("{} has no parent directory", item.path.display()))?;
+    tokio::fs::create_dir_all(parent).await?;
+
+    // Checked again here, as the file may have appeared since the plan was shown
+    let path = match policy {
+        _ if !item.path.exists() => item.path.clone(),
+        ConflictPolicy::Overwrite => item.path.clone(),
+        ConflictPolicy::Rename => {
+            let file_name = item.path.file_name().unwrap_or_default().to_string_lossy();
+            create_unique_filepath(parent, &file_name)
+        }
+        ConflictPolicy::Skip => {
+            control.skipped.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+    };
+    s3_client
+        .download_to_file(bucket, &item.key, &path, control)
+        .await?;
+    Ok(())
+}
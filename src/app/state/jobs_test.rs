@@ -0,0 +1,200 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::app::state::download::{ConflictPolicy, DownloadItem};
+use crate::app::state::jobs::{JobStatus, JobsState, MAX_RUNNING_JOBS};
+use crate::aws::s3_client::S3Client;
+use aws_config::BehaviorVersion;
+use aws_sdk_s3::config::http::{HttpRequest, HttpResponse};
+use aws_sdk_s3::config::{Credentials, Region};
+use aws_sdk_s3::primitives::SdkBody;
+use aws_smithy_runtime_api::client::http::{
+    HttpConnector, HttpConnectorFuture, SharedHttpConnector, http_client_fn,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fake S3 endpoint that deletes any key except those containing "bad", and records
+    /// the DELETE requests it gets
+    #[derive(Debug, Clone, Default)]
+    struct FakeS3 {
+        deletes: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl HttpConnector for FakeS3 {
+        fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+            let uri = request.uri().to_string();
+            let (status, body) = match request.method() {
+                "DELETE" => {
+                    self.deletes.lock().unwrap().push(uri.clone());
+                    if uri.contains("bad") {
+                        (403, "<Error><Code>AccessDenied</Code></Error>")
+                    } else {
+                        (204, "")
+                    }
+                }
+                _ => (403, "<Error><Code>AccessDenied</Code></Error>"),
+            };
+            let response = HttpResponse::new(status.try_into().unwrap(), SdkBody::from(body));
+            HttpConnectorFuture::ready(Ok(response))
+        }
+    }
+
+    async fn jobs(fake: &FakeS3) -> JobsState {
+        let fake = fake.clone();
+        let config = aws_config::defaults(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("AKID", "SECRET", None, None, "test"))
+            .http_client(http_client_fn(move |_, _| {
+                SharedHttpConnector::new(fake.clone())
+            }))
+            .load()
+            .await;
+        JobsState::new(Arc::new(S3Client::new(&config)))
+    }
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    }
+
+    fn act(jobs: &mut JobsState, action: AppActions) {
+        let mut mode = AppMode::Jobs;
+        jobs.handle_action(action, &mut mode);
+    }
+
+    fn status(jobs: &JobsState, index: usize) -> JobStatus {
+        jobs.jobs.items[index].status
+    }
+
+    /// Poll until the job at `index` has finished
+    async fn settle(jobs: &mut JobsState, index: usize) {
+        for _ in 0..500 {
+            jobs.poll();
+            if !matches!(
+                status(jobs, index),
+                JobStatus::Queued | JobStatus::Running | JobStatus::Paused
+            ) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("job {} did not finish", index);
+    }
+
+    #[tokio::test]
+    async fn test_pause_resume_and_cancel() {
+        let mut jobs = jobs(&FakeS3::default()).await;
+        jobs.spawn_delete("bucket".to_string(), keys(&["a", "b"]));
+        assert_eq!(status(&jobs, 0), JobStatus::Running);
+
+        act(&mut jobs, AppActions::PauseJob);
+        assert_eq!(status(&jobs, 0), JobStatus::Paused);
+        act(&mut jobs, AppActions::PauseJob);
+        assert_eq!(status(&jobs, 0), JobStatus::Running);
+        act(&mut jobs, AppActions::PauseJob);
+
+        // A paused job that was started winds down before it counts as cancelled
+        act(&mut jobs, AppActions::CancelJob);
+        assert!(jobs.jobs.items[0].is_cancelling());
+        settle(&mut jobs, 0).await;
+        assert_eq!(status(&jobs, 0), JobStatus::Cancelled);
+        assert_eq!(jobs.jobs.items[0].done, 0);
+
+        // A finished job can no longer be paused
+        act(&mut jobs, AppActions::PauseJob);
+        assert_eq!(status(&jobs, 0), JobStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_queued_jobs_pause_and_cancel_without_starting() {
+        let mut jobs = jobs(&FakeS3::default()).await;
+        for key in ["a", "b", "c", "d"] {
+            jobs.spawn_delete("bucket".to_string(), keys(&[key]));
+        }
+        assert_eq!(status(&jobs, MAX_RUNNING_JOBS), JobStatus::Queued);
+
+        jobs.jobs.select(Some(MAX_RUNNING_JOBS));
+        act(&mut jobs, AppActions::PauseJob);
+        assert_eq!(status(&jobs, MAX_RUNNING_JOBS), JobStatus::Paused);
+        act(&mut jobs, AppActions::PauseJob);
+        assert_eq!(status(&jobs, MAX_RUNNING_JOBS), JobStatus::Queued);
+
+        jobs.jobs.select(Some(MAX_RUNNING_JOBS + 1));
+        act(&mut jobs, AppActions::CancelJob);
+        assert_eq!(status(&jobs, MAX_RUNNING_JOBS + 1), JobStatus::Cancelled);
+
+        // The queued job starts once a running one finishes; the cancelled one never does
+        settle(&mut jobs, MAX_RUNNING_JOBS).await;
+        assert_eq!(status(&jobs, MAX_RUNNING_JOBS), JobStatus::Completed);
+        assert_eq!(status(&jobs, MAX_RUNNING_JOBS + 1), JobStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_retry_runs_only_the_failed_items() {
+        let fake = FakeS3::default();
+        let mut jobs = jobs(&fake).await;
+        jobs.spawn_delete("bucket".to_string(), keys(&["ok1", "bad1", "ok2", "bad2"]));
+        settle(&mut jobs, 0).await;
+        assert_eq!(status(&jobs, 0), JobStatus::Failed);
+        assert_eq!(jobs.jobs.items[0].done, 2);
+        assert_eq!(jobs.jobs.items[0].failures.len(), 2);
+        assert!(jobs.retry_mutation().is_some());
+
+        fake.deletes.lock().unwrap().clear();
+        act(&mut jobs, AppActions::Refresh);
+        let job = &jobs.jobs.items[0];
+        assert_eq!((job.retries, job.total, job.done), (1, 2, 0));
+        assert!(job.failures.is_empty());
+
+        settle(&mut jobs, 0).await;
+        assert_eq!(status(&jobs, 0), JobStatus::Failed);
+        let mut deletes = fake.deletes.lock().unwrap().clone();
+        deletes.sort();
+        assert_eq!(deletes.len(), 2);
+        assert!(deletes[0].contains("/bad1") && deletes[1].contains("/bad2"));
+    }
+
+    #[tokio::test]
+    async fn test_completed_jobs_are_not_retried() {
+        let fake = FakeS3::default();
+        let mut jobs = jobs(&fake).await;
+        jobs.spawn_delete("bucket".to_string(), keys(&["ok"]));
+        assert!(jobs.retry_mutation().is_none());
+        settle(&mut jobs, 0).await;
+        assert_eq!(status(&jobs, 0), JobStatus::Completed);
+
+        assert!(jobs.retry_mutation().is_none());
+        act(&mut jobs, AppActions::Refresh);
+        assert_eq!(jobs.jobs.items[0].retries, 0);
+        assert_eq!(fake.deletes.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_skipped_downloads_count_as_done() {
+        let path = std::env::temp_dir().join(format!("jobs_skip_{}", std::process::id()));
+        std::fs::write(&path, "kept").unwrap();
+
+        let mut jobs = jobs(&FakeS3::default()).await;
+        let item = DownloadItem {
+            key: "report.csv".to_string(),
+            path: path.clone(),
+            size: Some(10),
+        };
+        jobs.spawn_download(
+            "Download".to_string(),
+            "bucket".to_string(),
+            vec![item],
+            ConflictPolicy::Skip,
+        );
+        settle(&mut jobs, 0).await;
+        let job = &jobs.jobs.items[0];
+        assert_eq!(job.status, JobStatus::Completed);
+        assert_eq!((job.done, job.skipped), (1, 1));
+        assert!(job.failures.is_empty());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "kept");
+        let _ = std::fs::remove_file(&path);
+    }
+}
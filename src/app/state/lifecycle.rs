@@ -0,0 +1,455 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::aws::s3_client::S3Client;
+use crate::lifecycle::{
+    AbortIncompleteUpload, LifecycleConfig, LifecycleRule, RuleFilter, format_expiration,
+    format_noncurrent_expiration, format_noncurrent_transitions, format_tags, format_transitions,
+    parse_days, parse_expiration, parse_noncurrent_expiration, parse_noncurrent_transitions,
+    parse_tags, parse_transitions,
+};
+use crate::search::SearchBar;
+use crate::ui::ScrollableList;
+use std::sync::Arc;
+
+/// Rows of the rule form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleField {
+    Id,
+    Status,
+    Prefix,
+    Tags,
+    Transitions,
+    Expiration,
+    NoncurrentTransitions,
+    NoncurrentExpiration,
+    AbortIncompleteUploads,
+    SaveRule,
+}
+
+const RULE_FIELDS: [RuleField; 10] = [
+    RuleField::Id,
+    RuleField::Status,
+    RuleField::Prefix,
+    RuleField::Tags,
+    RuleField::Transitions,
+    RuleField::Expiration,
+    RuleField::NoncurrentTransitions,
+    RuleField::NoncurrentExpiration,
+    RuleField::AbortIncompleteUploads,
+    RuleField::SaveRule,
+];
+
+/// A bucket's lifecycle rules, each described in words; rules are changed from a form or
+/// as JSON, and saved together once they validate
+#[derive(Debug)]
+pub struct LifecycleState {
+    pub bucket: String,
+    pub rules: ScrollableList<LifecycleRule>,
+    /// Whether the rules differ from the bucket's
+    pub modified: bool,
+    /// The rule form, shown over the list while a rule is added or changed
+    pub fields: ScrollableList<RuleField>,
+    pub editing: bool,
+    pub prompt: SearchBar,
+    /// The rule in the form
+    pub draft: LifecycleRule,
+    /// Where the rule in the form came from, or `None` for a new rule
+    draft_index: Option<usize>,
+    /// The last JSON that did not validate, opened again by the next edit
+    pub json_draft: Option<String>,
+    prompt_for: RuleField,
+    confirm_discard: bool,
+    s3_client: Arc<S3Client>,
+}
+
+impl LifecycleState {
+    pub fn new(s3_client: Arc<S3Client>) -> Self {
+        Self {
+            bucket: String::new(),
+            rules: ScrollableList::new("Lifecycle Rules"),
+            modified: false,
+            fields: ScrollableList::new("Lifecycle Rule"),
+            editing: false,
+            prompt: SearchBar::default(),
+            draft: LifecycleRule::default(),
+            draft_index: None,
+            json_draft: None,
+            prompt_for: RuleField::Id,
+            confirm_discard: false,
+            s3_client,
+        }
+    }
+
+    pub async fn open(&mut self, bucket: &str) {
+        self.bucket = bucket.to_string();
+        self.modified = false;
+        self.editing = false;
+        self.json_draft = None;
+        self.confirm_discard = false;
+        self.prompt.active = false;
+        self.prompt.clear();
+        match self.s3_client.get_bucket_lifecycle(bucket).await {
+            Ok(config) => {
+                self.show_rules(config.rules, 0);
+                if self.rules.is_empty() {
+                    self.rules.summary =
+                        Some("No lifecycle rules. a: Add a rule  e: Edit as JSON".to_string());
+                }
+            }
+            Err(e) => {
+                self.show_rules(Vec::new(), 0);
+                self.rules.summary = Some(e.to_string());
+            }
+        }
+    }
+
+    fn show_rules(&mut self, rules: Vec<LifecycleRule>, selected: usize) {
+        let title = if self.modified {
+            format!("Lifecycle of {} (modified, Ctrl+s: Save)", self.bucket)
+        } else {
+            format!("Lifecycle of {}", self.bucket)
+        };
+        self.rules = ScrollableList::new(title);
+        self.rules.filtered_indices = (0..rules.len()).collect();
+        self.rules.append_items(rules);
+        self.rules.set_has_more(false);
+        if !self.rules.is_empty() {
+            self.rules
+                .select(Some(selected.min(self.rules.items.len() - 1)));
+        }
+    }
+
+    fn config(&self) -> LifecycleConfig {
+        LifecycleConfig {
+            rules: self.rules.items.clone(),
+        }
+    }
+
+    /// How a rule reads in the list
+    pub fn rule_line(rule: &LifecycleRule) -> String {
+        format!(
+            "{}  {}",
+            rule.id.as_deref().unwrap_or("(no ID)"),
+            rule.describe()
+        )
+    }
+
+    pub fn label(&self, field: RuleField) -> String {
+        let rule = &self.draft;
+        let or_none = |text: String| {
+            if text.is_empty() {
+                "-".to_string()
+            } else {
+                text
+            }
+        };
+        match field {
+            RuleField::Id => format!("ID: {}", rule.id.as_deref().unwrap_or("-")),
+            RuleField::Status => format!("Status: {}", rule.status),
+            RuleField::Prefix => format!("Prefix: {}", rule.filter.prefix().unwrap_or("-")),
+            RuleField::Tags => format!("Tags: {}", or_none(format_tags(&rule.filter.tags()))),
+            RuleField::Transitions => format!(
+                "Transitions: {}",
+                or_none(format_transitions(&rule.transitions))
+            ),
+            RuleField::Expiration => format!(
+                "Expiration: {}",
+                or_none(format_expiration(rule.expiration.as_ref()))
+            ),
+            RuleField::NoncurrentTransitions => format!(
+                "Noncurrent transitions: {}",
+                or_none(format_noncurrent_transitions(
+                    &rule.noncurrent_version_transitions
+                ))
+            ),
+            RuleField::NoncurrentExpiration => format!(
+                "Noncurrent expiration: {}",
+                or_none(format_noncurrent_expiration(
+                    rule.noncurrent_version_expiration.as_ref()
+                ))
+            ),
+            RuleField::AbortIncompleteUploads => format!(
+                "Abort incomplete uploads after days: {}",
+                rule.abort_incomplete_multipart_upload
+                    .as_ref()
+                    .map_or("-".to_string(), |a| a.days_after_initiation.to_string())
+            ),
+            RuleField::SaveRule => "Save rule".to_string(),
+        }
+    }
+
+    pub fn prompt_help(&self) -> &'static str {
+        match self.prompt_for {
+            RuleField::Id => "Type the rule ID  Enter: Apply  Esc: Cancel",
+            RuleField::Prefix => {
+                "Type the key prefix, empty for all objects  Enter: Apply  Esc: Cancel"
+            }
+            RuleField::Tags => "Type tags as key=value,key2=value2  Enter: Apply  Esc: Cancel",
+            RuleField::Transitions => {
+                "Type DAYS:CLASS or YYYY-MM-DD:CLASS, e.g. 30:STANDARD_IA 90:GLACIER  Enter: Apply  Esc: Cancel"
+            }
+            RuleField::Expiration => {
+                "Type days, a YYYY-MM-DD date, markers (remove expired delete markers) or off  Enter: Apply  Esc: Cancel"
+            }
+            RuleField::NoncurrentTransitions => {
+                "Type DAYS:CLASS for noncurrent versions, e.g. 30:GLACIER  Enter: Apply  Esc: Cancel"
+            }
+            RuleField::NoncurrentExpiration => {
+                "Type days, optionally keep=N newer versions, e.g. 90 keep=3, or off  Enter: Apply  Esc: Cancel"
+            }
+            RuleField::AbortIncompleteUploads => "Type days or off  Enter: Apply  Esc: Cancel",
+            RuleField::Status | RuleField::SaveRule => "Enter: Apply  Esc: Cancel",
+        }
+    }
+
+    /// Open the form on a copy of `rule`, or on a new enabled rule
+    fn open_form(&mut self, index: Option<usize>) {
+        self.draft_index = index;
+        self.draft = match index.and_then(|i| self.rules.items.get(i)) {
+            Some(rule) => {
+                self.fields = ScrollableList::new("Edit Lifecycle Rule");
+                rule.clone()
+            }
+            None => {
+                self.fields = ScrollableList::new("New Lifecycle Rule");
+                LifecycleRule {
+                    id: Some(format!("rule-{}", self.rules.items.len() + 1)),
+                    status: "Enabled".to_string(),
+                    ..LifecycleRule::default()
+                }
+            }
+        };
+        self.fields.filtered_indices = (0..RULE_FIELDS.len()).collect();
+        self.fields.append_items(RULE_FIELDS.to_vec());
+        self.fields.set_has_more(false);
+        self.fields.first();
+        self.editing = true;
+    }
+
+    fn start_prompt(&mut self, field: RuleField) {
+        let rule = &self.draft;
+        let initial = match field {
+            RuleField::Id => rule.id.clone().unwrap_or_default(),
+            RuleField::Prefix => rule.filter.prefix().unwrap_or_default().to_string(),
+            RuleField::Tags => format_tags(&rule.filter.tags()),
+            RuleField::Transitions => format_transitions(&rule.transitions),
+            RuleField::Expiration => format_expiration(rule.expiration.as_ref()),
+            RuleField::NoncurrentTransitions => {
+                format_noncurrent_transitions(&rule.noncurrent_version_transitions)
+            }
+            RuleField::NoncurrentExpiration => {
+                format_noncurrent_expiration(rule.noncurrent_version_expiration.as_ref())
+            }
+            RuleField::AbortIncompleteUploads => rule
+                .abort_incomplete_multipart_upload
+                .as_ref()
+                .map_or(String::new(), |a| a.days_after_initiation.to_string()),
+            RuleField::Status | RuleField::SaveRule => return,
+        };
+        self.prompt_for = field;
+        self.prompt.set_query(&initial);
+        self.prompt.error = None;
+        self.prompt.active = true;
+    }
+
+    /// Apply the prompt to the rule in the form
+    fn commit_prompt(&mut self) -> Result<(), String> {
+        let input = self.prompt.query.trim();
+        let rule = &mut self.draft;
+        let (min_size, max_size) = rule.filter.size_range();
+        match self.prompt_for {
+            RuleField::Id => rule.id = Some(input.to_string()).filter(|id| !id.is_empty()),
+            RuleField::Prefix => {
+                let prefix = Some(input.to_string()).filter(|prefix| !prefix.is_empty());
+                rule.filter = RuleFilter::new(prefix, rule.filter.tags(), min_size, max_size);
+            }
+            RuleField::Tags => {
+                let prefix = rule.filter.prefix().map(String::from);
+                rule.filter = RuleFilter::new(prefix, parse_tags(input)?, min_size, max_size);
+            }
+            RuleField::Transitions => rule.transitions = parse_transitions(input)?,
+            RuleField::Expiration => rule.expiration = parse_expiration(input)?,
+            RuleField::NoncurrentTransitions => {
+                rule.noncurrent_version_transitions = parse_noncurrent_transitions(input)?
+            }
+            RuleField::NoncurrentExpiration => {
+                rule.noncurrent_version_expiration = parse_noncurrent_expiration(input)?
+            }
+            RuleField::AbortIncompleteUploads => {
+                rule.abort_incomplete_multipart_upload =
+                    parse_days(input)?.map(|days| AbortIncompleteUpload {
+                        days_after_initiation: days,
+                    })
+            }
+            RuleField::Status | RuleField::SaveRule => {}
+        }
+        Ok(())
+    }
+
+    /// Put the rule in the form into the list, if it validates
+    fn save_rule(&mut self) {
+        if let Err(e) = self.draft.validate() {
+            self.fields.summary = Some(e);
+            return;
+        }
+        let duplicate = self.rules.items.iter().enumerate().any(|(index, rule)| {
+            Some(index) != self.draft_index && rule.id.is_some() && rule.id == self.draft.id
+        });
+        if duplicate {
+            self.fields.summary = Some("Another rule has this ID".to_string());
+            return;
+        }
+
+        let mut rules = self.rules.items.clone();
+        let index = match self.draft_index.filter(|&i| i < rules.len()) {
+            Some(index) => {
+                rules[index] = self.draft.clone();
+                index
+            }
+            None => {
+                rules.push(self.draft.clone());
+                rules.len() - 1
+            }
+        };
+        self.modified = true;
+        self.editing = false;
+        self.show_rules(rules, index);
+    }
+
+    fn remove_rule(&mut self) {
+        let Some(index) = self.rules.selected_index() else {
+            return;
+        };
+        let mut rules = self.rules.items.clone();
+        if index < rules.len() {
+            rules.remove(index);
+            self.modified = true;
+            self.show_rules(rules, index);
+        }
+    }
+
+    /// What the editor starts with: the last JSON that failed, or the rules in the list
+    pub fn edit_text(&self) -> String {
+        self.json_draft
+            .clone()
+            .unwrap_or_else(|| self.config().to_json())
+    }
+
+    /// Replace the rules with edited JSON if it validates, returning a status message
+    pub fn apply_json(&mut self, edited: String) -> String {
+        match LifecycleConfig::parse(&edited) {
+            Ok(config) => {
+                self.json_draft = None;
+                if config == self.config() {
+                    return "No changes to the lifecycle rules".to_string();
+                }
+                self.modified = true;
+                self.show_rules(config.rules, 0);
+                "Rules updated; Ctrl+s: Save them to the bucket".to_string()
+            }
+            Err(e) => {
+                self.json_draft = Some(edited);
+                self.rules.summary = Some(format!("Not valid: {}. e: Fix it", e));
+                format!("The lifecycle rules were not changed: {}", e)
+            }
+        }
+    }
+
+    async fn save(&mut self) {
+        let config = self.config();
+        if let Err(e) = config.validate() {
+            self.rules.summary = Some(e);
+            return;
+        }
+        match self
+            .s3_client
+            .put_bucket_lifecycle(&self.bucket, &config)
+            .await
+        {
+            Ok(()) => {
+                self.modified = false;
+                self.confirm_discard = false;
+                let selected = self.rules.selected_index().unwrap_or_default();
+                self.show_rules(config.rules, selected);
+                self.rules.summary = Some("Saved the lifecycle rules".to_string());
+            }
+            Err(e) => self.rules.summary = Some(e.to_string()),
+        }
+    }
+
+    pub async fn handle_action(&mut self, action: AppActions, app_mode: &mut AppMode) {
+        if self.prompt.active {
+            match action {
+                AppActions::SearchInput(c) => {
+                    self.prompt.input(c);
+                    self.prompt.error = None;
+                }
+                AppActions::SearchDelete => {
+                    self.prompt.delete();
+                    self.prompt.error = None;
+                }
+                AppActions::Enter => match self.commit_prompt() {
+                    Ok(()) => {
+                        self.prompt.active = false;
+                        self.prompt.clear();
+                        self.fields.summary = None;
+                    }
+                    Err(e) => self.prompt.error = Some(e),
+                },
+                AppActions::GoBack => {
+                    self.prompt.active = false;
+                    self.prompt.clear();
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if self.editing {
+            match action {
+                AppActions::MoveDown => self.fields.next(),
+                AppActions::MoveUp => self.fields.previous(),
+                AppActions::MoveToTop => self.fields.first(),
+                AppActions::MoveToBottom => self.fields.last(),
+                AppActions::Enter => match self.fields.selected_item().copied() {
+                    Some(RuleField::Status) => {
+                        self.draft.status = if self.draft.status == "Enabled" {
+                            "Disabled".to_string()
+                        } else {
+                            "Enabled".to_string()
+                        };
+                    }
+                    Some(RuleField::SaveRule) => self.save_rule(),
+                    Some(field) => self.start_prompt(field),
+                    None => {}
+                },
+                AppActions::GoBack => self.editing = false,
+                _ => {}
+            }
+            return;
+        }
+
+        if !matches!(action, AppActions::GoBack) {
+            self.confirm_discard = false;
+        }
+        match action {
+            AppActions::MoveDown => self.rules.next(),
+            AppActions::MoveUp => self.rules.previous(),
+            AppActions::MoveToTop => self.rules.first(),
+            AppActions::MoveToBottom => self.rules.last(),
+            AppActions::Add => self.open_form(None),
+            AppActions::Enter if !self.rules.is_empty() => {
+                self.open_form(self.rules.selected_index())
+            }
+            AppActions::Delete => self.remove_rule(),
+            AppActions::Save => self.save().await,
+            AppActions::GoBack if self.modified && !self.confirm_discard => {
+                self.confirm_discard = true;
+                self.rules.summary =
+                    Some("Unsaved changes. Ctrl+s: Save  Esc: Discard them".to_string());
+            }
+            AppActions::GoBack => *app_mode = AppMode::BucketList,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,456 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::app::state::jobs::JobsState;
+use crate::aws::s3_client::{ObjectHeaders, S3Client};
+use crate::search::SearchBar;
+use crate::ui::ScrollableList;
+use aws_sdk_s3::primitives::{DateTime, DateTimeFormat};
+use std::sync::Arc;
+
+/// Content headers that can be edited, in display order
+const SYSTEM_HEADERS: [&str; 7] = [
+    "Content-Type",
+    "Cache-Control",
+    "Content-Encoding",
+    "Content-Disposition",
+    "Content-Language",
+    "Expires",
+    "x-amz-website-redirect-location",
+];
+const USER_METADATA_PREFIX: &str = "x-amz-meta-";
+
+/// A header in the editor; in bulk mode a missing value removes it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataEntry {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+/// One header of one object that the confirmed edit will change
+#[derive(Debug, Clone)]
+pub struct MetadataChange {
+    pub key: String,
+    pub name: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// The headers to write to one object, guarded by the ETag seen in the preview
+#[derive(Debug, Clone)]
+pub struct HeaderUpdate {
+    pub key: String,
+    pub etag: Option<String>,
+    pub headers: ObjectHeaders,
+}
+
+/// Canonical spelling of a header name, or an error for headers that cannot be edited
+fn normalize_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if let Some(header) = SYSTEM_HEADERS
+        .iter()
+        .find(|header| header.eq_ignore_ascii_case(name))
+    {
+        return Ok(header.to_string());
+    }
+
+    let lower = name.to_ascii_lowercase();
+    match lower.strip_prefix(USER_METADATA_PREFIX) {
+        Some("") => Err("missing user metadata name".to_string()),
+        Some(user) if user.chars().any(|c| c.is_whitespace() || c == ':') => {
+            Err(format!("invalid metadata name '{}'", name))
+        }
+        // S3 stores user metadata names in lower case
+        Some(_) => Ok(lower),
+        None => Err(format!(
+            "unknown header '{}'; use {}<name> for user metadata",
+            name, USER_METADATA_PREFIX
+        )),
+    }
+}
+
+/// Checks values that S3 would reject or silently drop
+fn check_value(name: &str, value: &str) -> Result<(), String> {
+    match name {
+        "Expires" if DateTime::from_str(value, DateTimeFormat::HttpDate).is_err() => {
+            Err("Expires must be an HTTP date, e.g. Wed, 21 Oct 2026 07:28:00 GMT".to_string())
+        }
+        "x-amz-website-redirect-location"
+            if !["/", "http://", "https://"]
+                .iter()
+                .any(|start| value.starts_with(start)) =>
+        {
+            Err("redirect location must start with /, http:// or https://".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Parses `Name: value`, or `-Name` to remove a header when `allow_removal` is set
+pub fn parse_entry(input: &str, allow_removal: bool) -> Result<MetadataEntry, String> {
+    let input = input.trim();
+    if let Some(name) = input.strip_prefix('-').filter(|_| allow_removal) {
+        return Ok(MetadataEntry {
+            name: normalize_name(name)?,
+            value: None,
+        });
+    }
+
+    let (name, value) = input
+        .split_once(':')
+        .ok_or_else(|| "expected Name: value".to_string())?;
+    let (name, value) = (normalize_name(name)?, value.trim());
+    check_value(&name, value)?;
+    Ok(MetadataEntry {
+        name,
+        value: Some(value.to_string()),
+    })
+}
+
+/// Headers as editor fields: content headers in display order, then sorted user metadata
+pub fn header_fields(headers: &ObjectHeaders) -> Vec<(String, String)> {
+    let system = [
+        &headers.content_type,
+        &headers.cache_control,
+        &headers.content_encoding,
+        &headers.content_disposition,
+        &headers.content_language,
+        &headers.expires,
+        &headers.website_redirect_location,
+    ];
+    let mut fields: Vec<(String, String)> = SYSTEM_HEADERS
+        .iter()
+        .zip(system)
+        .filter_map(|(name, value)| value.clone().map(|value| (name.to_string(), value)))
+        .collect();
+
+    let mut user: Vec<(String, String)> = headers
+        .metadata
+        .iter()
+        .map(|(name, value)| (format!("{}{}", USER_METADATA_PREFIX, name), value.clone()))
+        .collect();
+    user.sort();
+    fields.extend(user);
+    fields
+}
+
+pub fn headers_from_fields(fields: &[(String, String)]) -> ObjectHeaders {
+    let mut headers = ObjectHeaders::default();
+    for (name, value) in fields {
+        let slot = match name.as_str() {
+            "Content-Type" => &mut headers.content_type,
+            "Cache-Control" => &mut headers.cache_control,
+            "Content-Encoding" => &mut headers.content_encoding,
+            "Content-Disposition" => &mut headers.content_disposition,
+            "Content-Language" => &mut headers.content_language,
+            "Expires" => &mut headers.expires,
+            "x-amz-website-redirect-location" => &mut headers.website_redirect_location,
+            _ => {
+                if let Some(user) = name.strip_prefix(USER_METADATA_PREFIX) {
+                    headers.metadata.insert(user.to_string(), value.clone());
+                }
+                continue;
+            }
+        };
+        *slot = Some(value.clone());
+    }
+    headers
+}
+
+fn field_value<'a>(fields: &'a [(String, String)], name: &str) -> Option<&'a String> {
+    fields
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, value)| value)
+}
+
+/// Editor for content headers and user metadata of the selected or marked objects
+#[derive(Debug)]
+pub struct MetadataState {
+    pub fields: ScrollableList<MetadataEntry>,
+    pub changes: ScrollableList<MetadataChange>,
+    pub prompt: SearchBar,
+    pub bucket: String,
+    pub keys: Vec<String>,
+    /// Showing the before/after confirmation rather than the editor
+    pub confirming: bool,
+    updates: Vec<HeaderUpdate>,
+    editing: Option<usize>,
+    s3_client: Arc<S3Client>,
+}
+
+impl MetadataState {
+    pub fn new(s3_client: Arc<S3Client>) -> Self {
+        Self {
+            fields: ScrollableList::new("Metadata"),
+            changes: ScrollableList::new("Confirm Metadata Changes"),
+            prompt: SearchBar::default(),
+            bucket: String::new(),
+            keys: Vec::new(),
+            confirming: false,
+            updates: Vec::new(),
+            editing: None,
+            s3_client,
+        }
+    }
+
+    pub fn is_bulk(&self) -> bool {
+        self.keys.len() > 1
+    }
+
+    /// Open the editor with one object's current headers, or blank for a bulk edit
+    pub async fn open(&mut self, bucket: &str, keys: Vec<String>) {
+        self.bucket = bucket.to_string();
+        self.keys = keys;
+        self.confirming = false;
+        self.editing = None;
+        self.updates.clear();
+        self.prompt.active = false;
+        self.prompt.clear();
+
+        let title = match self.keys.as_slice() {
+            [key] => format!("Metadata: {}", key),
+            keys => format!("Bulk metadata edit: {} marked objects", keys.len()),
+        };
+        self.fields = ScrollableList::new(title);
+        self.fields.set_has_more(false);
+
+        if self.is_bulk() {
+            self.fields.summary = Some(
+                "Headers added here are set on every marked object; add -Name to remove one"
+                    .to_string(),
+            );
+            return;
+        }
+
+        let Some(key) = self.keys.first() else {
+            return;
+        };
+        match self.s3_client.head_object(&self.bucket, key).await {
+            Ok(head) => {
+                let entries = header_fields(&ObjectHeaders::from_head(&head))
+                    .into_iter()
+                    .map(|(name, value)| MetadataEntry {
+                        name,
+                        value: Some(value),
+                    })
+                    .collect();
+                self.set_entries(entries);
+                self.fields.first();
+            }
+            Err(e) => self.fields.summary = Some(e.to_string()),
+        }
+    }
+
+    fn set_entries(&mut self, entries: Vec<MetadataEntry>) {
+        let selected = self.fields.selected_index();
+        self.fields.items.clear();
+        self.fields.append_items(entries);
+        self.fields.filtered_indices = (0..self.fields.items.len()).collect();
+        match selected {
+            Some(index) if index < self.fields.items.len() => self.fields.select(Some(index)),
+            _ => self.fields.last(),
+        }
+    }
+
+    /// Add or replace the entry typed in the prompt
+    fn commit_prompt(&mut self) {
+        let entry = match parse_entry(&self.prompt.query, self.is_bulk()) {
+            Ok(entry) => entry,
+            Err(e) => {
+                self.prompt.error = Some(e);
+                return;
+            }
+        };
+
+        let mut entries = self.fields.items.clone();
+        if let Some(index) = self.editing {
+            entries.remove(index);
+        }
+        match entries.iter().position(|e| e.name == entry.name) {
+            Some(existing) => entries[existing] = entry,
+            None => entries.insert(self.editing.unwrap_or(entries.len()), entry),
+        }
+
+        self.set_entries(entries);
+        self.prompt.active = false;
+        self.prompt.clear();
+        self.fields.summary = Some("Unsaved changes  Ctrl+s: Review".to_string());
+    }
+
+    /// Apply the edit to `before`: entries set or remove headers, and in single-object mode a
+    /// header missing from the editor is removed
+    fn apply_edit(&self, before: &[(String, String)]) -> Vec<(String, String)> {
+        let mut after: Vec<(String, String)> = before
+            .iter()
+            .filter(|(name, _)| {
+                let entry = self.fields.items.iter().find(|e| &e.name == name);
+                match entry {
+                    Some(entry) => entry.value.is_some(),
+                    None => self.is_bulk(),
+                }
+            })
+            .cloned()
+            .collect();
+
+        for entry in &self.fields.items {
+            let Some(value) = &entry.value else { continue };
+            match after.iter_mut().find(|(name, _)| *name == entry.name) {
+                Some(field) => field.1 = value.clone(),
+                None => after.push((entry.name.clone(), value.clone())),
+            }
+        }
+        after
+    }
+
+    /// Read every object's current headers and list what the edit would change
+    async fn review(&mut self) {
+        let mut changes = Vec::new();
+        let mut updates = Vec::new();
+        let mut errors = Vec::new();
+
+        for key in &self.keys {
+            let head = match self.s3_client.head_object(&self.bucket, key).await {
+                Ok(head) => head,
+                Err(e) => {
+                    errors.push(e.to_string());
+                    continue;
+                }
+            };
+            let before = header_fields(&ObjectHeaders::from_head(&head));
+            let after = self.apply_edit(&before);
+
+            let mut names: Vec<&String> = Vec::new();
+            for (name, _) in before.iter().chain(after.iter()) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            let mut changed = false;
+            for name in names {
+                let (old, new) = (field_value(&before, name), field_value(&after, name));
+                if old != new {
+                    changed = true;
+                    changes.push(MetadataChange {
+                        key: key.clone(),
+                        name: name.clone(),
+                        before: old.cloned(),
+                        after: new.cloned(),
+                    });
+                }
+            }
+            if changed {
+                updates.push(HeaderUpdate {
+                    key: key.clone(),
+                    etag: head.e_tag().map(String::from),
+                    headers: headers_from_fields(&after),
+                });
+            }
+        }
+
+        let mut summary = format!(
+            "{} object(s) will be rewritten in place; tags, storage class, encryption and ACLs are kept. Enter: Apply  Esc: Edit",
+            updates.len()
+        );
+        if let Some(error) = errors.last() {
+            summary = format!(
+                "{} ({} could not be read, last: {})",
+                summary,
+                errors.len(),
+                error
+            );
+        }
+
+        self.changes = ScrollableList::new(format!("Confirm Metadata Changes in {}", self.bucket));
+        self.changes.set_has_more(false);
+        self.changes.summary = Some(summary);
+        self.changes.append_items(changes);
+        self.changes.filtered_indices = (0..self.changes.items.len()).collect();
+        self.changes.first();
+        self.updates = updates;
+        self.confirming = true;
+    }
+
+    pub async fn handle_action(
+        &mut self,
+        action: AppActions,
+        app_mode: &mut AppMode,
+        jobs: &mut JobsState,
+    ) -> color_eyre::Result<()> {
+        if self.prompt.active {
+            match action {
+                AppActions::SearchInput(c) => {
+                    self.prompt.input(c);
+                    self.prompt.error = None;
+                }
+                AppActions::SearchDelete => {
+                    self.prompt.delete();
+                    self.prompt.error = None;
+                }
+                AppActions::Enter => self.commit_prompt(),
+                AppActions::GoBack => {
+                    self.prompt.active = false;
+                    self.prompt.clear();
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        if self.confirming {
+            match action {
+                AppActions::MoveDown => self.changes.next(),
+                AppActions::MoveUp => self.changes.previous(),
+                AppActions::MoveToTop => self.changes.first(),
+                AppActions::MoveToBottom => self.changes.last(),
+                AppActions::GoBack => self.confirming = false,
+                AppActions::Enter => {
+                    if !self.updates.is_empty() {
+                        let updates = std::mem::take(&mut self.updates);
+                        jobs.spawn_header_updates(self.bucket.clone(), updates);
+                    }
+                    self.confirming = false;
+                    *app_mode = AppMode::ObjectList;
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match action {
+            AppActions::MoveDown => self.fields.next(),
+            AppActions::MoveUp => self.fields.previous(),
+            AppActions::MoveToTop => self.fields.first(),
+            AppActions::MoveToBottom => self.fields.last(),
+            AppActions::Add => {
+                self.editing = None;
+                self.prompt.clear();
+                self.prompt.active = true;
+            }
+            AppActions::Enter => {
+                if let Some(index) = self.fields.selected_index() {
+                    let entry = &self.fields.items[index];
+                    let text = match &entry.value {
+                        Some(value) => format!("{}: {}", entry.name, value),
+                        None => format!("-{}", entry.name),
+                    };
+                    self.editing = Some(index);
+                    self.prompt.set_query(&text);
+                    self.prompt.active = true;
+                }
+            }
+            AppActions::Delete => {
+                if let Some(index) = self.fields.selected_index() {
+                    let mut entries = self.fields.items.clone();
+                    entries.remove(index);
+                    self.set_entries(entries);
+                    self.fields.summary = Some("Unsaved changes  Ctrl+s: Review".to_string());
+                }
+            }
+            AppActions::Save => self.review().await,
+            AppActions::GoBack => *app_mode = AppMode::ObjectList,
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use crate::app::state::metadata::{MetadataEntry, header_fields, headers_from_fields, parse_entry};
+use crate::aws::s3_client::ObjectHeaders;
+use aws_sdk_s3::operation::head_object::HeadObjectOutput;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, value: Option<&str>) -> MetadataEntry {
+        MetadataEntry {
+            name: name.to_string(),
+            value: value.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        assert_eq!(
+            parse_entry(" content-type : text/plain ", false),
+            Ok(entry("Content-Type", Some("text/plain")))
+        );
+        assert_eq!(
+            parse_entry("X-Amz-Meta-Owner: ops", false),
+            Ok(entry("x-amz-meta-owner", Some("ops")))
+        );
+        assert_eq!(
+            parse_entry("-cache-control", true),
+            Ok(entry("Cache-Control", None))
+        );
+        assert!(parse_entry("-cache-control", false).is_err());
+        assert!(parse_entry("Content-Type", false).is_err());
+        assert!(parse_entry("x-amz-meta-: x", false).is_err());
+        assert!(parse_entry("Server: nginx", false).is_err());
+    }
+
+    #[test]
+    fn test_parse_expires_and_redirect() {
+        assert_eq!(
+            parse_entry("expires: Wed, 21 Oct 2026 07:28:00 GMT", false),
+            Ok(entry("Expires", Some("Wed, 21 Oct 2026 07:28:00 GMT")))
+        );
+        assert!(parse_entry("Expires: tomorrow", false).is_err());
+
+        assert_eq!(
+            parse_entry("X-Amz-Website-Redirect-Location: /new/page.html", false),
+            Ok(entry(
+                "x-amz-website-redirect-location",
+                Some("/new/page.html")
+            ))
+        );
+        assert!(
+            parse_entry(
+                "x-amz-website-redirect-location: https://example.com",
+                false
+            )
+            .is_ok()
+        );
+        assert!(parse_entry("x-amz-website-redirect-location: page.html", false).is_err());
+    }
+
+    #[test]
+    fn test_headers_round_trip_through_fields() {
+        let head = HeadObjectOutput::builder()
+            .content_type("text/html")
+            .expires_string("Wed, 21 Oct 2026 07:28:00 GMT")
+            .website_redirect_location("/index.html")
+            .metadata("owner", "ops")
+            .build();
+        let headers = ObjectHeaders::from_head(&head);
+        assert_eq!(
+            headers.expires_date().unwrap().map(|date| date.secs()),
+            Some(1_792_567_680)
+        );
+
+        let fields = header_fields(&headers);
+        let names: Vec<&str> = fields.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Content-Type",
+                "Expires",
+                "x-amz-website-redirect-location",
+                "x-amz-meta-owner"
+            ]
+        );
+        assert_eq!(headers_from_fields(&fields), headers);
+    }
+
+    #[test]
+    fn test_unparsable_expires_is_an_error() {
+        let head = HeadObjectOutput::builder().expires_string("never").build();
+        assert!(ObjectHeaders::from_head(&head).expires_date().is_err());
+        assert!(ObjectHeaders::default().expires_date().unwrap().is_none());
+    }
+}
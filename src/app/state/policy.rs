@@ -0,0 +1,198 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::aws::s3_client::S3Client;
+use crate::policy::{Change, Policy, diff_lines, policy_template};
+use crate::ui::ScrollableList;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyLineKind {
+    Warning,
+    Text,
+    /// A line of the policy with a wildcard principal
+    Wildcard,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyLine {
+    pub kind: PolicyLineKind,
+    pub text: String,
+}
+
+/// `"Principal": "*"` or `"AWS": "*"` as pretty-printed JSON puts them
+fn is_wildcard_line(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with(r#""Principal": "*""#) || line.starts_with(r#""AWS": "*""#)
+}
+
+/// A bucket's policy as pretty-printed JSON; edits are validated and shown as a diff
+/// before they are saved
+#[derive(Debug)]
+pub struct PolicyState {
+    pub bucket: String,
+    pub lines: ScrollableList<PolicyLine>,
+    /// The saved policy, pretty-printed, or `None` if the bucket has none
+    pub current: Option<String>,
+    /// The last edit, opened again by the next edit so a mistake can be fixed
+    pub draft: Option<String>,
+    /// A valid edit waiting for review
+    pub pending: Option<Policy>,
+    s3_client: Arc<S3Client>,
+}
+
+impl PolicyState {
+    pub fn new(s3_client: Arc<S3Client>) -> Self {
+        Self {
+            bucket: String::new(),
+            lines: ScrollableList::new("Bucket Policy"),
+            current: None,
+            draft: None,
+            pending: None,
+            s3_client,
+        }
+    }
+
+    pub async fn open(&mut self, bucket: &str) {
+        self.bucket = bucket.to_string();
+        self.current = None;
+        self.draft = None;
+        self.pending = None;
+        match self.s3_client.get_bucket_policy(bucket).await {
+            Ok(policy) => {
+                // Policies S3 accepted but this grammar does not are shown as they are
+                self.current = policy.map(|text| Policy::parse(&text).map_or(text, |p| p.pretty));
+                self.show_current();
+            }
+            Err(e) => {
+                self.show_lines(Vec::new(), Vec::new());
+                self.lines.summary = Some(e.to_string());
+            }
+        }
+    }
+
+    fn show_lines(&mut self, warnings: Vec<String>, lines: Vec<PolicyLine>) {
+        let title = if self.pending.is_some() {
+            format!("Policy of {} (review changes)", self.bucket)
+        } else {
+            format!("Policy of {}", self.bucket)
+        };
+        let warnings = warnings.into_iter().map(|text| PolicyLine {
+            kind: PolicyLineKind::Warning,
+            text,
+        });
+        self.lines = ScrollableList::new(title);
+        self.lines.append_items(warnings.chain(lines).collect());
+        self.lines.filtered_indices = (0..self.lines.items.len()).collect();
+        self.lines.set_has_more(false);
+        self.lines.first();
+    }
+
+    fn show_current(&mut self) {
+        let Some(current) = self.current.clone() else {
+            self.show_lines(Vec::new(), Vec::new());
+            self.lines.summary = Some("No bucket policy. e: Create one".to_string());
+            return;
+        };
+        let warnings = Policy::parse(&current)
+            .map(|policy| policy.warnings)
+            .unwrap_or_default();
+        let lines = current
+            .lines()
+            .map(|line| PolicyLine {
+                kind: if is_wildcard_line(line) {
+                    PolicyLineKind::Wildcard
+                } else {
+                    PolicyLineKind::Text
+                },
+                text: line.to_string(),
+            })
+            .collect();
+        self.show_lines(warnings, lines);
+    }
+
+    /// What the editor starts with: the last edit, the saved policy or a template
+    pub fn edit_text(&self) -> String {
+        self.draft
+            .clone()
+            .or_else(|| self.current.clone())
+            .unwrap_or_else(|| policy_template(&self.bucket))
+    }
+
+    /// Check an edit and show it as a diff against the saved policy, returning a status message
+    pub fn review(&mut self, edited: String) -> String {
+        let policy = match Policy::parse(&edited) {
+            Ok(policy) => policy,
+            Err(e) => {
+                self.draft = Some(edited);
+                self.lines.summary = Some(format!("Not valid: {}. e: Fix it", e));
+                return format!("The policy was not saved: {}", e);
+            }
+        };
+        if self.current.as_deref() == Some(policy.pretty.as_str()) {
+            self.draft = None;
+            return "No changes to the policy".to_string();
+        }
+
+        let old = self.current.clone().unwrap_or_default();
+        let lines = diff_lines(&old, &policy.pretty)
+            .into_iter()
+            .map(|(change, text)| PolicyLine {
+                kind: match change {
+                    Change::Added => PolicyLineKind::Added,
+                    Change::Removed => PolicyLineKind::Removed,
+                    Change::Same if is_wildcard_line(&text) => PolicyLineKind::Wildcard,
+                    Change::Same => PolicyLineKind::Text,
+                },
+                text,
+            })
+            .collect();
+        let warnings = policy.warnings.clone();
+        self.draft = Some(edited);
+        self.pending = Some(policy);
+        self.show_lines(warnings, lines);
+        self.lines.summary =
+            Some("Enter: Save the policy  e: Edit again  Esc: Discard".to_string());
+        "Review the policy changes".to_string()
+    }
+
+    async fn apply(&mut self) {
+        let Some(policy) = self.pending.take() else {
+            return;
+        };
+        match self
+            .s3_client
+            .put_bucket_policy(&self.bucket, &policy.pretty)
+            .await
+        {
+            Ok(()) => {
+                self.current = Some(policy.pretty);
+                self.draft = None;
+                self.show_current();
+                self.lines.summary = Some("Saved the policy".to_string());
+            }
+            Err(e) => {
+                self.pending = Some(policy);
+                self.lines.summary = Some(e.to_string());
+            }
+        }
+    }
+
+    pub async fn handle_action(&mut self, action: AppActions, app_mode: &mut AppMode) {
+        match action {
+            AppActions::MoveDown => self.lines.next(),
+            AppActions::MoveUp => self.lines.previous(),
+            AppActions::MoveToTop => self.lines.first(),
+            AppActions::MoveToBottom => self.lines.last(),
+            AppActions::Enter => self.apply().await,
+            AppActions::GoBack if self.pending.is_some() => {
+                self.pending = None;
+                self.draft = None;
+                self.show_current();
+            }
+            AppActions::GoBack => *app_mode = AppMode::BucketList,
+            _ => {}
+        }
+    }
+}
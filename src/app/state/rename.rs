@@ -0,0 +1,229 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::app::state::jobs::{JobKind, JobsState, TransferItem};
+use crate::aws::s3_client::{S3Client, list_error_reason};
+use crate::search::SearchBar;
+use crate::ui::ScrollableList;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// What is being renamed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenameSource {
+    Key(String),
+    /// Every key under a "folder" prefix ending in `/`
+    Prefix(String),
+}
+
+impl RenameSource {
+    pub fn old_name(&self) -> &str {
+        match self {
+            Self::Key(key) | Self::Prefix(key) => key,
+        }
+    }
+}
+
+/// One line of the dry-run preview
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenameEntry {
+    pub old_key: String,
+    pub new_key: String,
+    /// The new key already exists, so this entry is skipped rather than overwriting it
+    pub conflict: bool,
+}
+
+/// The new name as typed, normalised so folder renames stay folders
+pub fn target_name(source: &RenameSource, typed: &str) -> String {
+    match source {
+        RenameSource::Prefix(_) if !typed.ends_with('/') => format!("{}/", typed),
+        _ => typed.to_string(),
+    }
+}
+
+/// Maps each old key to its new key, keeping the part below a renamed folder, and flags new
+/// keys found in `existing`. Keys outside the folder are left out.
+pub fn plan_rename(
+    source: &RenameSource,
+    new_name: &str,
+    old_keys: Vec<String>,
+    existing: &HashSet<String>,
+) -> Vec<RenameEntry> {
+    old_keys
+        .into_iter()
+        .filter_map(|old_key| {
+            let new_key = match source {
+                RenameSource::Key(_) => new_name.to_string(),
+                RenameSource::Prefix(prefix) => {
+                    format!("{}{}", new_name, old_key.strip_prefix(prefix.as_str())?)
+                }
+            };
+            Some(RenameEntry {
+                conflict: existing.contains(&new_key),
+                old_key,
+                new_key,
+            })
+        })
+        .collect()
+}
+
+/// Rename prompt and dry-run preview; renames run as copy-then-delete move jobs
+#[derive(Debug)]
+pub struct RenameState {
+    pub prompt: SearchBar,
+    pub bucket: String,
+    pub source: Option<RenameSource>,
+    pub preview: ScrollableList<RenameEntry>,
+    s3_client: Arc<S3Client>,
+}
+
+impl RenameState {
+    pub fn new(s3_client: Arc<S3Client>) -> Self {
+        Self {
+            prompt: SearchBar::default(),
+            bucket: String::new(),
+            source: None,
+            preview: ScrollableList::new("Rename Preview"),
+            s3_client,
+        }
+    }
+
+    /// Open the prompt, pre-filled with the current name
+    pub fn open(&mut self, bucket: &str, source: RenameSource) {
+        self.bucket = bucket.to_string();
+        self.prompt.active = true;
+        self.prompt.set_query(source.old_name());
+        self.prompt.error = None;
+        self.source = Some(source);
+        self.preview = ScrollableList::new("Rename Preview");
+        self.preview.set_has_more(false);
+    }
+
+    fn new_name(&self) -> String {
+        match &self.source {
+            Some(source) => target_name(source, &self.prompt.query),
+            None => self.prompt.query.clone(),
+        }
+    }
+
+    /// List what would be renamed and flag anything that would overwrite an existing key
+    async fn build_preview(&mut self) -> color_eyre::Result<()> {
+        let Some(source) = self.source.clone() else {
+            return Ok(());
+        };
+        let new_name = self.new_name();
+
+        let old_keys = match &source {
+            RenameSource::Key(key) => vec![key.clone()],
+            RenameSource::Prefix(prefix) => {
+                self.s3_client.list_all_keys(&self.bucket, prefix).await?
+            }
+        };
+        let existing: HashSet<String> = self
+            .s3_client
+            .list_all_keys(&self.bucket, &new_name)
+            .await?
+            .into_iter()
+            .collect();
+
+        let entries = plan_rename(&source, &new_name, old_keys, &existing);
+
+        let conflicts = entries.iter().filter(|entry| entry.conflict).count();
+        self.preview = ScrollableList::new(format!(
+            "Rename Preview: {} → {}",
+            source.old_name(),
+            new_name
+        ));
+        self.preview.set_has_more(false);
+        self.preview.summary = Some(format!(
+            "{} to rename, {} skipped because the new key exists. Enter: Confirm  Esc: Edit",
+            entries.len() - conflicts,
+            conflicts
+        ));
+        self.preview.append_items(entries);
+        self.preview.filtered_indices = (0..self.preview.items.len()).collect();
+        self.preview.first();
+        Ok(())
+    }
+
+    pub async fn handle_action(
+        &mut self,
+        action: AppActions,
+        app_mode: &mut AppMode,
+        jobs: &mut JobsState,
+    ) -> color_eyre::Result<()> {
+        if self.prompt.active {
+            match action {
+                AppActions::SearchInput(c) => {
+                    self.prompt.input(c);
+                    self.prompt.error = None;
+                }
+                AppActions::SearchDelete => {
+                    self.prompt.delete();
+                    self.prompt.error = None;
+                }
+                AppActions::Enter => {
+                    let unchanged =
+                        self.source.as_ref().map(|s| s.old_name()) == Some(&self.new_name());
+                    if self.prompt.query.is_empty() || unchanged {
+                        self.prompt.error = Some("enter a different name".to_string());
+                    } else {
+                        self.prompt.active = false;
+                        if let Err(e) = self.build_preview().await {
+                            self.prompt.active = true;
+                            self.prompt.error = Some(list_error_reason(&e));
+                        }
+                    }
+                }
+                AppActions::GoBack => {
+                    self.source = None;
+                    *app_mode = AppMode::ObjectList;
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match action {
+            AppActions::MoveDown => self.preview.next(),
+            AppActions::MoveUp => self.preview.previous(),
+            AppActions::MoveToTop => self.preview.first(),
+            AppActions::MoveToBottom => self.preview.last(),
+            AppActions::GoBack => {
+                // Back to editing the new name
+                self.prompt.active = true;
+                self.preview = ScrollableList::new("Rename Preview");
+                self.preview.set_has_more(false);
+            }
+            AppActions::Enter => {
+                let items: Vec<TransferItem> = self
+                    .preview
+                    .items
+                    .iter()
+                    .filter(|entry| !entry.conflict)
+                    .map(|entry| TransferItem {
+                        src_bucket: self.bucket.clone(),
+                        src_key: entry.old_key.clone(),
+                        dst_bucket: self.bucket.clone(),
+                        dst_key: entry.new_key.clone(),
+                    })
+                    .collect();
+
+                if !items.is_empty() {
+                    let description = format!(
+                        "Rename {} object(s) in {}: {} → {}",
+                        items.len(),
+                        self.bucket,
+                        self.source.as_ref().map_or("", |s| s.old_name()),
+                        self.new_name()
+                    );
+                    jobs.spawn_transfer(JobKind::Move, description, items);
+                }
+                self.source = None;
+                *app_mode = AppMode::ObjectList;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
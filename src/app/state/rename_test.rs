@@ -0,0 +1,86 @@
+use crate::app::state::rename::{RenameEntry, RenameSource, plan_rename, target_name};
+use std::collections::HashSet;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    }
+
+    fn entry(old_key: &str, new_key: &str, conflict: bool) -> RenameEntry {
+        RenameEntry {
+            old_key: old_key.to_string(),
+            new_key: new_key.to_string(),
+            conflict,
+        }
+    }
+
+    #[test]
+    fn test_target_name() {
+        let folder = RenameSource::Prefix("data/2024/".to_string());
+        assert_eq!(target_name(&folder, "data/archive"), "data/archive/");
+        assert_eq!(target_name(&folder, "data/archive/"), "data/archive/");
+
+        let key = RenameSource::Key("notes.txt".to_string());
+        assert_eq!(target_name(&key, "docs/notes"), "docs/notes");
+    }
+
+    #[test]
+    fn test_folder_rename_keeps_the_rest_of_each_key() {
+        let source = RenameSource::Prefix("data/2024/".to_string());
+        let old_keys = keys(&["data/2024/a.csv", "data/2024/jan/b.csv", "data/2024/"]);
+        assert_eq!(
+            plan_rename(&source, "data/archive/", old_keys, &HashSet::new()),
+            [
+                entry("data/2024/a.csv", "data/archive/a.csv", false),
+                entry("data/2024/jan/b.csv", "data/archive/jan/b.csv", false),
+                entry("data/2024/", "data/archive/", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_folder_rename_ignores_sibling_prefixes() {
+        // data/2024-old shares the characters of data/2024 but is not inside the folder
+        let source = RenameSource::Prefix("data/2024/".to_string());
+        let old_keys = keys(&["data/2024/a.csv", "data/2024-old/a.csv"]);
+        assert_eq!(
+            plan_rename(&source, "data/2025/", old_keys, &HashSet::new()),
+            [entry("data/2024/a.csv", "data/2025/a.csv", false)]
+        );
+    }
+
+    #[test]
+    fn test_dry_run_flags_existing_keys() {
+        let source = RenameSource::Prefix("logs/".to_string());
+        let existing: HashSet<String> = keys(&["old-logs/b.log", "old-logs/c.log"])
+            .into_iter()
+            .collect();
+        let old_keys = keys(&["logs/a.log", "logs/b.log"]);
+        assert_eq!(
+            plan_rename(&source, "old-logs/", old_keys, &existing),
+            [
+                entry("logs/a.log", "old-logs/a.log", false),
+                entry("logs/b.log", "old-logs/b.log", true),
+            ]
+        );
+
+        let source = RenameSource::Key("report.pdf".to_string());
+        let existing: HashSet<String> = keys(&["report-final.pdf"]).into_iter().collect();
+        assert_eq!(
+            plan_rename(&source, "report", keys(&["report.pdf"]), &existing),
+            [entry("report.pdf", "report", false)]
+        );
+        assert_eq!(
+            plan_rename(
+                &source,
+                "report-final.pdf",
+                keys(&["report.pdf"]),
+                &existing
+            ),
+            [entry("report.pdf", "report-final.pdf", true)]
+        );
+    }
+}
@@ -51,12 +51,19 @@ impl S3BucketState {
         Ok(())
     }
 
+    /// Load the bucket list again from the start
+    pub async fn reload_buckets(&mut self) -> color_eyre::Result<()> {
+        self.bucket_list = ScrollableList::new("S3 Buckets");
+        self.bucket_continuation_token = None;
+        self.load_buckets().await
+    }
+
     /// Select a bucket and load its objects
     pub async fn select_bucket(&mut self) -> color_eyre::Result<()> {
-        if let Some(bucket) = self.bucket_list.selected_item() {
-            if let Some(name) = &bucket.name {
-                self.current_bucket = Some(name.clone());
-            }
+        if let Some(bucket) = self.bucket_list.selected_item()
+            && let Some(name) = &bucket.name
+        {
+            self.current_bucket = Some(name.clone());
         }
         Ok(())
     }
@@ -134,24 +141,20 @@ impl S3BucketState {
                     s3_object_state.object_list =
                         ScrollableList::new(format!("Contents of {}", bucket_name));
                     s3_object_state.current_bucket = bucket_name.clone();
+                    s3_object_state.current_prefix = None;
+                    s3_object_state.marked.clear();
                     *app_mode = AppMode::ObjectList; // Change to ObjectList mode
                     s3_object_state.load_objects().await?;
                 }
             }
-            AppActions::Refresh => {
-                self.bucket_list = ScrollableList::new("S3 Buckets");
-                self.bucket_continuation_token = None;
+            AppActions::Refresh => self.reload_buckets().await?,
+            AppActions::LoadMore if self.bucket_continuation_token.is_some() => {
                 self.load_buckets().await?;
             }
-            AppActions::LoadMore => {
-                if self.bucket_continuation_token.is_some() {
-                    self.load_buckets().await?;
-                }
-            }
             AppActions::ClearSearch => {
                 self.search_bar.clear();
                 self.bucket_list
-                    .apply_search(&self.search_bar, |object| "Unknown".to_string());
+                    .apply_search(&self.search_bar, |_object| "Unknown".to_string());
             }
             _ => {}
         }
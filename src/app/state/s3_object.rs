@@ -1,17 +1,155 @@
 use crate::app::AppMode;
 use crate::app::actions::AppActions;
+use crate::app::state::jobs::{JobKind, TransferItem};
 use crate::app::state::{LoadingState, NotificationMessage};
+use crate::archive::{RestoreStatus, is_archived};
 use crate::aws::s3_client::S3Client;
-use crate::search::SearchBar;
+use crate::filter::ObjectFilter;
+use crate::search::{SearchBar, SearchMode};
 use crate::ui::ScrollableList;
-use crate::util::create_unique_filepath;
+use crate::util::format_size;
+use aws_sdk_s3::operation::head_object::HeadObjectOutput;
+use aws_sdk_s3::primitives::DateTimeFormat;
 use aws_sdk_s3::types::Object;
-use color_eyre::eyre::Context;
-use std::fs;
-use std::path::{Path, PathBuf};
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Instant;
 
+/// Listing pages to load while looking for a key to select
+const MAX_PAGES_TO_FIND_KEY: usize = 50;
+
+/// Objects yanked with `y` (copy) or `x` (move), waiting to be pasted with `p`
+#[derive(Debug, Clone)]
+pub struct Yank {
+    pub kind: JobKind,
+    pub bucket: String,
+    /// Keys are pasted relative to this "folder"
+    pub source_folder: String,
+    pub keys: Vec<String>,
+}
+
+/// HeadObject details shown in place of the preview
+#[derive(Debug, Clone)]
+pub struct ObjectInfo {
+    pub key: String,
+    pub fields: Vec<(String, String)>,
+}
+
+impl ObjectInfo {
+    fn from_head(key: &str, head: &HeadObjectOutput) -> Self {
+        let date = |d: &aws_sdk_s3::primitives::DateTime| {
+            d.fmt(DateTimeFormat::DateTime)
+                .unwrap_or_else(|_| d.secs().to_string())
+        };
+        let size = head.content_length().unwrap_or(0);
+
+        let fields: Vec<(&str, Option<String>)> = vec![
+            (
+                "Content-Length",
+                Some(format!("{} ({} bytes)", format_size(size), size)),
+            ),
+            ("ETag", head.e_tag().map(String::from)),
+            ("Last-Modified", head.last_modified().map(date)),
+            (
+                "Storage Class",
+                Some(
+                    head.storage_class()
+                        .map_or("STANDARD", |c| c.as_str())
+                        .to_string(),
+                ),
+            ),
+            ("Version ID", head.version_id().map(String::from)),
+            ("Content-Type", head.content_type().map(String::from)),
+            (
+                "Content-Encoding",
+                head.content_encoding().map(String::from),
+            ),
+            (
+                "Content-Disposition",
+                head.content_disposition().map(String::from),
+            ),
+            (
+                "Content-Language",
+                head.content_language().map(String::from),
+            ),
+            ("Cache-Control", head.cache_control().map(String::from)),
+            ("Expires", head.expires_string().map(String::from)),
+            (
+                "Server-Side Encryption",
+                head.server_side_encryption()
+                    .map(|s| s.as_str().to_string()),
+            ),
+            ("KMS Key", head.ssekms_key_id().map(String::from)),
+            (
+                "Bucket Key",
+                head.bucket_key_enabled().map(|b| b.to_string()),
+            ),
+            (
+                "SSE-C Algorithm",
+                head.sse_customer_algorithm().map(String::from),
+            ),
+            ("Checksum CRC32", head.checksum_crc32().map(String::from)),
+            ("Checksum CRC32C", head.checksum_crc32_c().map(String::from)),
+            (
+                "Checksum CRC64NVME",
+                head.checksum_crc64_nvme().map(String::from),
+            ),
+            ("Checksum SHA1", head.checksum_sha1().map(String::from)),
+            ("Checksum SHA256", head.checksum_sha256().map(String::from)),
+            (
+                "Checksum Type",
+                head.checksum_type().map(|t| t.as_str().to_string()),
+            ),
+            ("Parts", head.parts_count().map(|p| p.to_string())),
+            (
+                "Object Lock Mode",
+                head.object_lock_mode().map(|m| m.as_str().to_string()),
+            ),
+            (
+                "Object Lock Until",
+                head.object_lock_retain_until_date().map(date),
+            ),
+            (
+                "Legal Hold",
+                head.object_lock_legal_hold_status()
+                    .map(|s| s.as_str().to_string()),
+            ),
+            (
+                "Replication Status",
+                head.replication_status().map(|s| s.as_str().to_string()),
+            ),
+            ("Restore", head.restore().map(String::from)),
+            (
+                "Archive Status",
+                head.archive_status().map(|s| s.as_str().to_string()),
+            ),
+            ("Expiration", head.expiration().map(String::from)),
+            (
+                "Redirect Location",
+                head.website_redirect_location().map(String::from),
+            ),
+        ];
+
+        let mut metadata: Vec<_> = head.metadata().into_iter().flatten().collect();
+        metadata.sort();
+        let metadata_fields: Vec<(String, String)> = metadata
+            .into_iter()
+            .map(|(k, v)| (format!("x-amz-meta-{}", k), v.clone()))
+            .collect();
+
+        let mut all: Vec<(String, String)> = fields
+            .into_iter()
+            .filter_map(|(name, value)| value.map(|value| (name.to_string(), value)))
+            .collect();
+        all.extend(metadata_fields);
+
+        Self {
+            key: key.to_string(),
+            fields: all,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct S3ObjectState {
     pub current_bucket: String,
@@ -24,7 +162,18 @@ pub struct S3ObjectState {
     pub object_preview: Option<String>,
     pub processed_preview_lines: Option<Vec<String>>,
     pub preview_scroll_offset: usize,
+    /// 1-based line of the raw content to scroll to once the preview is laid out
+    pub pending_preview_line: Option<usize>,
     pub search_bar: SearchBar,
+    pub filter_bar: SearchBar,
+    pub object_filter: Option<ObjectFilter>,
+    /// Keys marked in the current bucket for bulk operations
+    pub marked: HashSet<String>,
+    pub yank: Option<Yank>,
+    /// Keys awaiting a second `D` to be deleted
+    pending_delete: Option<Vec<String>>,
+    /// HeadObject details for the panel toggled with `i`
+    pub object_info: Option<ObjectInfo>,
     pub s3_client: Arc<S3Client>,
     pub notification: Option<NotificationMessage>,
     pub loading_state: LoadingState,
@@ -41,10 +190,17 @@ impl S3ObjectState {
             current_object_content_type: None,
             processed_preview_lines: None,
             preview_scroll_offset: 0,
+            pending_preview_line: None,
             current_prefix: None,
             object_continuation_token: None,
             s3_client,
             search_bar: SearchBar::default(),
+            filter_bar: SearchBar::default(),
+            object_filter: None,
+            marked: HashSet::new(),
+            yank: None,
+            pending_delete: None,
+            object_info: None,
             notification: None,
             loading_state: LoadingState::Idle,
         }
@@ -58,10 +214,7 @@ impl S3ObjectState {
             .list_objects(
                 &self.current_bucket,
                 self.object_continuation_token.clone(),
-                match !self.search_bar.query.is_empty() {
-                    true => Some(self.search_bar.query.clone()),
-                    false => None,
-                },
+                self.listing_prefix(),
                 20,
             )
             .await?;
@@ -71,85 +224,282 @@ impl S3ObjectState {
 
         self.object_list.append_items(buckets);
 
+        // Keep any metadata filter applied to newly loaded pages
         self.object_list.filtered_indices = (0..self.object_list.items.len()).collect();
+        if self.object_filter.is_some() {
+            self.apply_current_search();
+        }
 
         self.object_list.first();
         self.loading_state = LoadingState::Loaded;
         Ok(())
     }
 
-    pub fn update_status(&mut self, message: String) {
-        self.notification = Some(NotificationMessage {
-            status_message: message,
-            status_message_time: Instant::now(),
-        })
+    /// The prefix the object list is currently narrowed to
+    pub fn listing_prefix(&self) -> Option<String> {
+        // Only plain fuzzy queries are usable as a listing prefix
+        match self.search_bar.mode_and_pattern() {
+            (SearchMode::Fuzzy, pattern) if !pattern.is_empty() => Some(pattern.to_string()),
+            _ => self.current_prefix.clone(),
+        }
     }
 
-    /// Apply the current search query to the appropriate list based on the current mode
-    pub fn apply_current_search(&mut self) {
-        self.object_list.apply_search(&self.search_bar, |object| {
-            object.key().unwrap_or("Unknown").to_string()
-        });
+    /// The "folder" part of the listing prefix, where pasted objects land
+    pub fn current_folder(&self) -> String {
+        let prefix = self.listing_prefix().unwrap_or_default();
+        match prefix.rfind('/') {
+            Some(end) => prefix[..=end].to_string(),
+            None => String::new(),
+        }
+    }
+
+    /// Marked keys, or the selected key when nothing is marked
+    pub fn marked_or_selected_keys(&self) -> Vec<String> {
+        if self.marked.is_empty() {
+            return self
+                .object_list
+                .selected_item()
+                .and_then(|object| object.key())
+                .map(|key| vec![key.to_string()])
+                .unwrap_or_default();
+        }
+        let mut keys: Vec<String> = self.marked.iter().cloned().collect();
+        keys.sort();
+        keys
     }
 
-    /// Download the currently selected object
-    pub async fn download_object(&mut self) -> color_eyre::Result<()> {
-        let (bucket_name, object_key) = match (&self.current_bucket, &self.current_object) {
-            (bucket, Some(key)) => (bucket.clone(), key.clone()),
-            _ => return Ok(()),
+    /// Show HeadObject details for the selected object, or hide them if already shown
+    async fn toggle_object_info(&mut self) {
+        let Some(key) = self
+            .object_list
+            .selected_item()
+            .and_then(|object| object.key())
+            .map(String::from)
+        else {
+            return;
         };
+        if self
+            .object_info
+            .as_ref()
+            .is_some_and(|info| info.key == key)
+        {
+            self.object_info = None;
+            return;
+        }
 
-        let original_filename = object_key.split('/').last().unwrap_or(&object_key);
-        self.update_status(format!("Downloading {}...", original_filename));
+        match self.s3_client.head_object(&self.current_bucket, &key).await {
+            Ok(head) => self.object_info = Some(ObjectInfo::from_head(&key, &head)),
+            Err(e) => self.update_status(e.to_string()),
+        }
+    }
 
-        let download_dir = dirs::download_dir().unwrap_or_else(|| {
-            self.update_status(
-                "Could not determine the download directory. Using the current directory."
-                    .to_string(),
-            );
-            PathBuf::from(".")
+    /// Keep the info panel on the selected object as the selection moves
+    async fn follow_object_info(&mut self) {
+        let Some(info) = &self.object_info else {
+            return;
+        };
+        let selected = self
+            .object_list
+            .selected_item()
+            .and_then(|object| object.key())
+            .map(String::from);
+        if selected.as_deref() == Some(info.key.as_str()) {
+            return;
+        }
+        self.object_info = None;
+        if let Some(key) = selected {
+            match self.s3_client.head_object(&self.current_bucket, &key).await {
+                Ok(head) => self.object_info = Some(ObjectInfo::from_head(&key, &head)),
+                Err(e) => self.update_status(e.to_string()),
+            }
+        }
+    }
+
+    pub fn toggle_mark(&mut self) {
+        if let Some(key) = self
+            .object_list
+            .selected_item()
+            .and_then(|object| object.key())
+            .map(String::from)
+            && !self.marked.remove(&key)
+        {
+            self.marked.insert(key);
+        }
+        self.object_list.next();
+    }
+
+    pub fn yank_objects(&mut self, kind: JobKind) {
+        let keys = self.marked_or_selected_keys();
+        if keys.is_empty() {
+            return;
+        }
+
+        self.update_status(format!(
+            "Yanked {} object(s) to {}; press p in the destination to paste",
+            keys.len(),
+            kind.to_string().to_lowercase()
+        ));
+        self.yank = Some(Yank {
+            kind,
+            bucket: self.current_bucket.clone(),
+            source_folder: self.current_folder(),
+            keys,
         });
+        self.marked.clear();
+    }
 
-        if !download_dir.exists() {
-            if let Err(e) = std::fs::create_dir_all(&download_dir) {
-                self.update_status(format!(
-                    "Error creating download dir {}: {}",
-                    download_dir.display(),
-                    e
-                ));
-                return Ok(());
-            }
+    /// Builds the transfers for pasting the yanked objects into the current folder.
+    /// A move can only be pasted once.
+    pub fn take_paste(&mut self) -> Option<(JobKind, String, Vec<TransferItem>)> {
+        let yank = self.yank.clone()?;
+        let dst_folder = self.current_folder();
+
+        let items: Vec<TransferItem> = yank
+            .keys
+            .iter()
+            .map(|key| {
+                let relative = key
+                    .strip_prefix(&yank.source_folder)
+                    .unwrap_or_else(|| key.rsplit_once('/').map_or(key.as_str(), |(_, name)| name));
+                TransferItem {
+                    src_bucket: yank.bucket.clone(),
+                    src_key: key.clone(),
+                    dst_bucket: self.current_bucket.clone(),
+                    dst_key: format!("{}{}", dst_folder, relative),
+                }
+            })
+            .filter(|item| item.src_bucket != item.dst_bucket || item.src_key != item.dst_key)
+            .collect();
+
+        if items.is_empty() {
+            self.update_status("Nothing to paste: source and destination are the same".into());
+            return None;
         }
 
-        let download_path = create_unique_filepath(&download_dir, original_filename);
+        if yank.kind == JobKind::Move {
+            self.yank = None;
+        }
+        let description = format!(
+            "{} {} object(s) from {}/{} to {}/{}",
+            yank.kind,
+            items.len(),
+            yank.bucket,
+            yank.source_folder,
+            self.current_bucket,
+            dst_folder
+        );
+        self.update_status(format!("Started: {}", description));
+        Some((yank.kind, description, items))
+    }
 
-        match self
-            .download_and_save(&self.s3_client, &bucket_name, &object_key, &download_path)
-            .await
-        {
-            Ok(_) => self.update_status(format!("Downloaded to {}", download_path.display())),
-            Err(e) => self.update_status(format!("Download failed: {}", e)),
+    /// Open `bucket` at the "folder" containing `key` and select it
+    pub async fn open_at_key(&mut self, bucket: &str, key: &str) -> color_eyre::Result<()> {
+        let prefix = key.rfind('/').map(|end| key[..=end].to_string());
+
+        self.current_bucket = bucket.to_string();
+        self.object_list = ScrollableList::new(format!(
+            "Contents of {}/{}",
+            bucket,
+            prefix.as_deref().unwrap_or("")
+        ));
+        self.current_prefix = prefix;
+        self.object_continuation_token = None;
+        self.current_object = None;
+        self.preview_object = false;
+        self.search_bar.clear();
+
+        // Page through the listing until the key shows up
+        for _ in 0..MAX_PAGES_TO_FIND_KEY {
+            self.load_objects().await?;
+            if let Some(idx) = self
+                .object_list
+                .items
+                .iter()
+                .position(|object| object.key() == Some(key))
+            {
+                self.object_list.select(Some(idx));
+                return Ok(());
+            }
+            if self.object_continuation_token.is_none() {
+                self.update_status(format!("{} was not found in {}", key, bucket));
+                return Ok(());
+            }
         }
 
+        // Gave up paging; say so rather than leaving nothing selected without a word
+        self.update_status(format!(
+            "{} is not among the first {} objects listed; press Space to load more",
+            key,
+            self.object_list.items.len()
+        ));
         Ok(())
     }
 
-    async fn download_and_save(
-        &self,
-        s3_client: &S3Client,
-        bucket_name: &str,
-        object_key: &str,
-        path: &Path,
-    ) -> color_eyre::Result<()> {
-        let content_bytes = s3_client
-            .download_object_bytes(bucket_name, object_key)
-            .await
-            .wrap_err("Failed to download the object")?;
+    pub fn update_status(&mut self, message: String) {
+        self.notification = Some(NotificationMessage {
+            status_message: message,
+            status_message_time: Instant::now(),
+        })
+    }
 
-        fs::write(path, content_bytes)
-            .wrap_err_with(|| format!("Failed to write the file to {}", path.display()))?;
+    /// The marked or selected keys, once `D` was pressed twice in a row for them
+    /// Whether the next `D` deletes, rather than asking to press it again
+    pub fn delete_armed(&self) -> bool {
+        self.pending_delete.as_ref() == Some(&self.marked_or_selected_keys())
+    }
 
-        Ok(())
+    pub fn take_delete(&mut self) -> Option<Vec<String>> {
+        let keys = self.marked_or_selected_keys();
+        if keys.is_empty() {
+            return None;
+        }
+        if self.pending_delete.as_ref() == Some(&keys) {
+            self.pending_delete = None;
+            self.marked.clear();
+            self.update_status(format!("Deleting {} object(s)", keys.len()));
+            return Some(keys);
+        }
+        self.update_status(format!("Press D again to delete {} object(s)", keys.len()));
+        self.pending_delete = Some(keys);
+        None
+    }
+
+    /// Apply the current search query to the appropriate list based on the current mode
+    pub fn apply_current_search(&mut self) {
+        self.object_list.apply_search(&self.search_bar, |object| {
+            object.key().unwrap_or("Unknown").to_string()
+        });
+
+        self.object_list.summary = None;
+        if let Some(filter) = &self.object_filter {
+            self.object_list
+                .retain_filtered(|object| filter.matches(object));
+
+            let total_size: i64 = self
+                .object_list
+                .filtered_indices
+                .iter()
+                .map(|&idx| self.object_list.items[idx].size().unwrap_or(0))
+                .sum();
+            self.object_list.summary = Some(format!(
+                "{} of {} loaded objects match ({})",
+                self.object_list.filtered_indices.len(),
+                self.object_list.items.len(),
+                format_size(total_size)
+            ));
+        }
+    }
+
+    /// Re-parse the filter expression and re-apply it to the list
+    pub fn apply_current_filter(&mut self) {
+        self.object_filter = None;
+        match ObjectFilter::parse(&self.filter_bar.query) {
+            Ok(filter) if !filter.is_empty() => self.object_filter = Some(filter),
+            Ok(_) => {}
+            // Keep the list unfiltered while the expression is incomplete or invalid
+            Err(e) => self.filter_bar.error = Some(e),
+        }
+        self.apply_current_search();
     }
 
     /// Prepares display lines for the preview pane based on the content type and available width
@@ -165,8 +515,6 @@ impl S3ObjectState {
             .expect("object_preview is Some as checked above");
         let mut text_to_wrap = raw_content.clone(); // Start with raw content
 
-        let mut is_structured_text = false;
-
         if let Some(content_type) = &self.current_object_content_type {
             if content_type.starts_with("application/json")
                 && raw_content != "[Binary content - not valid UTF-8]"
@@ -175,7 +523,6 @@ impl S3ObjectState {
                     Ok(json_value) => {
                         if let Ok(pretty_json) = serde_json::to_string_pretty(&json_value) {
                             text_to_wrap = pretty_json;
-                            is_structured_text = true;
                         }
                         // If pretty printing fails, text_to_wrap remains raw_content
                     }
@@ -214,39 +561,69 @@ impl S3ObjectState {
             return;
         }
 
-        let wrapped_lines: Vec<String> = textwrap::wrap(&text_to_wrap, available_width as usize)
-            .into_iter()
-            .map(|s| s.into_owned())
-            .collect();
+        // Wrap line by line so raw line numbers can be mapped to display lines
+        let mut wrapped_lines: Vec<String> = Vec::new();
+        let mut line_starts = Vec::new();
+        for raw_line in text_to_wrap.split('\n') {
+            line_starts.push(wrapped_lines.len());
+            wrapped_lines.extend(
+                textwrap::wrap(raw_line, available_width as usize)
+                    .into_iter()
+                    .map(|s| s.into_owned()),
+            );
+        }
+
+        if let Some(line) = self.pending_preview_line.take() {
+            self.preview_scroll_offset = line_starts
+                .get(line.saturating_sub(1))
+                .copied()
+                .unwrap_or(0);
+        }
 
         self.processed_preview_lines = Some(wrapped_lines);
     }
 
     /// Select an object and load its preview
     pub async fn select_object(&mut self) -> color_eyre::Result<()> {
-        if let Some(selected_s3_object) = self.object_list.selected_item() {
-            if let (bucket_name, Some(object_key)) = (&self.current_bucket, &selected_s3_object.key)
-            {
-                self.loading_state = LoadingState::Loading;
-                self.object_preview = None;
-                self.current_object_content_type = None;
-                self.processed_preview_lines = None;
-
-                let (content, content_type) = self
-                    .s3_client
-                    .get_object_content(bucket_name, object_key)
-                    .await?;
-
-                self.loading_state = LoadingState::Loaded;
-                self.current_object = Some(object_key.clone());
-                self.object_preview = Some(content);
-                self.current_object_content_type = content_type;
-
-                // Mark that processed lines need regeneration
-                self.processed_preview_lines = None;
-                self.preview_scroll_offset = 0; // Reset scroll for new content
-                self.preview_object = true;
+        if let Some(selected_s3_object) = self.object_list.selected_item()
+            && let (bucket_name, Some(object_key)) = (&self.current_bucket, &selected_s3_object.key)
+        {
+            // Archived objects can only be read once a restored copy exists
+            let class = selected_s3_object.storage_class().map(|c| c.as_str());
+            if is_archived(class) {
+                let head = self.s3_client.head_object(bucket_name, object_key).await?;
+                let status = RestoreStatus::parse(head.restore());
+                if !status.readable(class) {
+                    let message = format!(
+                        "{} is in {} ({}); press A to restore it before previewing",
+                        object_key,
+                        class.unwrap_or_default(),
+                        status
+                    );
+                    self.update_status(message);
+                    return Ok(());
+                }
             }
+
+            self.loading_state = LoadingState::Loading;
+            self.object_preview = None;
+            self.current_object_content_type = None;
+            self.processed_preview_lines = None;
+
+            let (content, content_type) = self
+                .s3_client
+                .get_object_content(bucket_name, object_key)
+                .await?;
+
+            self.loading_state = LoadingState::Loaded;
+            self.current_object = Some(object_key.clone());
+            self.object_preview = Some(content);
+            self.current_object_content_type = content_type;
+
+            // Mark that processed lines need regeneration
+            self.processed_preview_lines = None;
+            self.preview_scroll_offset = 0; // Reset scroll for new content
+            self.preview_object = true;
         }
         Ok(())
     }
@@ -276,18 +653,54 @@ impl S3ObjectState {
                 }
                 _ => {}
             }
+            self.follow_object_info().await;
             return Ok(());
         }
 
+        if self.filter_bar.active {
+            match action {
+                AppActions::SearchInput(c) => {
+                    self.filter_bar.input(c);
+                    self.apply_current_filter();
+                }
+                AppActions::SearchDelete => {
+                    self.filter_bar.delete();
+                    self.apply_current_filter();
+                }
+                AppActions::Enter => {
+                    // Keep the expression visible while the filter stays applied
+                    self.filter_bar.active = false;
+                    self.apply_current_filter();
+                }
+                AppActions::GoBack => {
+                    self.filter_bar.toggle();
+                    self.apply_current_filter();
+                }
+                _ => {}
+            }
+            self.follow_object_info().await;
+            return Ok(());
+        }
+
+        // Any other key between the two presses of `D` calls the delete off
+        self.pending_delete = None;
         match action {
+            AppActions::GoBack if self.object_info.is_some() => self.object_info = None,
+            AppActions::ShowInfo => self.toggle_object_info().await,
             AppActions::GoBack => {
                 *app_mode = AppMode::BucketList;
                 self.object_list = ScrollableList::new("");
                 self.current_object = None;
+                self.current_prefix = None;
+                self.marked.clear();
             }
             AppActions::StartSearch => {
                 self.search_bar.toggle();
             }
+            AppActions::StartFilterSearch => {
+                self.filter_bar.active = true;
+                self.filter_bar.cursor_position = self.filter_bar.query.len();
+            }
             AppActions::MoveDown => self.object_list.next(),
             AppActions::MoveUp => self.object_list.previous(),
             AppActions::MoveToTop => self.object_list.first(),
@@ -315,27 +728,30 @@ impl S3ObjectState {
             AppActions::Enter => {
                 self.select_object().await?;
             }
-            AppActions::Download => {
-                self.download_object().await?;
-            }
+            AppActions::ToggleMark => self.toggle_mark(),
+            AppActions::ClearMarks => self.marked.clear(),
+            AppActions::Yank => self.yank_objects(JobKind::Copy),
+            AppActions::Cut => self.yank_objects(JobKind::Move),
             AppActions::Refresh => {
                 self.object_list = ScrollableList::new("Objects");
                 self.object_continuation_token = None;
                 self.load_objects().await?;
             }
-            AppActions::LoadMore => {
-                if self.object_continuation_token.is_some() {
-                    self.load_objects().await?;
-                }
+            AppActions::LoadMore if self.object_continuation_token.is_some() => {
+                self.load_objects().await?;
             }
             AppActions::ClearSearch => {
                 self.search_bar.clear();
+                self.filter_bar.clear();
+                self.object_filter = None;
                 self.object_list
-                    .apply_search(&self.search_bar, |object| "Unknown".to_string());
+                    .apply_search(&self.search_bar, |_object| "Unknown".to_string());
+                self.object_list.summary = None;
             }
             _ => {} // Ignore actions not relevant to bucket mode
         }
 
+        self.follow_object_info().await;
         Ok(())
     }
 
@@ -398,9 +814,6 @@ impl S3ObjectState {
             AppActions::HalfPageUp => {
                 self.preview_scroll_offset = self.preview_scroll_offset.saturating_sub(5);
             }
-            AppActions::Download => {
-                self.download_object().await?;
-            }
             AppActions::Refresh => {
                 if let Some(key) = &self.current_object.clone() {
                     self.refresh_object_preview(key).await?;
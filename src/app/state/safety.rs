@@ -0,0 +1,121 @@
+use crate::app::actions::AppActions;
+use crate::safety::{Mutation, Safety, Verdict, confirms};
+use crate::search::SearchBar;
+use std::time::{Duration, Instant};
+
+/// How long a refused action stays in the banner
+const BANNER_TIME: Duration = Duration::from_secs(5);
+
+/// An action waiting for the name of the protected bucket it changes
+#[derive(Debug)]
+struct PendingAction {
+    action: AppActions,
+    description: String,
+    bucket: String,
+}
+
+/// Gate in front of every action that changes S3: refuses them in read-only mode, and asks
+/// for the bucket name before deleting or overwriting anything in a protected bucket
+#[derive(Debug)]
+pub struct SafetyState {
+    pub safety: Safety,
+    /// The AWS profile whose protected buckets apply
+    pub profile: String,
+    /// Takes the bucket name while an action waits for confirmation
+    pub prompt: SearchBar,
+    pending: Option<PendingAction>,
+    banner: Option<(String, Instant)>,
+}
+
+impl SafetyState {
+    pub fn new(safety: Safety, profile: String) -> Self {
+        Self {
+            safety,
+            profile,
+            prompt: SearchBar::default(),
+            pending: None,
+            banner: None,
+        }
+    }
+
+    pub fn is_confirming(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// The warning to show above the view, if any
+    pub fn banner(&self) -> Option<String> {
+        if let Some(pending) = &self.pending {
+            return Some(format!(
+                "PROTECTED BUCKET {} (profile {}): type the bucket name to {}  Enter: Confirm  Esc: Cancel",
+                pending.bucket, self.profile, pending.description
+            ));
+        }
+        self.banner
+            .as_ref()
+            .filter(|(_, shown_at)| shown_at.elapsed() < BANNER_TIME)
+            .map(|(message, _)| message.clone())
+    }
+
+    fn show(&mut self, message: String) {
+        self.banner = Some((message, Instant::now()));
+    }
+
+    /// Returns the action if it may run now; otherwise it is refused or held for confirmation
+    pub fn check(&mut self, action: AppActions, mutation: Mutation) -> Option<AppActions> {
+        match self.safety.check(&mutation) {
+            Verdict::Allow => Some(action),
+            Verdict::Block(reason) => {
+                self.show(reason);
+                None
+            }
+            Verdict::Confirm(bucket) => {
+                self.prompt.clear();
+                self.prompt.error = None;
+                self.prompt.active = true;
+                self.pending = Some(PendingAction {
+                    action,
+                    description: mutation.description,
+                    bucket,
+                });
+                None
+            }
+        }
+    }
+
+    /// Handle input while confirming, returning the held action once the name matches
+    pub fn handle_confirm(&mut self, action: AppActions) -> Option<AppActions> {
+        match action {
+            AppActions::SearchInput(c) => {
+                self.prompt.input(c);
+                self.prompt.error = None;
+            }
+            AppActions::SearchDelete => {
+                self.prompt.delete();
+                self.prompt.error = None;
+            }
+            AppActions::Enter => {
+                let bucket = self.pending.as_ref()?.bucket.clone();
+                if !confirms(&bucket, &self.prompt.query) {
+                    self.prompt.error = Some(format!("does not match '{}'", bucket));
+                    return None;
+                }
+                let pending = self.pending.take()?;
+                self.prompt.active = false;
+                self.prompt.clear();
+                return Some(pending.action);
+            }
+            AppActions::GoBack => {
+                if let Some(pending) = self.pending.take() {
+                    self.show(format!(
+                        "Cancelled: did not {} in protected bucket {}",
+                        pending.description, pending.bucket
+                    ));
+                }
+                self.prompt.active = false;
+                self.prompt.clear();
+            }
+            _ => {}
+        }
+        None
+    }
+}
@@ -0,0 +1,214 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::aws::s3_client::S3Client;
+use crate::config::parse_presign_expiry;
+use crate::search::SearchBar;
+use crate::ui::ScrollableList;
+use crate::util::{copy_to_clipboard, format_duration};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The links that can be copied for an object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareOption {
+    PresignedGet,
+    PresignedPut,
+    S3Uri,
+    HttpsUrl,
+    Expiry,
+}
+
+impl ShareOption {
+    const ALL: [ShareOption; 5] = [
+        ShareOption::PresignedGet,
+        ShareOption::PresignedPut,
+        ShareOption::S3Uri,
+        ShareOption::HttpsUrl,
+        ShareOption::Expiry,
+    ];
+}
+
+/// Modal for copying presigned, `s3://` and `https://` links to the selected object
+#[derive(Debug)]
+pub struct ShareState {
+    pub options: ScrollableList<ShareOption>,
+    /// Edits the expiry, or the key a presigned upload URL is for
+    pub prompt: SearchBar,
+    pub bucket: String,
+    pub key: String,
+    pub expiry: Duration,
+    /// The last link generated, shown in full under the options
+    pub url: Option<String>,
+    prompt_for: ShareOption,
+    s3_client: Arc<S3Client>,
+}
+
+impl ShareState {
+    pub fn new(s3_client: Arc<S3Client>) -> Self {
+        Self {
+            options: ScrollableList::new("Share"),
+            prompt: SearchBar::default(),
+            bucket: String::new(),
+            key: String::new(),
+            expiry: Duration::from_secs(60 * 60),
+            url: None,
+            prompt_for: ShareOption::Expiry,
+            s3_client,
+        }
+    }
+
+    pub fn open(&mut self, bucket: &str, key: &str, expiry: Duration) {
+        self.bucket = bucket.to_string();
+        self.key = key.to_string();
+        self.expiry = expiry;
+        self.url = None;
+        self.prompt.active = false;
+        self.prompt.clear();
+
+        self.options = ScrollableList::new(format!("Share: {}", key));
+        self.options.append_items(ShareOption::ALL.to_vec());
+        self.options.filtered_indices = (0..ShareOption::ALL.len()).collect();
+        self.options.set_has_more(false);
+        self.options.first();
+    }
+
+    pub fn label(&self, option: ShareOption) -> String {
+        let expiry = format_duration(self.expiry);
+        match option {
+            ShareOption::PresignedGet => format!("Presigned download URL (expires in {})", expiry),
+            ShareOption::PresignedPut => format!("Presigned upload URL (expires in {})", expiry),
+            ShareOption::S3Uri => format!("s3://{}/{}", self.bucket, self.key),
+            ShareOption::HttpsUrl => "https:// object URL (needs public access)".to_string(),
+            ShareOption::Expiry => format!("Change expiry: {}", expiry),
+        }
+    }
+
+    pub fn prompt_help(&self) -> &'static str {
+        match self.prompt_for {
+            ShareOption::PresignedPut => {
+                "Type the key to allow uploads to  Enter: Create URL  Esc: Cancel"
+            }
+            _ => "Type an expiry, e.g. 15m, 12h or 7d (at most 7d)  Enter: Apply  Esc: Cancel",
+        }
+    }
+
+    /// Whether Enter would create a presigned upload URL, which grants write access
+    pub fn creates_upload_url(&self) -> bool {
+        self.prompt.active && self.prompt_for == ShareOption::PresignedPut
+    }
+
+    fn start_prompt(&mut self, option: ShareOption, initial: &str) {
+        self.prompt_for = option;
+        self.prompt.set_query(initial);
+        self.prompt.error = None;
+        self.prompt.active = true;
+    }
+
+    /// Show the link and put it on the clipboard
+    fn share(&mut self, url: String, what: &str) {
+        self.options.summary = Some(match copy_to_clipboard(&url) {
+            Ok(()) => format!("Copied {} to the clipboard", what),
+            Err(e) => format!("Could not copy {}: {}", what, e),
+        });
+        self.url = Some(url);
+    }
+
+    async fn select(&mut self, option: ShareOption) {
+        match option {
+            ShareOption::PresignedGet => {
+                match self
+                    .s3_client
+                    .presign_get(&self.bucket, &self.key, self.expiry)
+                    .await
+                {
+                    Ok(url) => self.share(url, "the presigned download URL"),
+                    Err(e) => self.options.summary = Some(e.to_string()),
+                }
+            }
+            ShareOption::PresignedPut => {
+                let key = self.key.clone();
+                self.start_prompt(ShareOption::PresignedPut, &key);
+            }
+            ShareOption::S3Uri => {
+                let uri = format!("s3://{}/{}", self.bucket, self.key);
+                self.share(uri, "the s3:// URI");
+            }
+            ShareOption::HttpsUrl => {
+                let url = self.s3_client.object_url(&self.bucket, &self.key).await;
+                self.share(url, "the https:// URL");
+            }
+            ShareOption::Expiry => {
+                let expiry = format_duration(self.expiry);
+                self.start_prompt(ShareOption::Expiry, &expiry);
+            }
+        }
+    }
+
+    async fn commit_prompt(&mut self) {
+        let input = self.prompt.query.trim().to_string();
+        match self.prompt_for {
+            ShareOption::PresignedPut => {
+                if input.is_empty() || input.ends_with('/') {
+                    self.prompt.error = Some("enter an object key".to_string());
+                    return;
+                }
+                match self
+                    .s3_client
+                    .presign_put(&self.bucket, &input, self.expiry)
+                    .await
+                {
+                    Ok(url) => self.share(url, &format!("the upload URL for {}", input)),
+                    Err(e) => {
+                        self.prompt.error = Some(e.to_string());
+                        return;
+                    }
+                }
+            }
+            _ => match parse_presign_expiry(&input) {
+                Ok(expiry) => self.expiry = expiry,
+                Err(e) => {
+                    self.prompt.error = Some(e);
+                    return;
+                }
+            },
+        }
+        self.prompt.active = false;
+        self.prompt.clear();
+    }
+
+    pub async fn handle_action(&mut self, action: AppActions, app_mode: &mut AppMode) {
+        if self.prompt.active {
+            match action {
+                AppActions::SearchInput(c) => {
+                    self.prompt.input(c);
+                    self.prompt.error = None;
+                }
+                AppActions::SearchDelete => {
+                    self.prompt.delete();
+                    self.prompt.error = None;
+                }
+                AppActions::Enter => self.commit_prompt().await,
+                AppActions::GoBack => {
+                    self.prompt.active = false;
+                    self.prompt.clear();
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match action {
+            AppActions::MoveDown => self.options.next(),
+            AppActions::MoveUp => self.options.previous(),
+            AppActions::MoveToTop => self.options.first(),
+            AppActions::MoveToBottom => self.options.last(),
+            AppActions::Enter => {
+                if let Some(option) = self.options.selected_item().copied() {
+                    self.select(option).await;
+                }
+            }
+            AppActions::GoBack | AppActions::Share => *app_mode = AppMode::ObjectList,
+            _ => {}
+        }
+    }
+}
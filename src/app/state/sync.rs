@@ -0,0 +1,196 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::app::state::jobs::JobsState;
+use crate::aws::s3_client::{S3Client, list_error_reason};
+use crate::search::SearchBar;
+use crate::sync::{SyncAction, SyncActionKind, SyncDirection, SyncOptions, SyncTarget};
+use crate::ui::ScrollableList;
+use crate::util::format_size;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Parses `up|down DIR [--delete] [--dry-run] [--exclude GLOB] [--include GLOB]`
+fn parse_prompt(input: &str) -> Result<SyncOptions, String> {
+    let mut words = input.split_whitespace();
+    let direction = match words.next() {
+        Some("up") => SyncDirection::Upload,
+        Some("down") => SyncDirection::Download,
+        _ => return Err("start with up (local → S3) or down (S3 → local)".to_string()),
+    };
+    let local_dir = words
+        .next()
+        .ok_or_else(|| "enter a local directory".to_string())?;
+    SyncOptions::parse_flags(direction, local_dir, words)
+}
+
+/// Sync prompt and plan review for the current folder; approved plans run as a job
+#[derive(Debug)]
+pub struct SyncState {
+    pub prompt: SearchBar,
+    pub plan: ScrollableList<SyncAction>,
+    target: Option<SyncTarget>,
+    options: Option<SyncOptions>,
+    bucket: String,
+    folder: String,
+    s3_client: Arc<S3Client>,
+}
+
+impl SyncState {
+    pub fn new(s3_client: Arc<S3Client>) -> Self {
+        Self {
+            prompt: SearchBar::default(),
+            plan: ScrollableList::new("Sync Plan"),
+            target: None,
+            options: None,
+            bucket: String::new(),
+            folder: String::new(),
+            s3_client,
+        }
+    }
+
+    /// The bucket Enter would upload to or delete from, when the plan shown changes S3
+    pub fn upload_bucket(&self) -> Option<&str> {
+        let options = self.options.as_ref()?;
+        let uploads = options.direction == SyncDirection::Upload && !options.dry_run;
+        (uploads && !self.prompt.active && !self.plan.items.is_empty())
+            .then_some(self.bucket.as_str())
+    }
+
+    /// Open the prompt for syncing `folder`, suggesting a download into `default_dir`
+    pub fn open(&mut self, bucket: &str, folder: String, default_dir: &Path) {
+        self.bucket = bucket.to_string();
+        self.folder = folder;
+        self.target = None;
+        self.options = None;
+        self.prompt.active = true;
+        self.prompt
+            .set_query(&format!("down {}", default_dir.display()));
+        self.prompt.error = None;
+        self.plan = ScrollableList::new(format!("Sync s3://{}/{}", self.bucket, self.folder));
+        self.plan.set_has_more(false);
+    }
+
+    /// Compare both sides and list what would change
+    async fn build_plan(&mut self, options: SyncOptions) -> color_eyre::Result<()> {
+        let target = SyncTarget {
+            bucket: self.bucket.clone(),
+            folder: self.folder.clone(),
+            local_dir: options.local_dir.clone(),
+        };
+        let actions = target.plan(&self.s3_client, &options).await?;
+
+        let (from, to) = match options.direction {
+            SyncDirection::Upload => (
+                target.local_dir.display().to_string(),
+                format!("s3://{}/{}", target.bucket, target.folder),
+            ),
+            SyncDirection::Download => (
+                format!("s3://{}/{}", target.bucket, target.folder),
+                target.local_dir.display().to_string(),
+            ),
+        };
+        self.plan = ScrollableList::new(format!("Sync Plan: {} → {}", from, to));
+        self.plan.set_has_more(false);
+
+        let count = |kind| actions.iter().filter(|a| a.kind == kind).count();
+        let transfers = count(SyncActionKind::Upload) + count(SyncActionKind::Download);
+        let deletes = count(SyncActionKind::DeleteLocal) + count(SyncActionKind::DeleteRemote);
+        let bytes: u64 = actions
+            .iter()
+            .filter(|a| a.kind.transfers())
+            .map(|a| a.size)
+            .sum();
+        self.plan.summary = Some(if actions.is_empty() {
+            "Already in sync. Esc: Edit".to_string()
+        } else if options.dry_run {
+            format!(
+                "Dry run: {} to copy ({}), {} to delete. Esc: Edit",
+                transfers,
+                format_size(bytes as i64),
+                deletes
+            )
+        } else {
+            format!(
+                "{} to copy ({}), {} to delete. Enter: Run  Esc: Edit",
+                transfers,
+                format_size(bytes as i64),
+                deletes
+            )
+        });
+
+        self.plan.append_items(actions);
+        self.plan.filtered_indices = (0..self.plan.items.len()).collect();
+        self.plan.first();
+        self.target = Some(target);
+        self.options = Some(options);
+        Ok(())
+    }
+
+    pub async fn handle_action(
+        &mut self,
+        action: AppActions,
+        app_mode: &mut AppMode,
+        jobs: &mut JobsState,
+    ) -> color_eyre::Result<()> {
+        if self.prompt.active {
+            match action {
+                AppActions::SearchInput(c) => {
+                    self.prompt.input(c);
+                    self.prompt.error = None;
+                }
+                AppActions::SearchDelete => {
+                    self.prompt.delete();
+                    self.prompt.error = None;
+                }
+                AppActions::Enter => match parse_prompt(&self.prompt.query) {
+                    Ok(options) => {
+                        self.prompt.active = false;
+                        if let Err(e) = self.build_plan(options).await {
+                            self.prompt.active = true;
+                            self.prompt.error = Some(list_error_reason(&e));
+                        }
+                    }
+                    Err(e) => self.prompt.error = Some(e),
+                },
+                AppActions::GoBack => *app_mode = AppMode::ObjectList,
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match action {
+            AppActions::MoveDown => self.plan.next(),
+            AppActions::MoveUp => self.plan.previous(),
+            AppActions::MoveToTop => self.plan.first(),
+            AppActions::MoveToBottom => self.plan.last(),
+            AppActions::GoBack => {
+                // Back to editing the sync command
+                self.prompt.active = true;
+                self.plan.items.clear();
+                self.plan.filtered_indices.clear();
+                self.plan.summary = None;
+            }
+            AppActions::Enter => {
+                let dry_run = self.options.as_ref().is_none_or(|o| o.dry_run);
+                if dry_run || self.plan.items.is_empty() {
+                    return Ok(());
+                }
+                if let Some(target) = self.target.take() {
+                    let description = format!(
+                        "Sync {} action(s) between s3://{}/{} and {}",
+                        self.plan.items.len(),
+                        target.bucket,
+                        target.folder,
+                        target.local_dir.display()
+                    );
+                    jobs.spawn_sync(description, target, std::mem::take(&mut self.plan.items));
+                    self.plan.filtered_indices.clear();
+                }
+                *app_mode = AppMode::ObjectList;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,330 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::app::state::jobs::JobsState;
+use crate::aws::s3_client::S3Client;
+use crate::search::SearchBar;
+use crate::ui::ScrollableList;
+use std::fmt;
+use std::sync::Arc;
+
+/// S3 allows at most this many tags per object
+const MAX_TAGS: usize = 10;
+const MAX_KEY_LEN: usize = 128;
+const MAX_VALUE_LEN: usize = 256;
+
+/// A tag in the editor; in bulk mode a missing value removes the key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagEntry {
+    pub key: String,
+    pub value: Option<String>,
+}
+
+/// Changes applied to each object's existing tags in bulk mode
+#[derive(Debug, Clone, Default)]
+pub struct TagEdit {
+    pub set: Vec<(String, String)>,
+    pub remove: Vec<String>,
+}
+
+impl TagEdit {
+    pub fn apply(&self, tags: &mut Vec<(String, String)>) -> Result<(), String> {
+        tags.retain(|(key, _)| !self.remove.contains(key));
+        for (key, value) in &self.set {
+            match tags.iter_mut().find(|(k, _)| k == key) {
+                Some(tag) => tag.1 = value.clone(),
+                None => tags.push((key.clone(), value.clone())),
+            }
+        }
+        if tags.len() > MAX_TAGS {
+            return Err(format!(
+                "would have {} tags, S3 allows {}",
+                tags.len(),
+                MAX_TAGS
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TagEdit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let set = self.set.iter().map(|(k, v)| format!("+{}={}", k, v));
+        let remove = self.remove.iter().map(|k| format!("-{}", k));
+        write!(f, "{}", set.chain(remove).collect::<Vec<_>>().join(" "))
+    }
+}
+
+/// Parses `key=value`, or `-key` to remove a key when `allow_removal` is set
+pub fn parse_tag(input: &str, allow_removal: bool) -> Result<TagEntry, String> {
+    let input = input.trim();
+    let (key, value) = match input.strip_prefix('-') {
+        Some(key) if allow_removal => (key, None),
+        _ => match input.split_once('=') {
+            Some((key, value)) => (key, Some(value)),
+            None if allow_removal => return Err("expected key=value or -key".to_string()),
+            None => return Err("expected key=value".to_string()),
+        },
+    };
+
+    let (key, value) = (key.trim(), value.map(str::trim));
+    if key.is_empty() {
+        return Err("missing tag key".to_string());
+    }
+    if key.chars().count() > MAX_KEY_LEN {
+        return Err(format!(
+            "tag keys are limited to {} characters",
+            MAX_KEY_LEN
+        ));
+    }
+    if key.starts_with("aws:") {
+        return Err("the aws: prefix is reserved".to_string());
+    }
+    if value.is_some_and(|value| value.chars().count() > MAX_VALUE_LEN) {
+        return Err(format!(
+            "tag values are limited to {} characters",
+            MAX_VALUE_LEN
+        ));
+    }
+
+    Ok(TagEntry {
+        key: key.to_string(),
+        value: value.map(String::from),
+    })
+}
+
+/// Tag viewer and editor for the selected object, or a tag edit for every marked object
+#[derive(Debug)]
+pub struct TagsState {
+    pub tags: ScrollableList<TagEntry>,
+    pub prompt: SearchBar,
+    pub bucket: String,
+    pub keys: Vec<String>,
+    /// Index of the entry being edited, `None` while adding a new one
+    editing: Option<usize>,
+    dirty: bool,
+    /// Set after Esc with unsaved changes, so a second Esc discards them
+    discard_armed: bool,
+    s3_client: Arc<S3Client>,
+}
+
+impl TagsState {
+    pub fn new(s3_client: Arc<S3Client>) -> Self {
+        Self {
+            tags: ScrollableList::new("Tags"),
+            prompt: SearchBar::default(),
+            bucket: String::new(),
+            keys: Vec::new(),
+            editing: None,
+            dirty: false,
+            discard_armed: false,
+            s3_client,
+        }
+    }
+
+    pub fn is_bulk(&self) -> bool {
+        self.keys.len() > 1
+    }
+
+    /// Open the editor for one object, loading its tags, or a blank bulk edit for several
+    pub async fn open(&mut self, bucket: &str, keys: Vec<String>) {
+        self.bucket = bucket.to_string();
+        self.keys = keys;
+        self.editing = None;
+        self.dirty = false;
+        self.discard_armed = false;
+        self.prompt.active = false;
+        self.prompt.clear();
+
+        let title = match self.keys.as_slice() {
+            [key] => format!("Tags: {}", key),
+            keys => format!("Bulk tag edit: {} marked objects", keys.len()),
+        };
+        self.tags = ScrollableList::new(title);
+        self.tags.set_has_more(false);
+
+        if self.is_bulk() {
+            self.tags.summary = Some(
+                "Tags added here are set on every marked object; add -key to remove a key"
+                    .to_string(),
+            );
+            return;
+        }
+
+        let Some(key) = self.keys.first() else {
+            return;
+        };
+        match self.s3_client.get_object_tagging(&self.bucket, key).await {
+            Ok(tags) => {
+                self.set_entries(
+                    tags.into_iter()
+                        .map(|(key, value)| TagEntry {
+                            key,
+                            value: Some(value),
+                        })
+                        .collect(),
+                );
+                self.tags.first();
+            }
+            Err(e) => self.tags.summary = Some(e.to_string()),
+        }
+    }
+
+    fn set_entries(&mut self, entries: Vec<TagEntry>) {
+        let selected = self.tags.selected_index();
+        self.tags.items.clear();
+        self.tags.append_items(entries);
+        self.tags.filtered_indices = (0..self.tags.items.len()).collect();
+        match selected {
+            Some(index) if index < self.tags.items.len() => self.tags.select(Some(index)),
+            _ => self.tags.last(),
+        }
+    }
+
+    /// Add or replace the entry typed in the prompt
+    fn commit_prompt(&mut self) {
+        let entry = match parse_tag(&self.prompt.query, self.is_bulk()) {
+            Ok(entry) => entry,
+            Err(e) => {
+                self.prompt.error = Some(e);
+                return;
+            }
+        };
+
+        let mut entries = self.tags.items.clone();
+        if let Some(index) = self.editing {
+            entries.remove(index);
+        }
+        match entries.iter().position(|e| e.key == entry.key) {
+            Some(existing) => entries[existing] = entry,
+            // An edited entry keeps its place in the list
+            None => entries.insert(self.editing.unwrap_or(entries.len()), entry),
+        }
+        if !self.is_bulk() && entries.len() > MAX_TAGS {
+            self.prompt.error = Some(format!("S3 allows at most {} tags", MAX_TAGS));
+            return;
+        }
+
+        self.set_entries(entries);
+        self.dirty = true;
+        self.prompt.active = false;
+        self.prompt.clear();
+        self.tags.summary = Some("Unsaved changes  Ctrl+s: Save".to_string());
+    }
+
+    /// Apply the bulk edit to every marked object as a background job
+    fn start_bulk_edit(&self, jobs: &mut JobsState) {
+        let mut edit = TagEdit::default();
+        for entry in &self.tags.items {
+            match &entry.value {
+                Some(value) => edit.set.push((entry.key.clone(), value.clone())),
+                None => edit.remove.push(entry.key.clone()),
+            }
+        }
+        jobs.spawn_tagging(self.bucket.clone(), self.keys.clone(), edit);
+    }
+
+    /// Replace the object's tag set with the edited one
+    async fn save(&mut self) {
+        let Some(key) = self.keys.first() else {
+            return;
+        };
+        let tags: Vec<(String, String)> = self
+            .tags
+            .items
+            .iter()
+            .filter_map(|e| e.value.clone().map(|value| (e.key.clone(), value)))
+            .collect();
+        match self
+            .s3_client
+            .put_object_tagging(&self.bucket, key, &tags)
+            .await
+        {
+            Ok(()) => {
+                self.dirty = false;
+                self.tags.summary = Some(format!("Saved {} tags", tags.len()));
+            }
+            Err(e) => self.tags.summary = Some(e.to_string()),
+        }
+    }
+
+    pub async fn handle_action(
+        &mut self,
+        action: AppActions,
+        app_mode: &mut AppMode,
+        jobs: &mut JobsState,
+    ) -> color_eyre::Result<()> {
+        if self.prompt.active {
+            match action {
+                AppActions::SearchInput(c) => {
+                    self.prompt.input(c);
+                    self.prompt.error = None;
+                }
+                AppActions::SearchDelete => {
+                    self.prompt.delete();
+                    self.prompt.error = None;
+                }
+                AppActions::Enter => self.commit_prompt(),
+                AppActions::GoBack => {
+                    self.prompt.active = false;
+                    self.prompt.clear();
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        if !matches!(action, AppActions::GoBack) {
+            self.discard_armed = false;
+        }
+
+        match action {
+            AppActions::MoveDown => self.tags.next(),
+            AppActions::MoveUp => self.tags.previous(),
+            AppActions::MoveToTop => self.tags.first(),
+            AppActions::MoveToBottom => self.tags.last(),
+            AppActions::Add => {
+                self.editing = None;
+                self.prompt.clear();
+                self.prompt.active = true;
+            }
+            AppActions::Enter => {
+                if let Some(index) = self.tags.selected_index() {
+                    let entry = &self.tags.items[index];
+                    let text = match &entry.value {
+                        Some(value) => format!("{}={}", entry.key, value),
+                        None => format!("-{}", entry.key),
+                    };
+                    self.editing = Some(index);
+                    self.prompt.set_query(&text);
+                    self.prompt.active = true;
+                }
+            }
+            AppActions::Delete => {
+                if let Some(index) = self.tags.selected_index() {
+                    let mut entries = self.tags.items.clone();
+                    entries.remove(index);
+                    self.set_entries(entries);
+                    self.dirty = true;
+                    self.tags.summary = Some("Unsaved changes  Ctrl+s: Save".to_string());
+                }
+            }
+            AppActions::Save if self.is_bulk() => {
+                self.start_bulk_edit(jobs);
+                *app_mode = AppMode::ObjectList;
+            }
+            AppActions::Save => self.save().await,
+            AppActions::GoBack => {
+                if self.dirty && !self.discard_armed {
+                    self.discard_armed = true;
+                    self.tags.summary =
+                        Some("Unsaved changes  Ctrl+s: Save  Esc: Discard".to_string());
+                } else {
+                    *app_mode = AppMode::ObjectList;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
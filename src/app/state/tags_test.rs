@@ -0,0 +1,100 @@
+use crate::app::state::tags::{TagEdit, TagEntry, parse_tag};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_tag() {
+        assert_eq!(
+            parse_tag(" team = data ", false),
+            Ok(TagEntry {
+                key: "team".to_string(),
+                value: Some("data".to_string()),
+            })
+        );
+        // Only the first = separates the key
+        assert_eq!(
+            parse_tag("query=a=b", false).map(|tag| tag.value),
+            Ok(Some("a=b".to_string()))
+        );
+        assert_eq!(
+            parse_tag("-team", true),
+            Ok(TagEntry {
+                key: "team".to_string(),
+                value: None,
+            })
+        );
+        // Outside bulk mode a leading - is part of a key=value pair
+        assert_eq!(
+            parse_tag("-team=x", false).map(|tag| tag.key),
+            Ok("-team".to_string())
+        );
+        assert!(parse_tag("-team", false).is_err());
+        assert!(parse_tag("team", true).is_err());
+    }
+
+    #[test]
+    fn test_parse_tag_validation() {
+        assert!(parse_tag("=value", false).is_err());
+        assert!(parse_tag("aws:createdBy=me", false).is_err());
+        assert!(parse_tag(&format!("{}=v", "k".repeat(128)), false).is_ok());
+        assert!(parse_tag(&format!("{}=v", "k".repeat(129)), false).is_err());
+        assert!(parse_tag(&format!("k={}", "v".repeat(256)), false).is_ok());
+        assert!(parse_tag(&format!("k={}", "v".repeat(257)), false).is_err());
+        // Limits count characters, and surrounding spaces are not part of the value
+        assert!(parse_tag(&format!("k={}", "é".repeat(256)), false).is_ok());
+        assert!(parse_tag(&format!("k= {} ", "v".repeat(256)), false).is_ok());
+    }
+
+    #[test]
+    fn test_bulk_edit_merges_into_existing_tags() {
+        let edit = TagEdit {
+            set: tags(&[("team", "data"), ("env", "prod")]),
+            remove: vec!["temp".to_string(), "missing".to_string()],
+        };
+        assert_eq!(edit.to_string(), "+team=data +env=prod -temp -missing");
+
+        let mut existing = tags(&[("owner", "ops"), ("team", "web"), ("temp", "1")]);
+        edit.apply(&mut existing).unwrap();
+        assert_eq!(
+            existing,
+            tags(&[("owner", "ops"), ("team", "data"), ("env", "prod")])
+        );
+    }
+
+    #[test]
+    fn test_bulk_edit_respects_the_tag_limit() {
+        let full: Vec<(String, String)> =
+            (0..10).map(|i| (format!("k{}", i), "v".into())).collect();
+
+        let mut tags = full.clone();
+        let add = TagEdit {
+            set: vec![("extra".to_string(), "v".to_string())],
+            remove: Vec::new(),
+        };
+        assert!(add.apply(&mut tags).is_err());
+
+        // Removing first makes room, and overwriting does not add a tag
+        let mut tags = full.clone();
+        let swap = TagEdit {
+            set: vec![("extra".to_string(), "v".to_string())],
+            remove: vec!["k0".to_string()],
+        };
+        assert!(swap.apply(&mut tags).is_ok());
+        let mut tags = full;
+        let overwrite = TagEdit {
+            set: vec![("k9".to_string(), "new".to_string())],
+            remove: Vec::new(),
+        };
+        assert!(overwrite.apply(&mut tags).is_ok());
+        assert_eq!(tags[9].1, "new");
+    }
+}
@@ -0,0 +1,276 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::aws::s3_client::{S3Client, list_error_reason};
+use crate::ui::ScrollableList;
+use crate::usage::{Breakdown, DIRECT_OBJECTS, Usage, UsageRow, UsageSort};
+use crate::util::{format_eta, format_size};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+#[derive(Debug)]
+enum UsageEvent {
+    /// The totals of one page of the listing
+    Page(Usage),
+    Failed(String),
+}
+
+/// A finished scan, kept so reopening a prefix is instant
+#[derive(Debug, Clone)]
+struct CachedUsage {
+    usage: Usage,
+    computed_at: SystemTime,
+}
+
+/// `du` for a bucket or prefix: lists every object in the background and totals them by
+/// storage class and by immediate sub-prefix
+#[derive(Debug)]
+pub struct UsageState {
+    pub bucket: String,
+    /// The scanned prefix, empty for the whole bucket
+    pub prefix: String,
+    pub usage: Usage,
+    pub rows: ScrollableList<UsageRow>,
+    pub breakdown: Breakdown,
+    pub sort: UsageSort,
+    /// When the shown totals were computed, `None` while scanning
+    pub computed_at: Option<SystemTime>,
+    pub pages: usize,
+    pub error: Option<String>,
+    pub return_mode: AppMode,
+    /// Prefixes drilled down from, returned to by Esc
+    parents: Vec<String>,
+    cache: HashMap<(String, String), CachedUsage>,
+    receiver: Option<mpsc::UnboundedReceiver<UsageEvent>>,
+    task: Option<JoinHandle<()>>,
+    s3_client: Arc<S3Client>,
+}
+
+impl UsageState {
+    pub fn new(s3_client: Arc<S3Client>) -> Self {
+        Self {
+            bucket: String::new(),
+            prefix: String::new(),
+            usage: Usage::default(),
+            rows: ScrollableList::new("Usage"),
+            breakdown: Breakdown::Prefix,
+            sort: UsageSort::Bytes,
+            computed_at: None,
+            pages: 0,
+            error: None,
+            return_mode: AppMode::BucketList,
+            parents: Vec::new(),
+            cache: HashMap::new(),
+            receiver: None,
+            task: None,
+            s3_client,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// Show the usage of `prefix` in `bucket`, from the cache if it was scanned before
+    pub fn open(&mut self, bucket: &str, prefix: &str, return_mode: AppMode) {
+        self.return_mode = return_mode;
+        self.parents.clear();
+        self.show(bucket, prefix);
+    }
+
+    fn show(&mut self, bucket: &str, prefix: &str) {
+        self.cancel();
+        self.bucket = bucket.to_string();
+        self.prefix = prefix.to_string();
+        self.error = None;
+        self.rows = ScrollableList::new("Usage");
+        match self.cache.get(&(self.bucket.clone(), self.prefix.clone())) {
+            Some(cached) => {
+                self.usage = cached.usage.clone();
+                self.computed_at = Some(cached.computed_at);
+                self.refresh_rows();
+            }
+            None => self.start(),
+        }
+    }
+
+    /// Scan the prefix again, replacing any cached totals
+    pub fn start(&mut self) {
+        self.cancel();
+        self.usage = Usage::default();
+        self.computed_at = None;
+        self.pages = 0;
+        self.error = None;
+        self.refresh_rows();
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let s3_client = self.s3_client.clone();
+        let bucket = self.bucket.clone();
+        let prefix = self.prefix.clone();
+        self.receiver = Some(receiver);
+        self.task = Some(tokio::spawn(async move {
+            let listing_prefix = Some(prefix.clone()).filter(|p| !p.is_empty());
+            let mut continuation_token = None;
+            loop {
+                let (objects, next_token) = match s3_client
+                    .list_objects(&bucket, continuation_token, listing_prefix.clone(), 1000)
+                    .await
+                {
+                    Ok(page) => page,
+                    Err(e) => {
+                        let _ = sender.send(UsageEvent::Failed(list_error_reason(&e)));
+                        break;
+                    }
+                };
+
+                let mut page = Usage::default();
+                for object in &objects {
+                    if let Some(key) = object.key() {
+                        page.add(
+                            &prefix,
+                            key,
+                            object.size().unwrap_or(0).max(0) as u64,
+                            object.storage_class().map(|class| class.as_str()),
+                        );
+                    }
+                }
+                if sender.send(UsageEvent::Page(page)).is_err() {
+                    break;
+                }
+
+                match next_token {
+                    Some(token) => continuation_token = Some(token),
+                    None => break,
+                }
+            }
+        }));
+    }
+
+    /// Stop the running scan, if any
+    pub fn cancel(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+        self.receiver = None;
+    }
+
+    /// Add up the pages listed so far, caching the totals once the scan is done
+    pub fn poll(&mut self) {
+        let Some(receiver) = self.receiver.as_mut() else {
+            return;
+        };
+
+        let mut changed = false;
+        loop {
+            match receiver.try_recv() {
+                Ok(UsageEvent::Page(page)) => {
+                    self.usage.merge(&page);
+                    self.pages += 1;
+                    changed = true;
+                }
+                Ok(UsageEvent::Failed(reason)) => {
+                    self.error = Some(reason);
+                    changed = true;
+                }
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    self.receiver = None;
+                    self.task = None;
+                    if self.error.is_none() {
+                        let computed_at = SystemTime::now();
+                        self.computed_at = Some(computed_at);
+                        self.cache.insert(
+                            (self.bucket.clone(), self.prefix.clone()),
+                            CachedUsage {
+                                usage: self.usage.clone(),
+                                computed_at,
+                            },
+                        );
+                    }
+                    changed = true;
+                    break;
+                }
+            }
+        }
+        if changed {
+            self.refresh_rows();
+        }
+    }
+
+    fn refresh_rows(&mut self) {
+        let selected = self.rows.selected_item().map(|row| row.name.clone());
+        let rows = self.usage.rows(self.breakdown, self.sort);
+        let index = selected
+            .and_then(|name| rows.iter().position(|row| row.name == name))
+            .unwrap_or(0);
+
+        self.rows = ScrollableList::new(format!(
+            "Usage of s3://{}/{}: {} objects, {} by {}, sorted by {}",
+            self.bucket,
+            self.prefix,
+            self.usage.total.objects,
+            format_size(self.usage.total.bytes as i64),
+            self.breakdown.label(),
+            self.sort.label()
+        ));
+        self.rows.filtered_indices = (0..rows.len()).collect();
+        self.rows.append_items(rows);
+        self.rows.set_has_more(false);
+        self.rows.set_loading(self.is_running());
+        if !self.rows.is_empty() {
+            self.rows.select(Some(index));
+        }
+
+        self.rows.summary = match (&self.error, self.computed_at) {
+            (Some(error), _) => Some(format!("Scan failed: {}", error)),
+            (None, Some(computed_at)) => {
+                let age = computed_at.elapsed().unwrap_or_default();
+                Some(format!("Computed {} ago. r: Recompute", format_eta(age)))
+            }
+            (None, None) => Some(format!("Scanning... {} pages listed", self.pages)),
+        };
+    }
+
+    pub fn handle_action(&mut self, action: AppActions, app_mode: &mut AppMode) {
+        match action {
+            AppActions::MoveDown => self.rows.next(),
+            AppActions::MoveUp => self.rows.previous(),
+            AppActions::MoveToTop => self.rows.first(),
+            AppActions::MoveToBottom => self.rows.last(),
+            AppActions::MoveLeft | AppActions::MoveRight => {
+                self.breakdown = self.breakdown.next();
+                self.rows.first();
+                self.refresh_rows();
+            }
+            AppActions::Sort => {
+                self.sort = self.sort.next();
+                self.refresh_rows();
+            }
+            AppActions::Refresh => self.start(),
+            AppActions::Enter if self.breakdown == Breakdown::Prefix => {
+                let Some(name) = self.rows.selected_item().map(|row| row.name.clone()) else {
+                    return;
+                };
+                if name != DIRECT_OBJECTS {
+                    self.parents.push(self.prefix.clone());
+                    let (bucket, prefix) =
+                        (self.bucket.clone(), format!("{}{}", self.prefix, name));
+                    self.show(&bucket, &prefix);
+                }
+            }
+            AppActions::GoBack => match self.parents.pop() {
+                Some(parent) => {
+                    let bucket = self.bucket.clone();
+                    self.show(&bucket, &parent);
+                }
+                None => {
+                    self.cancel();
+                    *app_mode = self.return_mode;
+                }
+            },
+            _ => {}
+        }
+    }
+}
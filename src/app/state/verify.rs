@@ -0,0 +1,78 @@
+use crate::app::AppMode;
+use crate::app::actions::AppActions;
+use crate::aws::s3_client::S3Client;
+use crate::search::SearchBar;
+use crate::util::expand_tilde;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Prompt for a local file to compare with the selected object
+#[derive(Debug)]
+pub struct VerifyState {
+    pub prompt: SearchBar,
+    pub bucket: String,
+    pub key: String,
+    /// Outcome of the last comparison
+    pub result: Option<String>,
+    s3_client: Arc<S3Client>,
+}
+
+impl VerifyState {
+    pub fn new(s3_client: Arc<S3Client>) -> Self {
+        Self {
+            prompt: SearchBar::default(),
+            bucket: String::new(),
+            key: String::new(),
+            result: None,
+            s3_client,
+        }
+    }
+
+    /// Open the prompt, suggesting where the object would have been downloaded
+    pub fn open(&mut self, bucket: &str, key: &str, default_path: &Path) {
+        self.bucket = bucket.to_string();
+        self.key = key.to_string();
+        self.result = None;
+        self.prompt.active = true;
+        self.prompt.set_query(&default_path.to_string_lossy());
+        self.prompt.error = None;
+    }
+
+    pub async fn handle_action(&mut self, action: AppActions, app_mode: &mut AppMode) {
+        match action {
+            AppActions::SearchInput(c) => {
+                self.prompt.input(c);
+                self.prompt.error = None;
+            }
+            AppActions::SearchDelete => {
+                self.prompt.delete();
+                self.prompt.error = None;
+            }
+            AppActions::Enter => {
+                let path = expand_tilde(self.prompt.query.trim());
+                if !path.is_file() {
+                    self.prompt.error = Some("not a file".to_string());
+                    return;
+                }
+                self.result = Some(
+                    match self
+                        .s3_client
+                        .verify_file(&self.bucket, &self.key, &path)
+                        .await
+                    {
+                        Ok(verification) => format!(
+                            "{} and s3://{}/{}: {}",
+                            path.display(),
+                            self.bucket,
+                            self.key,
+                            verification
+                        ),
+                        Err(e) => format!("Could not verify: {}", e),
+                    },
+                );
+            }
+            AppActions::GoBack => *app_mode = AppMode::ObjectList,
+            _ => {}
+        }
+    }
+}
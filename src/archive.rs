@@ -0,0 +1,146 @@
+use std::fmt;
+
+/// Classes whose objects must be restored before they can be read
+pub const ARCHIVE_CLASSES: [&str; 2] = ["GLACIER", "DEEP_ARCHIVE"];
+
+/// Classes an object can be moved to with a self-copy
+pub const STORAGE_CLASSES: [&str; 7] = [
+    "STANDARD",
+    "STANDARD_IA",
+    "ONEZONE_IA",
+    "INTELLIGENT_TIERING",
+    "GLACIER_IR",
+    "GLACIER",
+    "DEEP_ARCHIVE",
+];
+
+/// Whether objects of this class (STANDARD when absent) need a restore to be read
+pub fn is_archived(storage_class: Option<&str>) -> bool {
+    storage_class.is_some_and(|class| ARCHIVE_CLASSES.contains(&class))
+}
+
+/// How fast, and how expensively, an archived object is restored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreTier {
+    Standard,
+    Bulk,
+    Expedited,
+}
+
+impl RestoreTier {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Standard => Self::Bulk,
+            Self::Bulk => Self::Expedited,
+            Self::Expedited => Self::Standard,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Standard => "Standard",
+            Self::Bulk => "Bulk",
+            Self::Expedited => "Expedited",
+        }
+    }
+
+    /// Typical time until the restored copy is readable
+    pub fn typical_time(self, storage_class: &str) -> &'static str {
+        let deep = storage_class == "DEEP_ARCHIVE";
+        match self {
+            Self::Standard if deep => "within 12 hours",
+            Self::Standard => "3-5 hours",
+            Self::Bulk if deep => "within 48 hours",
+            Self::Bulk => "5-12 hours",
+            Self::Expedited => "1-5 minutes",
+        }
+    }
+
+    /// S3 has no expedited retrieval from Deep Archive
+    pub fn check(self, storage_class: &str) -> Result<(), String> {
+        if self == Self::Expedited && storage_class == "DEEP_ARCHIVE" {
+            return Err(
+                "DEEP_ARCHIVE objects cannot be restored with the Expedited tier".to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RestoreTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A restore to request: a temporary copy kept for `days`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestoreRequest {
+    pub days: i32,
+    pub tier: RestoreTier,
+}
+
+impl Default for RestoreRequest {
+    fn default() -> Self {
+        Self {
+            days: 7,
+            tier: RestoreTier::Standard,
+        }
+    }
+}
+
+pub fn parse_restore_days(input: &str) -> Result<i32, String> {
+    match input.trim().parse::<i32>() {
+        Ok(days) if days >= 1 => Ok(days),
+        _ => Err(format!(
+            "'{}' is not a number of days (1 or more)",
+            input.trim()
+        )),
+    }
+}
+
+/// The restore state HeadObject reports in its `x-amz-restore` header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoreStatus {
+    NotRestored,
+    InProgress,
+    /// A restored copy is readable until the expiry date
+    Restored {
+        expiry: Option<String>,
+    },
+}
+
+impl RestoreStatus {
+    /// Parses e.g. `ongoing-request="false", expiry-date="Fri, 21 Dec 2012 00:00:00 GMT"`
+    pub fn parse(header: Option<&str>) -> Self {
+        let Some(header) = header else {
+            return Self::NotRestored;
+        };
+        if header.contains(r#"ongoing-request="true""#) {
+            return Self::InProgress;
+        }
+        let expiry = header
+            .split_once(r#"expiry-date=""#)
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .map(|(date, _)| date.to_string());
+        Self::Restored { expiry }
+    }
+
+    /// Whether an object of `storage_class` in this state can be read
+    pub fn readable(&self, storage_class: Option<&str>) -> bool {
+        !is_archived(storage_class) || matches!(self, Self::Restored { .. })
+    }
+}
+
+impl fmt::Display for RestoreStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotRestored => write!(f, "not restored"),
+            Self::InProgress => write!(f, "restore in progress"),
+            Self::Restored {
+                expiry: Some(expiry),
+            } => write!(f, "restored until {}", expiry),
+            Self::Restored { expiry: None } => write!(f, "restored"),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use crate::archive::{RestoreStatus, RestoreTier, is_archived, parse_restore_days};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_archived() {
+        assert!(is_archived(Some("GLACIER")));
+        assert!(is_archived(Some("DEEP_ARCHIVE")));
+        assert!(!is_archived(Some("GLACIER_IR")));
+        assert!(!is_archived(None));
+    }
+
+    #[test]
+    fn test_restore_status_parse() {
+        assert_eq!(RestoreStatus::parse(None), RestoreStatus::NotRestored);
+        assert_eq!(
+            RestoreStatus::parse(Some(r#"ongoing-request="true""#)),
+            RestoreStatus::InProgress
+        );
+        let restored = RestoreStatus::parse(Some(
+            r#"ongoing-request="false", expiry-date="Fri, 21 Dec 2012 00:00:00 GMT""#,
+        ));
+        assert_eq!(
+            restored.to_string(),
+            "restored until Fri, 21 Dec 2012 00:00:00 GMT"
+        );
+        assert!(restored.readable(Some("GLACIER")));
+        assert!(!RestoreStatus::InProgress.readable(Some("DEEP_ARCHIVE")));
+        assert!(RestoreStatus::NotRestored.readable(Some("STANDARD_IA")));
+    }
+
+    #[test]
+    fn test_restore_request_fields() {
+        assert_eq!(parse_restore_days(" 14 "), Ok(14));
+        assert!(parse_restore_days("0").is_err());
+        assert!(parse_restore_days("week").is_err());
+        assert!(RestoreTier::Expedited.check("DEEP_ARCHIVE").is_err());
+        assert!(RestoreTier::Expedited.check("GLACIER").is_ok());
+        assert_eq!(RestoreTier::Expedited.next(), RestoreTier::Standard);
+    }
+}
@@ -4,7 +4,10 @@ use color_eyre::Result;
 use env_config::file;
 
 pub mod s3_client;
+#[cfg(test)]
+mod s3_client_test;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug)]
 pub struct AWS {
     pub config: SdkConfig,
@@ -16,6 +19,15 @@ impl AWS {
         Self { config }
     }
 
+    /// The profile the SDK loads credentials for
+    pub fn profile_name() -> String {
+        std::env::var("AWS_PROFILE")
+            .ok()
+            .filter(|profile| !profile.is_empty())
+            .unwrap_or_else(|| "default".to_string())
+    }
+
+    #[allow(dead_code)]
     async fn get_profile_set() -> Result<Vec<String>> {
         let fs = os_shim_internal::Fs::real();
         let env = os_shim_internal::Env::real();
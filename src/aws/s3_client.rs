@@ -1,18 +1,199 @@
+use crate::archive::{RestoreRequest, RestoreStatus};
+use crate::bucket::{BucketSettings, Encryption};
+use crate::checksum::{self, Algorithm, Checksums, Hasher, Verification};
+use crate::lifecycle::{
+    self, AbortIncompleteUpload, AndFilter, Expiration, LifecycleConfig, NoncurrentExpiration,
+    NoncurrentTransition, RuleFilter, RuleTag, format_lifecycle_date, parse_lifecycle_date,
+};
+use crate::throttle::TransferLimits;
+use crate::util::{decompress_if_gzip, percent_encode};
 use aws_sdk_s3::Client;
-use aws_sdk_s3::types::{Bucket, Object};
+use aws_sdk_s3::config::Region;
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::operation::get_object::{GetObjectError, GetObjectOutput};
+use aws_sdk_s3::operation::get_object_acl::GetObjectAclOutput;
+use aws_sdk_s3::operation::head_object::HeadObjectOutput;
+use aws_sdk_s3::operation::list_objects_v2::ListObjectsV2Error;
+use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::primitives::{ByteStream, DateTime, DateTimeFormat, SdkBody};
+use aws_sdk_s3::types::{
+    AbortIncompleteMultipartUpload, AccessControlPolicy, Bucket, BucketLifecycleConfiguration,
+    BucketLocationConstraint, BucketVersioningStatus, ChecksumAlgorithm, ChecksumMode,
+    CompletedMultipartUpload, CompletedPart, CreateBucketConfiguration, Delete, ExpirationStatus,
+    GlacierJobParameters, LifecycleExpiration, LifecycleRule, LifecycleRuleAndOperator,
+    LifecycleRuleFilter, MetadataDirective, NoncurrentVersionExpiration,
+    NoncurrentVersionTransition, Object, ObjectIdentifier, ObjectOwnership, Permission,
+    PublicAccessBlockConfiguration, ServerSideEncryption, ServerSideEncryptionByDefault,
+    ServerSideEncryptionConfiguration, ServerSideEncryptionRule, StorageClass, Tag, Tagging,
+    TaggingDirective, Tier, Transition, TransitionStorageClass, VersioningConfiguration,
+};
 use aws_types::SdkConfig;
-use color_eyre::Result;
+use bytes::Bytes;
 use color_eyre::eyre::eyre;
+use color_eyre::{Report, Result};
+use http_body::{Frame, SizeHint};
+use std::collections::HashMap;
+use std::io::{Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, ready};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::sync::{AcquireError, OwnedSemaphorePermit, Semaphore, mpsc};
+
+/// CopyObject only handles sources up to 5GB; larger objects are copied in parts
+const MULTIPART_COPY_THRESHOLD: i64 = 5 * 1024 * 1024 * 1024;
+const COPY_PART_SIZE: i64 = 512 * 1024 * 1024;
+/// Files larger than this are uploaded in parts
+const MULTIPART_UPLOAD_THRESHOLD: u64 = 64 * 1024 * 1024;
+const UPLOAD_PART_SIZE: u64 = 16 * 1024 * 1024;
+/// Upload bodies are read, paced and reported in chunks of this size
+const BODY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Follows a transfer as it goes: `advance` is called with the size of each chunk moved,
+/// and an error from it aborts the transfer
+pub trait TransferProgress: Send + Sync {
+    fn advance(&self, bytes: u64) -> impl Future<Output = Result<()>> + Send;
+
+    /// Called between steps that move no data through the app, such as the parts of a
+    /// server-side copy; an error aborts the transfer
+    fn checkpoint(&self) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+}
+
+/// Transfers nobody is watching
+impl TransferProgress for () {
+    async fn advance(&self, _bytes: u64) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Where a page of `list_versions_page` starts
+#[derive(Debug, Clone, Default)]
+pub struct VersionMarker {
+    key: Option<String>,
+    version_id: Option<String>,
+}
+
+/// Content headers and user metadata that can be rewritten with a self-copy
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObjectHeaders {
+    pub content_type: Option<String>,
+    pub cache_control: Option<String>,
+    pub content_encoding: Option<String>,
+    pub content_disposition: Option<String>,
+    pub content_language: Option<String>,
+    /// Raw HTTP date, as S3 returns it
+    pub expires: Option<String>,
+    pub website_redirect_location: Option<String>,
+    pub metadata: HashMap<String, String>,
+}
+
+impl ObjectHeaders {
+    pub fn from_head(head: &HeadObjectOutput) -> Self {
+        Self {
+            content_type: head.content_type().map(String::from),
+            cache_control: head.cache_control().map(String::from),
+            content_encoding: head.content_encoding().map(String::from),
+            content_disposition: head.content_disposition().map(String::from),
+            content_language: head.content_language().map(String::from),
+            expires: head.expires_string().map(String::from),
+            website_redirect_location: head.website_redirect_location().map(String::from),
+            metadata: head.metadata().cloned().unwrap_or_default(),
+        }
+    }
+
+    /// `expires` as a date. S3 stores Expires as given, so a value that is not an HTTP date is
+    /// an error rather than being dropped by the rewrite.
+    pub fn expires_date(&self) -> Result<Option<DateTime>> {
+        let Some(expires) = self.expires.as_deref() else {
+            return Ok(None);
+        };
+        DateTime::from_str(expires, DateTimeFormat::HttpDate)
+            .map(Some)
+            .map_err(|_| {
+                eyre!(
+                    "Expires '{}' is not an HTTP date; fix or remove it with H first",
+                    expires
+                )
+            })
+    }
+}
 
 #[derive(Debug)]
 pub struct S3Client {
     client: Client,
+    config: SdkConfig,
+    /// Clients for buckets outside the configured region, keyed by bucket name
+    bucket_clients: Mutex<HashMap<String, Client>>,
+    limits: TransferLimits,
 }
 
 impl S3Client {
     pub fn new(config: &SdkConfig) -> Self {
         let client = Client::new(config);
-        Self { client }
+        Self {
+            client,
+            config: config.clone(),
+            bucket_clients: Mutex::new(HashMap::new()),
+            limits: TransferLimits::default(),
+        }
+    }
+
+    /// Bandwidth and request limits for every transfer, adjustable while they run
+    pub fn limits(&self) -> &TransferLimits {
+        &self.limits
+    }
+
+    /// Returns a client for the region `bucket` lives in, so requests are not redirected
+    async fn client_for(&self, bucket: &str) -> Client {
+        if let Some(client) = self.bucket_clients.lock().unwrap().get(bucket) {
+            return client.clone();
+        }
+
+        let region = match self
+            .client
+            .get_bucket_location()
+            .bucket(bucket)
+            .send()
+            .await
+        {
+            Ok(output) => match output.location_constraint().map(|c| c.as_str()) {
+                // Buckets in us-east-1 report no location constraint
+                None | Some("") => "us-east-1".to_string(),
+                Some("EU") => "eu-west-1".to_string(),
+                Some(region) => region.to_string(),
+            },
+            // Fall back to the configured region and let the request itself report errors.
+            // An answer from S3 (e.g. access denied) will not change, so remember it; a network
+            // error may, so look again next time.
+            Err(e) => {
+                if matches!(e, SdkError::ServiceError(_)) {
+                    self.bucket_clients
+                        .lock()
+                        .unwrap()
+                        .insert(bucket.to_string(), self.client.clone());
+                }
+                return self.client.clone();
+            }
+        };
+
+        let client = if self.config.region().map(|r| r.as_ref()) == Some(region.as_str()) {
+            self.client.clone()
+        } else {
+            let config = aws_sdk_s3::config::Builder::from(&self.config)
+                .region(Region::new(region))
+                .build();
+            Client::from_conf(config)
+        };
+
+        self.bucket_clients
+            .lock()
+            .unwrap()
+            .insert(bucket.to_string(), client.clone());
+        client
     }
 
     pub async fn get_bucket_list(
@@ -40,7 +221,8 @@ impl S3Client {
         max_keys: i32,
     ) -> Result<(Vec<Object>, Option<String>)> {
         let mut request = self
-            .client
+            .client_for(bucket)
+            .await
             .list_objects_v2()
             .bucket(bucket)
             .max_keys(max_keys);
@@ -61,6 +243,31 @@ impl S3Client {
         Ok((objects, next_token))
     }
 
+    /// Lists every key under `prefix`, following continuation tokens
+    pub async fn list_all_keys(&self, bucket: &str, prefix: &str) -> Result<Vec<String>> {
+        let objects = self.list_all_objects(bucket, prefix).await?;
+        Ok(objects
+            .iter()
+            .filter_map(|o| o.key().map(String::from))
+            .collect())
+    }
+
+    /// Lists every object under `prefix` with its size, ETag and modification time
+    pub async fn list_all_objects(&self, bucket: &str, prefix: &str) -> Result<Vec<Object>> {
+        let mut all_objects = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            let (objects, next_token) = self
+                .list_objects(bucket, continuation_token, Some(prefix.to_string()), 1000)
+                .await?;
+            all_objects.extend(objects);
+            match next_token {
+                Some(token) => continuation_token = Some(token),
+                None => return Ok(all_objects),
+            }
+        }
+    }
+
     /// Returns (content, content_type)
     pub async fn get_object_content(
         &self,
@@ -68,13 +275,17 @@ impl S3Client {
         key: &str,
     ) -> Result<(String, Option<String>)> {
         let response = self
-            .client
+            .client_for(bucket)
+            .await
             .get_object()
             .bucket(bucket)
             .key(key)
             .send()
             .await
-            .map_err(|e| color_eyre::eyre::eyre!("Failed to get object: {}", e))?;
+            .map_err(|e| {
+                archived_error(key, &e)
+                    .unwrap_or_else(|| color_eyre::eyre::eyre!("Failed to get object: {}", e))
+            })?;
 
         let content_type = response.content_type().map(String::from);
         let body_bytes = response
@@ -85,7 +296,7 @@ impl S3Client {
             .into_bytes();
 
         // Try to decode as UTF-8. If it fails, it's likely binary or a different encoding.
-        let content_string = String::from_utf8(body_bytes.to_vec())
+        let content_string = String::from_utf8(decompress_if_gzip(body_bytes.to_vec()))
             .unwrap_or_else(|_| String::from("[Binary content - not valid UTF-8]"));
 
         Ok((content_string, content_type))
@@ -93,32 +304,1642 @@ impl S3Client {
 
     /// Fetches the raw bytes of an S3 object.
     pub async fn download_object_bytes(&self, bucket: &str, key: &str) -> Result<Vec<u8>> {
+        self.download_object(bucket, key)
+            .await
+            .map(|(bytes, _)| bytes)
+    }
+
+    /// Streams an object's body into `writer`, returning the number of bytes written
+    pub async fn stream_object<W: AsyncWrite + Unpin, P: TransferProgress>(
+        &self,
+        bucket: &str,
+        key: &str,
+        writer: &mut W,
+        progress: &P,
+    ) -> Result<u64> {
+        let _permit = self.limits.requests.acquire().await;
         let response = self
-            .client
+            .client_for(bucket)
+            .await
             .get_object()
             .bucket(bucket)
             .key(key)
+            .checksum_mode(ChecksumMode::Enabled)
+            .send()
+            .await
+            .map_err(|e| {
+                archived_error(key, &e)
+                    .unwrap_or_else(|| eyre!("Failed to download {}/{}: {}", bucket, key, e))
+            })?;
+
+        let expected = get_checksums(&response);
+        let mut hasher = Hasher::new(&expected.algorithms());
+        let mut body = response.body;
+        let mut written = 0;
+        while let Some(chunk) = body.try_next().await? {
+            hasher.update(&chunk);
+            writer.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+            self.limits.bandwidth.take(chunk.len() as u64).await;
+            progress.advance(chunk.len() as u64).await?;
+        }
+        writer.flush().await?;
+        check_download(bucket, key, &expected, hasher)?;
+        Ok(written)
+    }
+
+    /// Streams an object to `path` through a `.part` file, so an interrupted download never
+    /// looks complete. Returns the number of bytes written.
+    pub async fn download_to_file<P: TransferProgress>(
+        &self,
+        bucket: &str,
+        key: &str,
+        path: &Path,
+        progress: &P,
+    ) -> Result<u64> {
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".part");
+        let partial = PathBuf::from(partial);
+
+        let result = async {
+            let mut file = tokio::fs::File::create(&partial)
+                .await
+                .map_err(|e| eyre!("Failed to create {}: {}", partial.display(), e))?;
+            let written = self.stream_object(bucket, key, &mut file, progress).await?;
+            tokio::fs::rename(&partial, path)
+                .await
+                .map_err(|e| eyre!("Failed to write {}: {}", path.display(), e))?;
+            Ok(written)
+        }
+        .await;
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&partial).await;
+        }
+        result
+    }
+
+    /// Returns the raw body together with the ETag of the version that was read
+    pub async fn download_object(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> Result<(Vec<u8>, Option<String>)> {
+        let _permit = self.limits.requests.acquire().await;
+        let response = self
+            .client_for(bucket)
+            .await
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .checksum_mode(ChecksumMode::Enabled)
             .send()
             .await
             .map_err(|sdk_err| {
-                eyre!(
-                    "S3 SDK Error getting object for download (bucket: {}, key: {}): {}",
+                archived_error(key, &sdk_err).unwrap_or_else(|| {
+                    eyre!(
+                        "S3 SDK Error getting object for download (bucket: {}, key: {}): {}",
+                        bucket,
+                        key,
+                        sdk_err
+                    )
+                })
+            })?;
+
+        let etag = response.e_tag().map(String::from);
+        let expected = get_checksums(&response);
+        let mut hasher = Hasher::new(&expected.algorithms());
+        let mut body = response.body;
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.try_next().await.map_err(|sdk_err| {
+            eyre!(
+                "S3 SDK Error collecting object body for download (bucket: {}, key: {}): {}",
+                bucket,
+                key,
+                sdk_err
+            )
+        })? {
+            hasher.update(&chunk);
+            bytes.extend_from_slice(&chunk);
+            self.limits.bandwidth.take(chunk.len() as u64).await;
+        }
+        check_download(bucket, key, &expected, hasher)?;
+        Ok((bytes, etag))
+    }
+
+    /// Replaces an object's body, keeping its headers, metadata, tags, storage class and
+    /// encryption. Fails without writing if the object no longer has `expected_etag`.
+    pub async fn replace_object_body(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: Vec<u8>,
+        expected_etag: &str,
+    ) -> Result<()> {
+        let client = self.client_for(bucket).await;
+        let head = self.head_object(bucket, key).await?;
+        let headers = ObjectHeaders::from_head(&head);
+        let expires = headers.expires_date()?;
+        let (sse, kms_key) = kept_encryption(&head);
+        let tagging = tagging_header(&client, bucket, key).await?;
+        // A PutObject resets the ACL to private, like CopyObject
+        let acl = saved_acl(&client, bucket, key).await;
+        let mut hasher = Hasher::new(&[Algorithm::Crc32c]);
+        hasher.update(&body);
+        let crc32c = hasher.finish();
+
+        let _permit = self.limits.requests.acquire().await;
+        self.limits.bandwidth.take(body.len() as u64).await;
+        client
+            .put_object()
+            .bucket(bucket)
+            .key(key)
+            .set_checksum_crc32_c(crc32c.get(Algorithm::Crc32c).map(String::from))
+            .body(ByteStream::from(body))
+            .if_match(expected_etag)
+            .set_metadata(Some(headers.metadata))
+            .set_content_type(headers.content_type)
+            .set_content_encoding(headers.content_encoding)
+            .set_content_disposition(headers.content_disposition)
+            .set_content_language(headers.content_language)
+            .set_cache_control(headers.cache_control)
+            .set_expires(expires)
+            .set_website_redirect_location(headers.website_redirect_location)
+            .set_storage_class(head.storage_class().cloned())
+            .set_server_side_encryption(sse)
+            .set_ssekms_key_id(kms_key)
+            .set_tagging(tagging)
+            .send()
+            .await
+            .map_err(|e| match e.code() {
+                Some("PreconditionFailed") => {
+                    eyre!("{} was changed in S3 after it was downloaded", key)
+                }
+                _ => eyre!("Failed to upload {}/{}: {}", bucket, key, e),
+            })?;
+        restore_acl(&client, bucket, key, acl).await
+    }
+
+    /// Copies an object, possibly across buckets and regions, keeping its metadata, tags and
+    /// storage class. Objects over 5GB are copied with a multipart upload.
+    pub async fn copy_object<P: TransferProgress>(
+        &self,
+        src_bucket: &str,
+        src_key: &str,
+        dst_bucket: &str,
+        dst_key: &str,
+        progress: &P,
+    ) -> Result<()> {
+        let src_client = self.client_for(src_bucket).await;
+        let dst_client = self.client_for(dst_bucket).await;
+        let copy_source = format!("{}/{}", src_bucket, percent_encode(src_key, true));
+
+        let head = src_client
+            .head_object()
+            .bucket(src_bucket)
+            .key(src_key)
+            .send()
+            .await
+            .map_err(|e| eyre!("Failed to read {}/{}: {}", src_bucket, src_key, e))?;
+        let size = head.content_length().unwrap_or(0);
+
+        if size <= MULTIPART_COPY_THRESHOLD {
+            // CopyObject keeps metadata and tags by default, but not the storage class
+            let _permit = self.limits.requests.acquire().await;
+            dst_client
+                .copy_object()
+                .bucket(dst_bucket)
+                .key(dst_key)
+                .copy_source(copy_source)
+                .metadata_directive(MetadataDirective::Copy)
+                .tagging_directive(TaggingDirective::Copy)
+                .set_storage_class(head.storage_class().cloned())
+                .send()
+                .await
+                .map_err(|e| eyre!("Failed to copy to {}/{}: {}", dst_bucket, dst_key, e))?;
+            return Ok(());
+        }
+
+        let headers = ObjectHeaders::from_head(&head);
+        self.multipart_copy(
+            &src_client,
+            &dst_client,
+            (src_bucket, src_key),
+            (dst_bucket, dst_key),
+            &head,
+            &headers,
+            head.storage_class().cloned(),
+            progress,
+        )
+        .await
+    }
+
+    /// Copies an object with a multipart upload, writing `headers` to the destination
+    #[allow(clippy::too_many_arguments)]
+    async fn multipart_copy<P: TransferProgress>(
+        &self,
+        src_client: &Client,
+        dst_client: &Client,
+        (src_bucket, src_key): (&str, &str),
+        (dst_bucket, dst_key): (&str, &str),
+        head: &HeadObjectOutput,
+        headers: &ObjectHeaders,
+        storage_class: Option<StorageClass>,
+        progress: &P,
+    ) -> Result<()> {
+        let copy_source = format!("{}/{}", src_bucket, percent_encode(src_key, true));
+        let size = head.content_length().unwrap_or(0);
+
+        // Multipart uploads start without metadata or tags, so carry them over explicitly
+        let tagging = tagging_header(src_client, src_bucket, src_key).await?;
+
+        // Encryption keys are bucket specific, so only keep them within a bucket
+        let (sse, kms_key) = if src_bucket == dst_bucket {
+            kept_encryption(head)
+        } else {
+            (None, None)
+        };
+
+        let upload = dst_client
+            .create_multipart_upload()
+            .bucket(dst_bucket)
+            .key(dst_key)
+            .set_metadata(Some(headers.metadata.clone()))
+            .set_content_type(headers.content_type.clone())
+            .set_content_encoding(headers.content_encoding.clone())
+            .set_content_disposition(headers.content_disposition.clone())
+            .set_content_language(headers.content_language.clone())
+            .set_cache_control(headers.cache_control.clone())
+            .set_expires(headers.expires_date()?)
+            .set_website_redirect_location(headers.website_redirect_location.clone())
+            .set_storage_class(storage_class)
+            .set_server_side_encryption(sse)
+            .set_ssekms_key_id(kms_key)
+            .set_tagging(tagging)
+            .send()
+            .await
+            .map_err(|e| eyre!("Failed to start multipart copy: {}", e))?;
+        let upload_id = upload
+            .upload_id()
+            .ok_or_else(|| eyre!("Multipart copy returned no upload id"))?
+            .to_string();
+
+        let result = self
+            .copy_parts(
+                dst_client,
+                &copy_source,
+                head.e_tag(),
+                size,
+                dst_bucket,
+                dst_key,
+                &upload_id,
+                progress,
+            )
+            .await;
+
+        if result.is_err() {
+            // Don't leave billable orphaned parts behind
+            let _ = dst_client
+                .abort_multipart_upload()
+                .bucket(dst_bucket)
+                .key(dst_key)
+                .upload_id(&upload_id)
+                .send()
+                .await;
+        }
+        result
+    }
+
+    /// Sends `length` bytes of `path` from `offset` as the body of the request `send` makes.
+    /// Each chunk is paced by the bandwidth limit and `progress` before the next one is read,
+    /// so both hold while the body is on the wire. A body the SDK resends is paced again
+    /// but only counts once towards `progress`.
+    async fn send_paced<P, T, E, F, Fut>(
+        &self,
+        path: &Path,
+        offset: u64,
+        length: u64,
+        progress: &P,
+        send: F,
+    ) -> Result<T>
+    where
+        P: TransferProgress,
+        E: std::error::Error + Send + Sync + 'static,
+        F: FnOnce(ByteStream) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let credits = Arc::new(Semaphore::new(1));
+        let (sent_tx, mut sent) = mpsc::unbounded_channel();
+        let body = {
+            let path = path.to_path_buf();
+            let credits = credits.clone();
+            SdkBody::retryable(move || {
+                SdkBody::from_body_1_x(PacedFileBody::open(
+                    &path,
+                    offset,
+                    length,
+                    credits.clone(),
+                    sent_tx.clone(),
+                ))
+            })
+        };
+
+        let mut reported = 0;
+        let mut pace = async |bytes: u64| {
+            self.limits.bandwidth.take(bytes).await;
+            let fresh = bytes.min(length - reported);
+            reported += fresh;
+            if let Err(e) = progress.advance(fresh).await {
+                // Fails the body, and with it the request
+                credits.close();
+                return Err(e);
+            }
+            credits.add_permits(1);
+            Ok(())
+        };
+
+        let mut request = std::pin::pin!(send(ByteStream::new(body)));
+        let result = loop {
+            tokio::select! {
+                result = &mut request => break result,
+                Some(bytes) = sent.recv() => pace(bytes).await?,
+            }
+        };
+        // The last chunks can be sent before they are paced
+        while let Ok(bytes) = sent.try_recv() {
+            pace(bytes).await?;
+        }
+        Ok(result?)
+    }
+
+    /// Uploads a local file, in parts when it is large
+    pub async fn upload_file<P: TransferProgress>(
+        &self,
+        bucket: &str,
+        key: &str,
+        path: &Path,
+        progress: &P,
+    ) -> Result<()> {
+        let client = self.client_for(bucket).await;
+        let size = tokio::fs::metadata(path)
+            .await
+            .map_err(|e| eyre!("Failed to read {}: {}", path.display(), e))?
+            .len();
+
+        if size <= MULTIPART_UPLOAD_THRESHOLD {
+            let crc32c = file_crc32c(path, 0, size).await?;
+            let _permit = self.limits.requests.acquire().await;
+            self.send_paced(path, 0, size, progress, |body| {
+                client
+                    .put_object()
+                    .bucket(bucket)
+                    .key(key)
+                    .checksum_crc32_c(crc32c)
+                    .body(body)
+                    .send()
+            })
+            .await
+            .map_err(|e| eyre!("Failed to upload {}/{}: {}", bucket, key, e))?;
+            return Ok(());
+        }
+
+        let upload = client
+            .create_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .checksum_algorithm(ChecksumAlgorithm::Crc32C)
+            .send()
+            .await
+            .map_err(|e| eyre!("Failed to start multipart upload: {}", e))?;
+        let upload_id = upload
+            .upload_id()
+            .ok_or_else(|| eyre!("Multipart upload returned no upload id"))?
+            .to_string();
+
+        let result = async {
+            // S3 allows at most 10,000 parts
+            let part_size = UPLOAD_PART_SIZE.max(size.div_ceil(10_000));
+            let mut parts = Vec::new();
+            let mut offset = 0;
+            while offset < size {
+                let length = part_size.min(size - offset);
+                let part_number = parts.len() as i32 + 1;
+                let crc32c = file_crc32c(path, offset, length).await?;
+                let _permit = self.limits.requests.acquire().await;
+                let part = self
+                    .send_paced(path, offset, length, progress, |body| {
+                        client
+                            .upload_part()
+                            .bucket(bucket)
+                            .key(key)
+                            .upload_id(&upload_id)
+                            .part_number(part_number)
+                            .checksum_crc32_c(crc32c)
+                            .body(body)
+                            .send()
+                    })
+                    .await
+                    .map_err(|e| eyre!("Failed to upload part {}: {}", part_number, e))?;
+                parts.push(
+                    CompletedPart::builder()
+                        .part_number(part_number)
+                        .set_e_tag(part.e_tag().map(String::from))
+                        .set_checksum_crc32_c(part.checksum_crc32_c().map(String::from))
+                        .build(),
+                );
+                offset += length;
+            }
+
+            client
+                .complete_multipart_upload()
+                .bucket(bucket)
+                .key(key)
+                .upload_id(&upload_id)
+                .multipart_upload(
+                    CompletedMultipartUpload::builder()
+                        .set_parts(Some(parts))
+                        .build(),
+                )
+                .send()
+                .await
+                .map_err(|e| eyre!("Failed to complete upload of {}/{}: {}", bucket, key, e))?;
+            Ok(())
+        }
+        .await;
+
+        if result.is_err() {
+            // Don't leave billable orphaned parts behind
+            let _ = client
+                .abort_multipart_upload()
+                .bucket(bucket)
+                .key(key)
+                .upload_id(&upload_id)
+                .send()
+                .await;
+        }
+        result
+    }
+
+    /// Rewrites an object's content headers and user metadata in place with a self-copy.
+    /// Tags, storage class, encryption and any non-default ACL are kept. Fails if the object
+    /// no longer has `expected_etag`.
+    pub async fn replace_object_headers(
+        &self,
+        bucket: &str,
+        key: &str,
+        headers: &ObjectHeaders,
+        expected_etag: Option<&str>,
+    ) -> Result<()> {
+        let head = self.head_object(bucket, key).await?;
+        if expected_etag.is_some() && head.e_tag() != expected_etag {
+            return Err(eyre!("{} changed since the edit was previewed", key));
+        }
+        let storage_class = head.storage_class().cloned();
+        self.rewrite_in_place(bucket, key, &head, Some(headers), storage_class)
+            .await
+    }
+
+    /// Moves an object to another storage class with a self-copy, keeping its headers, tags,
+    /// encryption and ACL. Archived objects must be restored first.
+    pub async fn change_storage_class(
+        &self,
+        bucket: &str,
+        key: &str,
+        storage_class: &str,
+    ) -> Result<()> {
+        let head = self.head_object(bucket, key).await?;
+        let current = head.storage_class().map(|class| class.as_str());
+        if current.unwrap_or("STANDARD") == storage_class {
+            return Ok(());
+        }
+        if !RestoreStatus::parse(head.restore()).readable(current) {
+            return Err(eyre!(
+                "{} is archived in {}; restore it before changing its class",
+                key,
+                current.unwrap_or_default()
+            ));
+        }
+        self.rewrite_in_place(
+            bucket,
+            key,
+            &head,
+            None,
+            Some(StorageClass::from(storage_class)),
+        )
+        .await
+    }
+
+    /// Copies an object onto itself with new headers (or its own when `None`) and the given
+    /// storage class, guarded by its ETag
+    async fn rewrite_in_place(
+        &self,
+        bucket: &str,
+        key: &str,
+        head: &HeadObjectOutput,
+        headers: Option<&ObjectHeaders>,
+        storage_class: Option<StorageClass>,
+    ) -> Result<()> {
+        let client = self.client_for(bucket).await;
+
+        // CopyObject resets the ACL to private, so remember any extra grants
+        let acl = saved_acl(&client, bucket, key).await;
+
+        let own_headers = ObjectHeaders::from_head(head);
+        let headers = headers.unwrap_or(&own_headers);
+        let expires = headers.expires_date()?;
+        if head.content_length().unwrap_or(0) <= MULTIPART_COPY_THRESHOLD {
+            let (sse, kms_key) = kept_encryption(head);
+            let _permit = self.limits.requests.acquire().await;
+            client
+                .copy_object()
+                .bucket(bucket)
+                .key(key)
+                .copy_source(format!("{}/{}", bucket, percent_encode(key, true)))
+                .set_copy_source_if_match(head.e_tag().map(String::from))
+                .metadata_directive(MetadataDirective::Replace)
+                .tagging_directive(TaggingDirective::Copy)
+                .set_metadata(Some(headers.metadata.clone()))
+                .set_content_type(headers.content_type.clone())
+                .set_content_encoding(headers.content_encoding.clone())
+                .set_content_disposition(headers.content_disposition.clone())
+                .set_content_language(headers.content_language.clone())
+                .set_cache_control(headers.cache_control.clone())
+                .set_expires(expires)
+                .set_website_redirect_location(headers.website_redirect_location.clone())
+                .set_storage_class(storage_class)
+                .set_server_side_encryption(sse)
+                .set_ssekms_key_id(kms_key)
+                .send()
+                .await
+                .map_err(|e| eyre!("Failed to update {}/{}: {}", bucket, key, e))?;
+        } else {
+            self.multipart_copy(
+                &client,
+                &client,
+                (bucket, key),
+                (bucket, key),
+                head,
+                headers,
+                storage_class,
+                &(),
+            )
+            .await?;
+        }
+
+        restore_acl(&client, bucket, key, acl).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn copy_parts<P: TransferProgress>(
+        &self,
+        dst_client: &Client,
+        copy_source: &str,
+        source_etag: Option<&str>,
+        size: i64,
+        dst_bucket: &str,
+        dst_key: &str,
+        upload_id: &str,
+        progress: &P,
+    ) -> Result<()> {
+        let mut parts = Vec::new();
+        let part_size = copy_part_size(size);
+        for (idx, start) in (0..size).step_by(part_size as usize).enumerate() {
+            // A pause holds the copy between parts, and a cancel aborts it
+            progress.checkpoint().await?;
+            let part_number = idx as i32 + 1;
+            let end = (start + part_size).min(size) - 1;
+
+            let _permit = self.limits.requests.acquire().await;
+            let output = dst_client
+                .upload_part_copy()
+                .bucket(dst_bucket)
+                .key(dst_key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .copy_source(copy_source)
+                .copy_source_range(format!("bytes={}-{}", start, end))
+                // Fail rather than stitch together parts of different versions
+                .set_copy_source_if_match(source_etag.map(String::from))
+                .send()
+                .await
+                .map_err(|e| eyre!("Failed to copy part {}: {}", part_number, e))?;
+
+            parts.push(
+                CompletedPart::builder()
+                    .part_number(part_number)
+                    .set_e_tag(
+                        output
+                            .copy_part_result()
+                            .and_then(|r| r.e_tag())
+                            .map(String::from),
+                    )
+                    .build(),
+            );
+        }
+
+        dst_client
+            .complete_multipart_upload()
+            .bucket(dst_bucket)
+            .key(dst_key)
+            .upload_id(upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|e| eyre!("Failed to complete multipart copy: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Asks S3 to restore a temporary copy of an archived object
+    pub async fn restore_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        request: &RestoreRequest,
+    ) -> Result<()> {
+        let _permit = self.limits.requests.acquire().await;
+        self.client_for(bucket)
+            .await
+            .restore_object()
+            .bucket(bucket)
+            .key(key)
+            .restore_request(
+                aws_sdk_s3::types::RestoreRequest::builder()
+                    .days(request.days)
+                    .glacier_job_parameters(
+                        GlacierJobParameters::builder()
+                            .tier(Tier::from(request.tier.as_str()))
+                            .build()?,
+                    )
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|e| match e.code() {
+                Some("RestoreAlreadyInProgress") => {
+                    eyre!("A restore of {} is already in progress", key)
+                }
+                Some("InvalidObjectState") => eyre!("{} is not archived", key),
+                _ => eyre!(
+                    "Failed to restore {}/{}: {}",
                     bucket,
                     key,
-                    sdk_err
+                    e.into_service_error()
+                ),
+            })?;
+        Ok(())
+    }
+
+    /// Fetches the object's metadata without its body, including stored checksums
+    pub async fn head_object(&self, bucket: &str, key: &str) -> Result<HeadObjectOutput> {
+        self.client_for(bucket)
+            .await
+            .head_object()
+            .bucket(bucket)
+            .key(key)
+            .checksum_mode(ChecksumMode::Enabled)
+            .send()
+            .await
+            .map_err(|e| eyre!("Failed to read {}/{}: {}", bucket, key, e))
+    }
+
+    /// Whether `key` exists in `bucket`
+    pub async fn object_exists(&self, bucket: &str, key: &str) -> Result<bool> {
+        match self
+            .client_for(bucket)
+            .await
+            .head_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(false),
+            Err(e) => Err(eyre!("Failed to read {}/{}: {}", bucket, key, e)),
+        }
+    }
+
+    /// Compares a local file with an object by size and every checksum S3 stores for it
+    pub async fn verify_file(&self, bucket: &str, key: &str, path: &Path) -> Result<Verification> {
+        let head = self.head_object(bucket, key).await?;
+        let local_size = tokio::fs::metadata(path)
+            .await
+            .map_err(|e| eyre!("Failed to read {}: {}", path.display(), e))?
+            .len();
+        let remote_size = head.content_length().unwrap_or(0).max(0) as u64;
+        if local_size != remote_size {
+            return Ok(Verification::Mismatch(format!(
+                "{} bytes in S3 but {} here",
+                remote_size, local_size
+            )));
+        }
+
+        let expected = stored_checksums(
+            [
+                head.checksum_crc32(),
+                head.checksum_crc32_c(),
+                head.checksum_sha256(),
+            ],
+            head.e_tag(),
+            head.server_side_encryption(),
+            head.sse_customer_algorithm().is_some(),
+        );
+        if expected.is_empty() {
+            return Ok(Verification::Unverified);
+        }
+        let algorithms = expected.algorithms();
+        let path = path.to_path_buf();
+        // Hashing a big file takes a while, so keep it off the async workers
+        let actual = tokio::task::spawn_blocking(move || {
+            checksum::file_checksums(&path, &algorithms, 0, None)
+        })
+        .await??;
+        Ok(checksum::verify(&expected, &actual))
+    }
+
+    /// Returns the object's tags as (key, value) pairs
+    pub async fn get_object_tagging(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> Result<Vec<(String, String)>> {
+        let resp = self
+            .client_for(bucket)
+            .await
+            .get_object_tagging()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| eyre!("Failed to get tags for {}/{}: {}", bucket, key, e))?;
+
+        Ok(resp
+            .tag_set()
+            .iter()
+            .map(|tag| (tag.key().to_string(), tag.value().to_string()))
+            .collect())
+    }
+
+    /// Replaces the object's whole tag set
+    pub async fn put_object_tagging(
+        &self,
+        bucket: &str,
+        key: &str,
+        tags: &[(String, String)],
+    ) -> Result<()> {
+        let tag_set = tags
+            .iter()
+            .map(|(k, v)| Tag::builder().key(k).value(v).build())
+            .collect::<Result<Vec<_>, _>>()?;
+        let tagging = Tagging::builder().set_tag_set(Some(tag_set)).build()?;
+
+        self.client_for(bucket)
+            .await
+            .put_object_tagging()
+            .bucket(bucket)
+            .key(key)
+            .tagging(tagging)
+            .send()
+            .await
+            .map_err(|e| eyre!("Failed to save tags for {}/{}: {}", bucket, key, e))?;
+        Ok(())
+    }
+
+    /// A URL that lets anyone download the object until it expires
+    pub async fn presign_get(
+        &self,
+        bucket: &str,
+        key: &str,
+        expires_in: Duration,
+    ) -> Result<String> {
+        let request = self
+            .client_for(bucket)
+            .await
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .presigned(PresigningConfig::expires_in(expires_in)?)
+            .await
+            .map_err(|e| eyre!("Failed to presign {}/{}: {}", bucket, key, e))?;
+        Ok(request.uri().to_string())
+    }
+
+    /// A URL that lets anyone upload to `key` with a plain PUT until it expires
+    pub async fn presign_put(
+        &self,
+        bucket: &str,
+        key: &str,
+        expires_in: Duration,
+    ) -> Result<String> {
+        let request = self
+            .client_for(bucket)
+            .await
+            .put_object()
+            .bucket(bucket)
+            .key(key)
+            .presigned(PresigningConfig::expires_in(expires_in)?)
+            .await
+            .map_err(|e| eyre!("Failed to presign an upload to {}/{}: {}", bucket, key, e))?;
+        Ok(request.uri().to_string())
+    }
+
+    /// The object's unsigned `https://` URL in its bucket's region
+    pub async fn object_url(&self, bucket: &str, key: &str) -> String {
+        let client = self.client_for(bucket).await;
+        let region = client
+            .config()
+            .region()
+            .map_or("us-east-1".to_string(), |r| r.to_string());
+        let key = percent_encode(key, true);
+        // Dotted bucket names do not match the wildcard certificate, so use path style for them
+        if bucket.contains('.') {
+            format!("https://s3.{}.amazonaws.com/{}/{}", region, bucket, key)
+        } else {
+            format!("https://{}.s3.{}.amazonaws.com/{}", bucket, region, key)
+        }
+    }
+
+    /// The region the client was configured with
+    pub fn region(&self) -> Option<String> {
+        self.config.region().map(|r| r.to_string())
+    }
+
+    /// Creates a bucket, then applies the settings S3 does not take at creation
+    pub async fn create_bucket(&self, settings: &BucketSettings) -> Result<()> {
+        let name = settings.name.as_str();
+        let config = aws_sdk_s3::config::Builder::from(&self.config)
+            .region(Region::new(settings.region.clone()))
+            .build();
+        let client = Client::from_conf(config);
+
+        let mut request = client
+            .create_bucket()
+            .bucket(name)
+            .object_ownership(ObjectOwnership::from(settings.ownership.as_str()));
+        // us-east-1 is the default and rejects being named as a location constraint
+        if settings.region != "us-east-1" {
+            request = request.create_bucket_configuration(
+                CreateBucketConfiguration::builder()
+                    .location_constraint(BucketLocationConstraint::from(settings.region.as_str()))
+                    .build(),
+            );
+        }
+        request
+            .send()
+            .await
+            .map_err(|e| eyre!("Failed to create {}: {}", name, e.into_service_error()))?;
+        self.bucket_clients
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), client.clone());
+
+        let block = settings.block_public_access;
+        client
+            .put_public_access_block()
+            .bucket(name)
+            .public_access_block_configuration(
+                PublicAccessBlockConfiguration::builder()
+                    .block_public_acls(block)
+                    .ignore_public_acls(block)
+                    .block_public_policy(block)
+                    .restrict_public_buckets(block)
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|e| eyre!("Created {}, but could not set public access: {}", name, e))?;
+
+        if settings.versioning {
+            client
+                .put_bucket_versioning()
+                .bucket(name)
+                .versioning_configuration(
+                    VersioningConfiguration::builder()
+                        .status(BucketVersioningStatus::Enabled)
+                        .build(),
+                )
+                .send()
+                .await
+                .map_err(|e| eyre!("Created {}, but could not enable versioning: {}", name, e))?;
+        }
+
+        // New buckets already encrypt with SSE-S3
+        if let Encryption::Kms(key) = &settings.encryption {
+            let default = ServerSideEncryptionByDefault::builder()
+                .sse_algorithm(ServerSideEncryption::AwsKms)
+                .set_kms_master_key_id(key.clone())
+                .build()?;
+            let rule = ServerSideEncryptionRule::builder()
+                .apply_server_side_encryption_by_default(default)
+                .bucket_key_enabled(true)
+                .build();
+            client
+                .put_bucket_encryption()
+                .bucket(name)
+                .server_side_encryption_configuration(
+                    ServerSideEncryptionConfiguration::builder()
+                        .rules(rule)
+                        .build()?,
+                )
+                .send()
+                .await
+                .map_err(|e| eyre!("Created {}, but could not set encryption: {}", name, e))?;
+        }
+        Ok(())
+    }
+
+    /// Whether the bucket holds no objects, versions or delete markers
+    pub async fn bucket_is_empty(&self, bucket: &str) -> Result<bool> {
+        let response = self
+            .client_for(bucket)
+            .await
+            .list_object_versions()
+            .bucket(bucket)
+            .max_keys(1)
+            .send()
+            .await
+            .map_err(|e| eyre!("Failed to list {}: {}", bucket, e.into_service_error()))?;
+        Ok(response.versions().is_empty() && response.delete_markers().is_empty())
+    }
+
+    /// A page of up to 1000 object versions and delete markers, starting at `marker`, and where
+    /// the next page starts if there is one
+    pub async fn list_versions_page(
+        &self,
+        bucket: &str,
+        marker: Option<VersionMarker>,
+    ) -> Result<(Vec<ObjectIdentifier>, Option<VersionMarker>)> {
+        let marker = marker.unwrap_or_default();
+        let response = self
+            .client_for(bucket)
+            .await
+            .list_object_versions()
+            .bucket(bucket)
+            .set_key_marker(marker.key)
+            .set_version_id_marker(marker.version_id)
+            .send()
+            .await
+            .map_err(|e| eyre!("Failed to list {}: {}", bucket, e.into_service_error()))?;
+
+        let versions = response
+            .versions()
+            .iter()
+            .map(|v| (v.key(), v.version_id()));
+        let markers = response
+            .delete_markers()
+            .iter()
+            .map(|m| (m.key(), m.version_id()));
+        let page = versions
+            .chain(markers)
+            .filter_map(|(key, version_id)| {
+                ObjectIdentifier::builder()
+                    .key(key?)
+                    .set_version_id(version_id.map(String::from))
+                    .build()
+                    .ok()
+            })
+            .collect();
+
+        let next = response
+            .is_truncated()
+            .unwrap_or(false)
+            .then(|| VersionMarker {
+                key: response.next_key_marker().map(String::from),
+                version_id: response.next_version_id_marker().map(String::from),
+            });
+        Ok((page, next))
+    }
+
+    /// Deletes up to 1000 object versions in one request
+    pub async fn delete_versions(&self, bucket: &str, objects: &[ObjectIdentifier]) -> Result<()> {
+        let delete = Delete::builder()
+            .set_objects(Some(objects.to_vec()))
+            .quiet(true)
+            .build()?;
+        let response = self
+            .client_for(bucket)
+            .await
+            .delete_objects()
+            .bucket(bucket)
+            .delete(delete)
+            .send()
+            .await
+            .map_err(|e| {
+                eyre!(
+                    "Failed to delete from {}: {}",
+                    bucket,
+                    e.into_service_error()
+                )
+            })?;
+
+        match response.errors() {
+            [] => Ok(()),
+            [first, ..] => Err(eyre!(
+                "{} of {} not deleted, e.g. {}: {}",
+                response.errors().len(),
+                objects.len(),
+                first.key().unwrap_or_default(),
+                first.message().or(first.code()).unwrap_or("unknown error")
+            )),
+        }
+    }
+
+    /// Deletes an empty bucket
+    pub async fn delete_bucket(&self, bucket: &str) -> Result<()> {
+        self.client_for(bucket)
+            .await
+            .delete_bucket()
+            .bucket(bucket)
+            .send()
+            .await
+            .map_err(|e| eyre!("Failed to delete {}: {}", bucket, e.into_service_error()))?;
+        self.bucket_clients.lock().unwrap().remove(bucket);
+        Ok(())
+    }
+
+    /// The bucket policy JSON, or `None` if the bucket has none
+    pub async fn get_bucket_policy(&self, bucket: &str) -> Result<Option<String>> {
+        match self
+            .client_for(bucket)
+            .await
+            .get_bucket_policy()
+            .bucket(bucket)
+            .send()
+            .await
+        {
+            Ok(output) => Ok(output.policy),
+            Err(e) if e.code() == Some("NoSuchBucketPolicy") => Ok(None),
+            Err(e) => Err(eyre!(
+                "Failed to get the policy of {}: {}",
+                bucket,
+                e.into_service_error()
+            )),
+        }
+    }
+
+    pub async fn put_bucket_policy(&self, bucket: &str, policy: &str) -> Result<()> {
+        self.client_for(bucket)
+            .await
+            .put_bucket_policy()
+            .bucket(bucket)
+            .policy(policy)
+            .send()
+            .await
+            .map_err(|e| {
+                eyre!(
+                    "Failed to save the policy of {}: {}",
+                    bucket,
+                    e.into_service_error()
                 )
             })?;
+        Ok(())
+    }
+
+    /// The bucket's lifecycle rules; none if it has no configuration
+    pub async fn get_bucket_lifecycle(&self, bucket: &str) -> Result<LifecycleConfig> {
+        match self
+            .client_for(bucket)
+            .await
+            .get_bucket_lifecycle_configuration()
+            .bucket(bucket)
+            .send()
+            .await
+        {
+            Ok(output) => Ok(LifecycleConfig {
+                rules: output.rules().iter().map(rule_from_sdk).collect(),
+            }),
+            Err(e) if e.code() == Some("NoSuchLifecycleConfiguration") => {
+                Ok(LifecycleConfig::default())
+            }
+            Err(e) => Err(eyre!(
+                "Failed to get the lifecycle rules of {}: {}",
+                bucket,
+                e.into_service_error()
+            )),
+        }
+    }
+
+    /// Replaces the bucket's lifecycle rules, removing the configuration when there are none
+    pub async fn put_bucket_lifecycle(&self, bucket: &str, config: &LifecycleConfig) -> Result<()> {
+        let client = self.client_for(bucket).await;
+        if config.rules.is_empty() {
+            client
+                .delete_bucket_lifecycle()
+                .bucket(bucket)
+                .send()
+                .await
+                .map_err(|e| {
+                    eyre!(
+                        "Failed to remove the lifecycle rules of {}: {}",
+                        bucket,
+                        e.into_service_error()
+                    )
+                })?;
+            return Ok(());
+        }
+
+        let rules = config
+            .rules
+            .iter()
+            .map(rule_to_sdk)
+            .collect::<Result<Vec<_>>>()?;
+        client
+            .put_bucket_lifecycle_configuration()
+            .bucket(bucket)
+            .lifecycle_configuration(
+                BucketLifecycleConfiguration::builder()
+                    .set_rules(Some(rules))
+                    .build()?,
+            )
+            .send()
+            .await
+            .map_err(|e| {
+                eyre!(
+                    "Failed to save the lifecycle rules of {}: {}",
+                    bucket,
+                    e.into_service_error()
+                )
+            })?;
+        Ok(())
+    }
+
+    pub async fn delete_object(&self, bucket: &str, key: &str) -> Result<()> {
+        self.client_for(bucket)
+            .await
+            .delete_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| eyre!("Failed to delete {}/{}: {}", bucket, key, e))?;
+        Ok(())
+    }
+}
+
+fn tag_from_sdk(tag: &Tag) -> RuleTag {
+    RuleTag {
+        key: tag.key().to_string(),
+        value: tag.value().to_string(),
+    }
+}
+
+fn tag_to_sdk(tag: &RuleTag) -> Result<Tag> {
+    Ok(Tag::builder().key(&tag.key).value(&tag.value).build()?)
+}
+
+fn lifecycle_date(date: &Option<String>) -> Result<Option<DateTime>> {
+    date.as_deref()
+        .map(parse_lifecycle_date)
+        .transpose()
+        .map_err(|e| eyre!(e))
+}
+
+pub fn rule_from_sdk(rule: &LifecycleRule) -> lifecycle::LifecycleRule {
+    // Rules written before filters existed keep their prefix on the rule itself; it is
+    // written back as a filter, which S3 treats the same way
+    #[allow(deprecated)]
+    let legacy_prefix = rule.prefix().map(|prefix| RuleFilter {
+        prefix: Some(prefix.to_string()),
+        ..RuleFilter::default()
+    });
+    let filter = rule.filter().map(|filter| {
+        let and = filter.and().map(|and| AndFilter {
+            prefix: and.prefix().map(String::from),
+            tags: and.tags().iter().map(tag_from_sdk).collect(),
+            object_size_greater_than: and.object_size_greater_than(),
+            object_size_less_than: and.object_size_less_than(),
+        });
+        RuleFilter {
+            prefix: filter.prefix().map(String::from),
+            tag: filter.tag().map(tag_from_sdk),
+            object_size_greater_than: filter.object_size_greater_than(),
+            object_size_less_than: filter.object_size_less_than(),
+            and,
+        }
+    });
+    let filter = match (filter, legacy_prefix) {
+        (Some(filter), Some(legacy)) if filter == RuleFilter::default() => legacy,
+        (Some(filter), _) => filter,
+        (None, legacy) => legacy.unwrap_or_default(),
+    };
+
+    lifecycle::LifecycleRule {
+        id: rule.id().map(String::from),
+        status: rule.status().as_str().to_string(),
+        filter,
+        transitions: rule
+            .transitions()
+            .iter()
+            .map(|t| lifecycle::Transition {
+                days: t.days(),
+                date: t.date().map(format_lifecycle_date),
+                storage_class: t
+                    .storage_class()
+                    .map_or(String::new(), |c| c.as_str().to_string()),
+            })
+            .collect(),
+        expiration: rule.expiration().map(|e| Expiration {
+            days: e.days(),
+            date: e.date().map(format_lifecycle_date),
+            expired_object_delete_marker: e.expired_object_delete_marker(),
+        }),
+        noncurrent_version_transitions: rule
+            .noncurrent_version_transitions()
+            .iter()
+            .map(|t| NoncurrentTransition {
+                noncurrent_days: t.noncurrent_days().unwrap_or_default(),
+                newer_noncurrent_versions: t.newer_noncurrent_versions(),
+                storage_class: t
+                    .storage_class()
+                    .map_or(String::new(), |c| c.as_str().to_string()),
+            })
+            .collect(),
+        noncurrent_version_expiration: rule.noncurrent_version_expiration().map(|e| {
+            NoncurrentExpiration {
+                noncurrent_days: e.noncurrent_days().unwrap_or_default(),
+                newer_noncurrent_versions: e.newer_noncurrent_versions(),
+            }
+        }),
+        abort_incomplete_multipart_upload: rule.abort_incomplete_multipart_upload().map(|a| {
+            AbortIncompleteUpload {
+                days_after_initiation: a.days_after_initiation().unwrap_or_default(),
+            }
+        }),
+    }
+}
+
+pub fn rule_to_sdk(rule: &lifecycle::LifecycleRule) -> Result<LifecycleRule> {
+    let filter = &rule.filter;
+    let and = match &filter.and {
+        Some(and) => Some(
+            LifecycleRuleAndOperator::builder()
+                .set_prefix(and.prefix.clone())
+                .set_tags(Some(
+                    and.tags
+                        .iter()
+                        .map(tag_to_sdk)
+                        .collect::<Result<Vec<_>>>()?,
+                ))
+                .set_object_size_greater_than(and.object_size_greater_than)
+                .set_object_size_less_than(and.object_size_less_than)
+                .build(),
+        ),
+        None => None,
+    };
+    let empty = filter == &RuleFilter::default();
+    let sdk_filter = LifecycleRuleFilter::builder()
+        // An empty prefix applies the rule to every object
+        .set_prefix(if empty {
+            Some(String::new())
+        } else {
+            filter.prefix.clone()
+        })
+        .set_tag(filter.tag.as_ref().map(tag_to_sdk).transpose()?)
+        .set_object_size_greater_than(filter.object_size_greater_than)
+        .set_object_size_less_than(filter.object_size_less_than)
+        .set_and(and)
+        .build();
 
-        let byte_stream = response.body;
-        let collected_data = byte_stream.collect().await.map_err(|sdk_err| {
+    let mut builder = LifecycleRule::builder()
+        .set_id(rule.id.clone())
+        .status(ExpirationStatus::from(rule.status.as_str()))
+        .filter(sdk_filter);
+    for transition in &rule.transitions {
+        builder = builder.transitions(
+            Transition::builder()
+                .set_days(transition.days)
+                .set_date(lifecycle_date(&transition.date)?)
+                .storage_class(TransitionStorageClass::from(
+                    transition.storage_class.as_str(),
+                ))
+                .build(),
+        );
+    }
+    if let Some(expiration) = &rule.expiration {
+        builder = builder.expiration(
+            LifecycleExpiration::builder()
+                .set_days(expiration.days)
+                .set_date(lifecycle_date(&expiration.date)?)
+                .set_expired_object_delete_marker(expiration.expired_object_delete_marker)
+                .build(),
+        );
+    }
+    for transition in &rule.noncurrent_version_transitions {
+        builder = builder.noncurrent_version_transitions(
+            NoncurrentVersionTransition::builder()
+                .noncurrent_days(transition.noncurrent_days)
+                .set_newer_noncurrent_versions(transition.newer_noncurrent_versions)
+                .storage_class(TransitionStorageClass::from(
+                    transition.storage_class.as_str(),
+                ))
+                .build(),
+        );
+    }
+    if let Some(expiration) = &rule.noncurrent_version_expiration {
+        builder = builder.noncurrent_version_expiration(
+            NoncurrentVersionExpiration::builder()
+                .noncurrent_days(expiration.noncurrent_days)
+                .set_newer_noncurrent_versions(expiration.newer_noncurrent_versions)
+                .build(),
+        );
+    }
+    if let Some(abort) = &rule.abort_incomplete_multipart_upload {
+        builder = builder.abort_incomplete_multipart_upload(
+            AbortIncompleteMultipartUpload::builder()
+                .days_after_initiation(abort.days_after_initiation)
+                .build(),
+        );
+    }
+    Ok(builder.build()?)
+}
+
+/// Parts of a multipart copy, large enough that the object fits in S3's 10,000 parts
+pub fn copy_part_size(size: i64) -> i64 {
+    COPY_PART_SIZE.max((size as u64).div_ceil(10_000) as i64)
+}
+
+/// An object's tags in the URL-encoded form taken by uploads, or `None` if it has none.
+/// Fails if they cannot be read, since writing without them would drop them.
+async fn tagging_header(client: &Client, bucket: &str, key: &str) -> Result<Option<String>> {
+    let tags = client
+        .get_object_tagging()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .map_err(|e| eyre!("Could not read the tags of {}/{}: {}", bucket, key, e))?
+        .tag_set;
+    let tagging = tags
+        .iter()
+        .map(|tag| {
+            format!(
+                "{}={}",
+                percent_encode(tag.key(), false),
+                percent_encode(tag.value(), false)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    Ok((!tagging.is_empty()).then_some(tagging))
+}
+
+/// An object's ACL if it grants more than full control to its owner, so a rewrite that
+/// resets it to private can put it back
+async fn saved_acl(client: &Client, bucket: &str, key: &str) -> Option<GetObjectAclOutput> {
+    client
+        .get_object_acl()
+        .bucket(bucket)
+        .key(key)
+        .send()
+        .await
+        .ok()
+        .filter(|acl| {
+            let owner = acl.owner().and_then(|o| o.id());
+            !acl.grants().iter().all(|grant| {
+                grant.permission() == Some(&Permission::FullControl)
+                    && grant.grantee().and_then(|g| g.id()) == owner
+            })
+        })
+}
+
+/// Puts back an ACL taken with `saved_acl` after the object was rewritten
+async fn restore_acl(
+    client: &Client,
+    bucket: &str,
+    key: &str,
+    acl: Option<GetObjectAclOutput>,
+) -> Result<()> {
+    let Some(acl) = acl else {
+        return Ok(());
+    };
+    client
+        .put_object_acl()
+        .bucket(bucket)
+        .key(key)
+        .access_control_policy(
+            AccessControlPolicy::builder()
+                .set_grants(Some(acl.grants().to_vec()))
+                .set_owner(acl.owner().cloned())
+                .build(),
+        )
+        .send()
+        .await
+        .map_err(|e| {
             eyre!(
-                "S3 SDK Error collecting object body for download (bucket: {}, key: {}): {}",
+                "Updated {}/{} but could not restore its ACL: {}",
                 bucket,
                 key,
-                sdk_err
+                e
             )
         })?;
+    Ok(())
+}
+
+/// Server-side encryption settings to carry over when rewriting an object in its own bucket
+fn kept_encryption(head: &HeadObjectOutput) -> (Option<ServerSideEncryption>, Option<String>) {
+    let sse = head.server_side_encryption().cloned();
+    let kms_key = match sse {
+        Some(ServerSideEncryption::AwsKms) | Some(ServerSideEncryption::AwsKmsDsse) => {
+            head.ssekms_key_id().map(String::from)
+        }
+        _ => None,
+    };
+    (sse, kms_key)
+}
+
+/// The checksums S3 stores for an object: the additional checksums it was uploaded with,
+/// and the ETag when that is the MD5 of the content
+fn stored_checksums(
+    [crc32, crc32c, sha256]: [Option<&str>; 3],
+    etag: Option<&str>,
+    sse: Option<&ServerSideEncryption>,
+    customer_key: bool,
+) -> Checksums {
+    let encrypted_with_key = customer_key
+        || matches!(
+            sse,
+            Some(ServerSideEncryption::AwsKms) | Some(ServerSideEncryption::AwsKmsDsse)
+        );
+    Checksums::default()
+        .with(Algorithm::Crc32, crc32)
+        .with(Algorithm::Crc32c, crc32c)
+        .with(Algorithm::Sha256, sha256)
+        .with(
+            Algorithm::Md5,
+            checksum::etag_md5(etag, encrypted_with_key).as_deref(),
+        )
+}
+
+fn get_checksums(response: &GetObjectOutput) -> Checksums {
+    stored_checksums(
+        [
+            response.checksum_crc32(),
+            response.checksum_crc32_c(),
+            response.checksum_sha256(),
+        ],
+        response.e_tag(),
+        response.server_side_encryption(),
+        response.sse_customer_algorithm().is_some(),
+    )
+}
+
+/// Fails when the downloaded content does not match a checksum S3 stores for it
+fn check_download(bucket: &str, key: &str, expected: &Checksums, hasher: Hasher) -> Result<()> {
+    match checksum::verify(expected, &hasher.finish()) {
+        Verification::Mismatch(reason) => Err(eyre!(
+            "Checksum mismatch downloading {}/{}: {}",
+            bucket,
+            key,
+            reason
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// The CRC32C of part of a file, sent with uploads so S3 rejects corrupted bodies
+async fn file_crc32c(path: &Path, offset: u64, length: u64) -> Result<String> {
+    let path = path.to_path_buf();
+    let checksums = tokio::task::spawn_blocking(move || {
+        checksum::file_checksums(&path, &[Algorithm::Crc32c], offset, Some(length))
+            .map_err(|e| eyre!("Failed to read {}: {}", path.display(), e))
+    })
+    .await??;
+    checksums
+        .get(Algorithm::Crc32c)
+        .map(String::from)
+        .ok_or_else(|| eyre!("CRC32C was not computed"))
+}
+
+/// Part of a file sent as a request body, one chunk per credit from `send_paced`. The size
+/// of every chunk sent is reported back, and the credit for the next one follows once it is
+/// paid for.
+struct PacedFileBody {
+    file: std::io::Result<tokio::fs::File>,
+    remaining: u64,
+    credits: Arc<Semaphore>,
+    acquiring: Option<Mutex<Pin<Box<dyn Future<Output = CreditResult> + Send>>>>,
+    credit: Option<OwnedSemaphorePermit>,
+    sent: mpsc::UnboundedSender<u64>,
+}
+
+type CreditResult = std::result::Result<OwnedSemaphorePermit, AcquireError>;
+
+impl PacedFileBody {
+    fn open(
+        path: &Path,
+        offset: u64,
+        length: u64,
+        credits: Arc<Semaphore>,
+        sent: mpsc::UnboundedSender<u64>,
+    ) -> Self {
+        let file = std::fs::File::open(path).and_then(|mut file| {
+            file.seek(SeekFrom::Start(offset))?;
+            Ok(tokio::fs::File::from_std(file))
+        });
+        Self {
+            file,
+            remaining: length,
+            credits,
+            acquiring: None,
+            credit: None,
+            sent,
+        }
+    }
+}
+
+impl http_body::Body for PacedFileBody {
+    type Data = Bytes;
+    type Error = std::io::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<std::io::Result<Frame<Bytes>>>> {
+        let this = self.get_mut();
+        if this.remaining == 0 {
+            return Poll::Ready(None);
+        }
+        if this.credit.is_none() {
+            let credits = this.credits.clone();
+            let acquiring = this
+                .acquiring
+                .get_or_insert_with(|| Mutex::new(Box::pin(credits.acquire_owned())));
+            let credit = ready!(acquiring.get_mut().unwrap().as_mut().poll(cx))
+                .map_err(|_| std::io::Error::other("transfer stopped"))?;
+            this.acquiring = None;
+            this.credit = Some(credit);
+        }
+
+        let file = match &mut this.file {
+            Ok(file) => file,
+            Err(e) => return Poll::Ready(Some(Err(std::io::Error::new(e.kind(), e.to_string())))),
+        };
+        let mut chunk = vec![0; BODY_CHUNK_SIZE.min(this.remaining as usize)];
+        let mut buf = ReadBuf::new(&mut chunk);
+        ready!(Pin::new(file).poll_read(cx, &mut buf))?;
+        let read = buf.filled().len();
+        if read == 0 {
+            return Poll::Ready(Some(Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "the file got shorter while it was uploaded",
+            ))));
+        }
+        chunk.truncate(read);
+        this.remaining -= read as u64;
+        // Spent: the next credit only comes once this chunk is paid for
+        if let Some(credit) = this.credit.take() {
+            credit.forget();
+        }
+        let _ = this.sent.send(read as u64);
+        Poll::Ready(Some(Ok(Frame::data(Bytes::from(chunk)))))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.remaining == 0
+    }
 
-        Ok(collected_data.into_bytes().to_vec())
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.remaining)
     }
 }
+
+/// Reading an archived object that was not restored fails with `InvalidObjectState`
+fn archived_error(key: &str, err: &SdkError<GetObjectError>) -> Option<Report> {
+    (err.code() == Some("InvalidObjectState")).then(|| {
+        eyre!(
+            "{} is archived in GLACIER or DEEP_ARCHIVE; restore it before reading it (A: Restore)",
+            key
+        )
+    })
+}
+
+/// Short reason for a failed listing, preferring the S3 error code (e.g. `AccessDenied`)
+/// Whether a listing failed because the caller may not read the bucket, rather than
+/// because of throttling or the network
+pub fn is_access_error(err: &Report) -> bool {
+    let code = err
+        .downcast_ref::<SdkError<ListObjectsV2Error>>()
+        .and_then(|sdk_err| sdk_err.code());
+    matches!(
+        code,
+        Some(
+            "AccessDenied"
+                | "AllAccessDisabled"
+                | "AccountProblem"
+                | "AuthorizationHeaderMalformed"
+                | "NoSuchBucket"
+                | "PermanentRedirect"
+        )
+    )
+}
+
+pub fn list_error_reason(err: &Report) -> String {
+    err.downcast_ref::<SdkError<ListObjectsV2Error>>()
+        .and_then(|sdk_err| sdk_err.code())
+        .map(String::from)
+        .unwrap_or_else(|| err.to_string())
+}
@@ -0,0 +1,221 @@
+use crate::aws::s3_client::{S3Client, TransferProgress, copy_part_size, is_access_error};
+use aws_config::BehaviorVersion;
+use aws_sdk_s3::config::http::{HttpRequest, HttpResponse};
+use aws_sdk_s3::config::{Credentials, Region};
+use aws_sdk_s3::primitives::{ByteStream, SdkBody};
+use aws_smithy_runtime_api::client::http::{
+    HttpConnector, HttpConnectorFuture, SharedHttpConnector, http_client_fn,
+};
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use color_eyre::eyre::eyre;
+use std::sync::{Arc, Mutex};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fake S3 endpoint that records each request as `METHOD uri if-match`
+    #[derive(Debug, Clone, Default)]
+    struct FakeS3 {
+        requests: Arc<Mutex<Vec<String>>>,
+        /// Sizes of the bodies of accepted PUTs
+        uploads: Arc<Mutex<Vec<usize>>>,
+        tags_readable: bool,
+        accept_puts: bool,
+    }
+
+    /// Records the chunks reported, and stops the transfer after `stop_after` of them
+    #[derive(Default)]
+    struct Chunks {
+        seen: Mutex<Vec<u64>>,
+        stop_after: Option<usize>,
+    }
+
+    impl TransferProgress for Chunks {
+        async fn advance(&self, bytes: u64) -> color_eyre::Result<()> {
+            let mut seen = self.seen.lock().unwrap();
+            seen.push(bytes);
+            match self.stop_after {
+                Some(limit) if seen.len() >= limit => Err(eyre!("cancelled")),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    impl FakeS3 {
+        fn respond(
+            &self,
+            request: &HttpRequest,
+        ) -> (u16, Vec<(&'static str, &'static str)>, &'static str) {
+            let uri = request.uri();
+            match request.method() {
+                "HEAD" => (200, vec![("ETag", "\"v1\""), ("Content-Length", "5")], ""),
+                "GET" if uri.contains("?tagging") && self.tags_readable => {
+                    (200, Vec::new(), "<Tagging><TagSet></TagSet></Tagging>")
+                }
+                "PUT" => (
+                    412,
+                    Vec::new(),
+                    "<Error><Code>PreconditionFailed</Code><Message>At least one of the pre-conditions you specified did not hold</Message></Error>",
+                ),
+                _ => (
+                    403,
+                    Vec::new(),
+                    "<Error><Code>AccessDenied</Code><Message>Access Denied</Message></Error>",
+                ),
+            }
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    impl HttpConnector for FakeS3 {
+        fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+            self.requests.lock().unwrap().push(format!(
+                "{} {} {}",
+                request.method(),
+                request.uri(),
+                request.headers().get("if-match").unwrap_or("-")
+            ));
+            if request.method() == "PUT" && self.accept_puts {
+                let uploads = self.uploads.clone();
+                return HttpConnectorFuture::new(async move {
+                    let body = ByteStream::new(request.into_body())
+                        .collect()
+                        .await
+                        .map_err(|e| ConnectorError::io(e.into()))?;
+                    uploads.lock().unwrap().push(body.into_bytes().len());
+                    let mut response = HttpResponse::new(200.try_into().unwrap(), SdkBody::empty());
+                    response.headers_mut().insert("ETag", "\"v2\"");
+                    Ok(response)
+                });
+            }
+            let (status, headers, body) = self.respond(&request);
+            let mut response = HttpResponse::new(status.try_into().unwrap(), SdkBody::from(body));
+            for (name, value) in headers {
+                response.headers_mut().insert(name, value);
+            }
+            HttpConnectorFuture::ready(Ok(response))
+        }
+    }
+
+    async fn client(fake: &FakeS3) -> S3Client {
+        let fake = fake.clone();
+        let config = aws_config::defaults(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("AKID", "SECRET", None, None, "test"))
+            .http_client(http_client_fn(move |_, _| {
+                SharedHttpConnector::new(fake.clone())
+            }))
+            .load()
+            .await;
+        S3Client::new(&config)
+    }
+
+    #[tokio::test]
+    async fn test_replace_body_is_guarded_by_etag() {
+        let fake = FakeS3 {
+            tags_readable: true,
+            ..Default::default()
+        };
+        let result = client(&fake)
+            .await
+            .replace_object_body("bucket", "notes.txt", b"edited".to_vec(), "\"v1\"")
+            .await;
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("notes.txt was changed in S3 after it was downloaded"));
+        let put = fake.requests().into_iter().find(|r| r.starts_with("PUT"));
+        assert!(put.unwrap().ends_with(" \"v1\""));
+    }
+
+    #[tokio::test]
+    async fn test_replace_body_needs_the_tags() {
+        let fake = FakeS3::default();
+        let result = client(&fake)
+            .await
+            .replace_object_body("bucket", "notes.txt", b"edited".to_vec(), "\"v1\"")
+            .await;
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("Could not read the tags of bucket/notes.txt"));
+        assert!(!fake.requests().iter().any(|r| r.starts_with("PUT")));
+    }
+
+    #[tokio::test]
+    async fn test_denied_buckets_are_skipped_and_looked_up_once() {
+        let fake = FakeS3::default();
+        let s3_client = client(&fake).await;
+        for _ in 0..2 {
+            let error = s3_client
+                .list_objects("private", None, None, 1000)
+                .await
+                .unwrap_err();
+            assert!(is_access_error(&error));
+        }
+        assert!(!is_access_error(&eyre!("connection reset")));
+
+        let lookups = fake.requests();
+        let lookups = lookups.iter().filter(|r| r.contains("location"));
+        assert_eq!(lookups.count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_upload_is_paced_while_it_is_sent() {
+        let path = std::env::temp_dir().join(format!("paced_upload_{}", std::process::id()));
+        std::fs::write(&path, vec![7u8; 200 * 1024]).unwrap();
+        let fake = FakeS3 {
+            accept_puts: true,
+            ..Default::default()
+        };
+        let s3_client = client(&fake).await;
+
+        let chunks = Chunks::default();
+        s3_client
+            .upload_file("bucket", "data.bin", &path, &chunks)
+            .await
+            .unwrap();
+        assert_eq!(*fake.uploads.lock().unwrap(), vec![200 * 1024]);
+        let kib = 1024;
+        assert_eq!(
+            *chunks.seen.lock().unwrap(),
+            vec![64 * kib, 64 * kib, 64 * kib, 8 * kib]
+        );
+
+        // The bandwidth limit holds for the body itself
+        s3_client.limits().bandwidth.set_limit(400 * kib);
+        let started = std::time::Instant::now();
+        s3_client
+            .upload_file("bucket", "data.bin", &path, &())
+            .await
+            .unwrap();
+        assert!(started.elapsed() >= std::time::Duration::from_millis(400));
+        s3_client.limits().bandwidth.set_limit(0);
+
+        // Stopping the transfer fails the upload partway through the body
+        let stopping = Chunks {
+            stop_after: Some(2),
+            ..Default::default()
+        };
+        let result = s3_client
+            .upload_file("bucket", "data.bin", &path, &stopping)
+            .await;
+        assert!(result.is_err());
+        assert_eq!(stopping.seen.lock().unwrap().len(), 2);
+        assert_eq!(fake.uploads.lock().unwrap().len(), 2);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_copy_parts_fit_the_part_limit() {
+        const MIB: i64 = 1024 * 1024;
+        assert_eq!(copy_part_size(6 * 1024 * MIB), 512 * MIB);
+
+        let max_object = 5 * 1024 * 1024 * MIB;
+        let part_size = copy_part_size(max_object);
+        assert!(part_size * 10_000 >= max_object);
+        assert!(part_size <= 5 * 1024 * MIB);
+    }
+}
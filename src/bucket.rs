@@ -0,0 +1,173 @@
+use std::fmt;
+use std::net::Ipv4Addr;
+
+/// Regions a bucket can be created in from the picker
+pub const REGIONS: [&str; 32] = [
+    "us-east-1",
+    "us-east-2",
+    "us-west-1",
+    "us-west-2",
+    "af-south-1",
+    "ap-east-1",
+    "ap-south-1",
+    "ap-south-2",
+    "ap-southeast-1",
+    "ap-southeast-2",
+    "ap-southeast-3",
+    "ap-southeast-4",
+    "ap-southeast-5",
+    "ap-southeast-7",
+    "ap-northeast-1",
+    "ap-northeast-2",
+    "ap-northeast-3",
+    "ca-central-1",
+    "ca-west-1",
+    "eu-central-1",
+    "eu-central-2",
+    "eu-west-1",
+    "eu-west-2",
+    "eu-west-3",
+    "eu-south-1",
+    "eu-south-2",
+    "eu-north-1",
+    "il-central-1",
+    "me-south-1",
+    "me-central-1",
+    "mx-central-1",
+    "sa-east-1",
+];
+
+/// Prefixes and suffixes S3 keeps for itself
+const RESERVED_PREFIXES: [&str; 3] = ["xn--", "sthree-", "amzn-s3-demo-"];
+const RESERVED_SUFFIXES: [&str; 5] = ["-s3alias", "--ol-s3", ".mrap", "--x-s3", "--table-s3"];
+
+/// Checks a name against the S3 rules for general purpose buckets
+pub fn validate_bucket_name(name: &str) -> Result<(), String> {
+    if !(3..=63).contains(&name.len()) {
+        return Err("must be 3 to 63 characters long".to_string());
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !matches!(c, 'a'..='z' | '0'..='9' | '.' | '-'))
+    {
+        return Err(format!(
+            "'{}' is not allowed: use lowercase letters, digits, '.' and '-'",
+            c
+        ));
+    }
+    let alphanumeric = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric());
+    if !alphanumeric(name.chars().next()) || !alphanumeric(name.chars().last()) {
+        return Err("must start and end with a letter or digit".to_string());
+    }
+    if name.contains("..") {
+        return Err("must not contain two periods in a row".to_string());
+    }
+    if name.parse::<Ipv4Addr>().is_ok() {
+        return Err("must not look like an IP address".to_string());
+    }
+    if let Some(prefix) = RESERVED_PREFIXES.iter().find(|p| name.starts_with(*p)) {
+        return Err(format!("must not start with '{}'", prefix));
+    }
+    if let Some(suffix) = RESERVED_SUFFIXES.iter().find(|s| name.ends_with(*s)) {
+        return Err(format!("must not end with '{}'", suffix));
+    }
+    Ok(())
+}
+
+/// Who owns objects uploaded to a bucket, and whether ACLs apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ownership {
+    /// ACLs are disabled and the bucket owner owns every object
+    BucketOwnerEnforced,
+    BucketOwnerPreferred,
+    ObjectWriter,
+}
+
+impl Ownership {
+    /// The value the S3 API takes
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BucketOwnerEnforced => "BucketOwnerEnforced",
+            Self::BucketOwnerPreferred => "BucketOwnerPreferred",
+            Self::ObjectWriter => "ObjectWriter",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::BucketOwnerEnforced => Self::BucketOwnerPreferred,
+            Self::BucketOwnerPreferred => Self::ObjectWriter,
+            Self::ObjectWriter => Self::BucketOwnerEnforced,
+        }
+    }
+}
+
+impl fmt::Display for Ownership {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BucketOwnerEnforced => write!(f, "bucket owner enforced (ACLs disabled)"),
+            Self::BucketOwnerPreferred => write!(f, "bucket owner preferred"),
+            Self::ObjectWriter => write!(f, "object writer"),
+        }
+    }
+}
+
+/// Default encryption for new objects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Encryption {
+    /// SSE-S3, with keys managed by S3
+    S3,
+    /// SSE-KMS with the given key, or the AWS managed `aws/s3` key
+    Kms(Option<String>),
+}
+
+impl Encryption {
+    /// Parses `sse-s3`, `kms` or `kms:KEY_ID`
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        match input.to_ascii_lowercase().as_str() {
+            "s3" | "sse-s3" | "aes256" => Ok(Self::S3),
+            "kms" | "sse-kms" => Ok(Self::Kms(None)),
+            _ => match input.split_once(':') {
+                Some((kms, key)) if kms.eq_ignore_ascii_case("kms") && !key.is_empty() => {
+                    Ok(Self::Kms(Some(key.to_string())))
+                }
+                _ => Err("expected sse-s3, kms or kms:KEY_ID".to_string()),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Encryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::S3 => write!(f, "SSE-S3"),
+            Self::Kms(None) => write!(f, "SSE-KMS (aws/s3 key)"),
+            Self::Kms(Some(key)) => write!(f, "SSE-KMS ({})", key),
+        }
+    }
+}
+
+/// Everything set when creating a bucket; the defaults match the S3 console's
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BucketSettings {
+    pub name: String,
+    pub region: String,
+    pub ownership: Ownership,
+    pub versioning: bool,
+    pub encryption: Encryption,
+    pub block_public_access: bool,
+}
+
+impl BucketSettings {
+    pub fn new(region: &str) -> Self {
+        Self {
+            name: String::new(),
+            region: region.to_string(),
+            ownership: Ownership::BucketOwnerEnforced,
+            versioning: false,
+            encryption: Encryption::S3,
+            block_public_access: true,
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use crate::bucket::{BucketSettings, Encryption, Ownership, REGIONS, validate_bucket_name};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_bucket_name() {
+        for name in ["my-bucket", "logs.example.com", "abc", "123-data"] {
+            assert_eq!(validate_bucket_name(name), Ok(()), "{}", name);
+        }
+        for name in [
+            "ab",
+            &"a".repeat(64),
+            "My-Bucket",
+            "my_bucket",
+            "-bucket",
+            "bucket.",
+            "my..bucket",
+            "192.168.1.1",
+            "xn--bucket",
+            "sthree-bucket",
+            "bucket-s3alias",
+            "bucket--ol-s3",
+            "bucket.mrap",
+        ] {
+            assert!(validate_bucket_name(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_encryption_parse() {
+        assert_eq!(Encryption::parse("sse-s3"), Ok(Encryption::S3));
+        assert_eq!(Encryption::parse("KMS"), Ok(Encryption::Kms(None)));
+        assert_eq!(
+            Encryption::parse("kms:arn:aws:kms:eu-west-1:123456789012:key/abc"),
+            Ok(Encryption::Kms(Some(
+                "arn:aws:kms:eu-west-1:123456789012:key/abc".to_string()
+            )))
+        );
+        assert!(Encryption::parse("kms:").is_err());
+        assert!(Encryption::parse("des").is_err());
+    }
+
+    #[test]
+    fn test_bucket_settings_defaults() {
+        let settings = BucketSettings::new("eu-west-1");
+        assert_eq!(settings.ownership, Ownership::BucketOwnerEnforced);
+        assert!(settings.block_public_access);
+        assert!(!settings.versioning);
+        assert_eq!(settings.encryption, Encryption::S3);
+        assert_eq!(
+            Ownership::ObjectWriter.next(),
+            Ownership::BucketOwnerEnforced
+        );
+        assert!(REGIONS.contains(&settings.region.as_str()));
+    }
+}
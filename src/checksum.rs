@@ -0,0 +1,189 @@
+use crate::util::base64_encode;
+use md5::{Digest, Md5};
+use sha2::Sha256;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Crc32,
+    Crc32c,
+    Sha256,
+    Md5,
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Crc32 => write!(f, "CRC32"),
+            Self::Crc32c => write!(f, "CRC32C"),
+            Self::Sha256 => write!(f, "SHA256"),
+            Self::Md5 => write!(f, "MD5"),
+        }
+    }
+}
+
+/// Checksums of some content, encoded the way S3 reports them: base64 for the CRCs and
+/// SHA256, lowercase hex for the MD5 (the ETag)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Checksums {
+    values: Vec<(Algorithm, String)>,
+}
+
+impl Checksums {
+    /// Adds a checksum if there is one. Composite checksums of multipart uploads (`...-N`)
+    /// cover the parts rather than the content, so they are left out.
+    pub fn with(mut self, algorithm: Algorithm, value: Option<&str>) -> Self {
+        if let Some(value) = value.filter(|value| !value.contains('-')) {
+            self.values.push((algorithm, value.to_string()));
+        }
+        self
+    }
+
+    pub fn get(&self, algorithm: Algorithm) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(a, _)| *a == algorithm)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn algorithms(&self) -> Vec<Algorithm> {
+        self.values
+            .iter()
+            .map(|(algorithm, _)| *algorithm)
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// The MD5 of the content when the ETag is one: single-part uploads that are not encrypted
+/// with SSE-KMS or a customer-provided key
+pub fn etag_md5(etag: Option<&str>, encrypted_with_key: bool) -> Option<String> {
+    let etag = etag?.trim_matches('"');
+    (!encrypted_with_key && etag.len() == 32 && etag.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| etag.to_ascii_lowercase())
+}
+
+/// Computes checksums of a stream of bytes as it goes by
+pub struct Hasher {
+    crc32: Option<crc32fast::Hasher>,
+    crc32c: Option<u32>,
+    sha256: Option<Sha256>,
+    md5: Option<Md5>,
+}
+
+impl Hasher {
+    pub fn new(algorithms: &[Algorithm]) -> Self {
+        let wants = |algorithm| algorithms.contains(&algorithm);
+        Self {
+            crc32: wants(Algorithm::Crc32).then(crc32fast::Hasher::new),
+            crc32c: wants(Algorithm::Crc32c).then_some(0),
+            sha256: wants(Algorithm::Sha256).then(Sha256::new),
+            md5: wants(Algorithm::Md5).then(Md5::new),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        if let Some(crc32) = &mut self.crc32 {
+            crc32.update(data);
+        }
+        if let Some(crc32c) = &mut self.crc32c {
+            *crc32c = crc32c::crc32c_append(*crc32c, data);
+        }
+        if let Some(sha256) = &mut self.sha256 {
+            sha256.update(data);
+        }
+        if let Some(md5) = &mut self.md5 {
+            md5.update(data);
+        }
+    }
+
+    pub fn finish(self) -> Checksums {
+        let crc32 = self
+            .crc32
+            .map(|h| base64_encode(&h.finalize().to_be_bytes()));
+        let crc32c = self.crc32c.map(|crc| base64_encode(&crc.to_be_bytes()));
+        let sha256 = self.sha256.map(|h| base64_encode(&h.finalize()));
+        let md5 = self.md5.map(|h| format!("{:x}", h.finalize()));
+        Checksums::default()
+            .with(Algorithm::Crc32, crc32.as_deref())
+            .with(Algorithm::Crc32c, crc32c.as_deref())
+            .with(Algorithm::Sha256, sha256.as_deref())
+            .with(Algorithm::Md5, md5.as_deref())
+    }
+}
+
+/// Checksums of `length` bytes of a file from `offset`, or of the rest of it
+pub fn file_checksums(
+    path: &Path,
+    algorithms: &[Algorithm],
+    offset: u64,
+    length: Option<u64>,
+) -> io::Result<Checksums> {
+    let mut file = fs::File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut reader = file.take(length.unwrap_or(u64::MAX));
+    let mut hasher = Hasher::new(algorithms);
+    let mut buffer = vec![0; 1024 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finish())
+}
+
+/// How content compares with an object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    /// Every checksum S3 has for the object matched
+    Match(Vec<Algorithm>),
+    Mismatch(String),
+    /// S3 has no checksum of the whole object, e.g. for multipart uploads without one
+    Unverified,
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Match(algorithms) => {
+                let names: Vec<String> = algorithms.iter().map(|a| a.to_string()).collect();
+                write!(f, "match ({})", names.join(", "))
+            }
+            Self::Mismatch(reason) => write!(f, "MISMATCH: {}", reason),
+            Self::Unverified => write!(
+                f,
+                "sizes match, but S3 stores no checksum of the whole object to compare with"
+            ),
+        }
+    }
+}
+
+/// Compares the checksums computed for some content with the ones S3 stored
+pub fn verify(expected: &Checksums, actual: &Checksums) -> Verification {
+    let mut matched = Vec::new();
+    for (algorithm, value) in &expected.values {
+        let Some(computed) = actual.get(*algorithm) else {
+            continue;
+        };
+        if computed != value {
+            return Verification::Mismatch(format!(
+                "{} is {} in S3 but {} here",
+                algorithm, value, computed
+            ));
+        }
+        matched.push(*algorithm);
+    }
+    if matched.is_empty() {
+        Verification::Unverified
+    } else {
+        Verification::Match(matched)
+    }
+}
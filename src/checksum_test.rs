@@ -0,0 +1,99 @@
+use crate::checksum::{
+    Algorithm, Checksums, Hasher, Verification, etag_md5, file_checksums, verify,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Algorithm; 4] = [
+        Algorithm::Crc32,
+        Algorithm::Crc32c,
+        Algorithm::Sha256,
+        Algorithm::Md5,
+    ];
+
+    #[test]
+    fn test_hasher_matches_s3_encoding() {
+        let mut hasher = Hasher::new(&ALL);
+        hasher.update(b"hello ");
+        hasher.update(b"world");
+        let checksums = hasher.finish();
+
+        assert_eq!(checksums.get(Algorithm::Crc32), Some("DUoRhQ=="));
+        assert_eq!(checksums.get(Algorithm::Crc32c), Some("yZRlqg=="));
+        assert_eq!(
+            checksums.get(Algorithm::Sha256),
+            Some("uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=")
+        );
+        assert_eq!(
+            checksums.get(Algorithm::Md5),
+            Some("5eb63bbbe01eeed093cb22bb8f5acdc3")
+        );
+
+        let only_crc = Hasher::new(&[Algorithm::Crc32c]).finish();
+        assert_eq!(only_crc.algorithms(), vec![Algorithm::Crc32c]);
+    }
+
+    #[test]
+    fn test_etag_md5() {
+        let etag = "\"5EB63BBBE01EEED093CB22BB8F5ACDC3\"";
+        assert_eq!(
+            etag_md5(Some(etag), false).as_deref(),
+            Some("5eb63bbbe01eeed093cb22bb8f5acdc3")
+        );
+        assert_eq!(etag_md5(Some(etag), true), None);
+        assert_eq!(
+            etag_md5(Some("9b2cf535f27731c974343645a3985328-3"), false),
+            None
+        );
+        assert_eq!(etag_md5(None, false), None);
+    }
+
+    #[test]
+    fn test_verify() {
+        let expected = Checksums::default()
+            .with(Algorithm::Crc32c, Some("yZRlqg=="))
+            .with(Algorithm::Sha256, Some("composite==-3"))
+            .with(Algorithm::Md5, Some("5eb63bbbe01eeed093cb22bb8f5acdc3"));
+        // The composite value is dropped rather than compared
+        assert_eq!(
+            expected.algorithms(),
+            vec![Algorithm::Crc32c, Algorithm::Md5]
+        );
+
+        let mut hasher = Hasher::new(&expected.algorithms());
+        hasher.update(b"hello world");
+        assert_eq!(
+            verify(&expected, &hasher.finish()),
+            Verification::Match(vec![Algorithm::Crc32c, Algorithm::Md5])
+        );
+
+        let mut hasher = Hasher::new(&expected.algorithms());
+        hasher.update(b"hello there");
+        assert!(matches!(
+            verify(&expected, &hasher.finish()),
+            Verification::Mismatch(_)
+        ));
+
+        assert_eq!(
+            verify(&Checksums::default(), &Hasher::new(&ALL).finish()),
+            Verification::Unverified
+        );
+    }
+
+    #[test]
+    fn test_file_checksums_of_a_range() {
+        let path = std::env::temp_dir().join(format!("checksum_{}", std::process::id()));
+        std::fs::write(&path, "xxhello worldyy").unwrap();
+
+        let range = file_checksums(&path, &[Algorithm::Md5], 2, Some(11)).unwrap();
+        assert_eq!(
+            range.get(Algorithm::Md5),
+            Some("5eb63bbbe01eeed093cb22bb8f5acdc3")
+        );
+        let rest = file_checksums(&path, &[Algorithm::Crc32c], 0, None).unwrap();
+        assert!(rest.get(Algorithm::Crc32c).is_some());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,299 @@
+use crate::aws::AWS;
+use crate::aws::s3_client::S3Client;
+use crate::checksum::Verification;
+use crate::config::Config;
+use crate::safety::{Mutation, Verdict, confirms};
+use crate::sync::{SyncDirection, SyncOptions, SyncTarget, folder_prefix};
+use crate::util::{expand_tilde, format_size};
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+
+pub const USAGE: &str = "\
+Usage:
+  aws_tui_toolkit                        Browse S3 in the terminal UI
+  aws_tui_toolkit --read-only            Browse S3 with every action that changes it disabled;
+                                         with sync, refuses to upload
+  aws_tui_toolkit cat s3://bucket/key    Write an object to stdout, e.g. to pipe it elsewhere
+  aws_tui_toolkit sync SOURCE DEST [--delete] [--dry-run] [--exclude GLOB] [--include GLOB] [--yes]
+                                         Sync a local directory and an s3://bucket/prefix;
+                                         the side given first is copied onto the other;
+                                         deletes are confirmed first unless --yes is given
+  aws_tui_toolkit verify FILE s3://bucket/key
+                                         Compare a local file with an object by size and checksums
+  aws_tui_toolkit --help                 Show this help";
+
+/// What to run, from the command line arguments
+#[derive(Debug, Clone)]
+pub enum CliCommand {
+    Tui {
+        read_only: bool,
+    },
+    Help,
+    Cat {
+        bucket: String,
+        key: String,
+    },
+    Sync {
+        target: SyncTarget,
+        options: SyncOptions,
+        /// Run deletes without asking
+        yes: bool,
+        /// Refuse to upload, as in the terminal UI
+        read_only: bool,
+    },
+    Verify {
+        path: PathBuf,
+        bucket: String,
+        key: String,
+    },
+}
+
+/// Parses the arguments after the program name
+pub fn parse_args(args: &[String]) -> Result<CliCommand, String> {
+    // `--read-only` goes with any command, before or after it
+    let read_only = args.iter().any(|arg| arg == "--read-only");
+    let args: Vec<String> = args
+        .iter()
+        .filter(|arg| *arg != "--read-only")
+        .cloned()
+        .collect();
+    match args.as_slice() {
+        [] => Ok(CliCommand::Tui { read_only }),
+        [flag] if flag == "-h" || flag == "--help" => Ok(CliCommand::Help),
+        [command, uri] if command == "cat" => {
+            let (bucket, key) = parse_s3_uri(uri)?;
+            if key.is_empty() || key.ends_with('/') {
+                return Err(format!("{} is not an object key", uri));
+            }
+            Ok(CliCommand::Cat { bucket, key })
+        }
+        [command, ..] if command == "cat" => Err("cat takes one s3://bucket/key".to_string()),
+        [command, source, destination, flags @ ..] if command == "sync" => {
+            let (direction, uri, local_dir) = match (
+                source.starts_with("s3://"),
+                destination.starts_with("s3://"),
+            ) {
+                (false, true) => (SyncDirection::Upload, destination, source),
+                (true, false) => (SyncDirection::Download, source, destination),
+                _ => return Err("sync needs one local directory and one s3:// URI".to_string()),
+            };
+            let (bucket, prefix) = parse_s3_uri(uri)?;
+            let yes = flags.iter().any(|flag| flag == "--yes");
+            let flags = flags
+                .iter()
+                .map(String::as_str)
+                .filter(|flag| *flag != "--yes");
+            let options = SyncOptions::parse_flags(direction, local_dir, flags)?;
+            let target = SyncTarget {
+                bucket,
+                folder: folder_prefix(&prefix),
+                local_dir: options.local_dir.clone(),
+            };
+            Ok(CliCommand::Sync {
+                target,
+                options,
+                yes,
+                read_only,
+            })
+        }
+        [command, ..] if command == "sync" => {
+            Err("sync takes a source and a destination".to_string())
+        }
+        [command, path, uri] if command == "verify" => {
+            let (bucket, key) = parse_s3_uri(uri)?;
+            if key.is_empty() || key.ends_with('/') {
+                return Err(format!("{} is not an object key", uri));
+            }
+            Ok(CliCommand::Verify {
+                path: expand_tilde(path),
+                bucket,
+                key,
+            })
+        }
+        [command, ..] if command == "verify" => {
+            Err("verify takes a local file and an s3://bucket/key".to_string())
+        }
+        [other, ..] => Err(format!("unknown command '{}'", other)),
+    }
+}
+
+/// Splits `s3://bucket/key` into the bucket and the (possibly empty) key
+pub fn parse_s3_uri(uri: &str) -> Result<(String, String), String> {
+    let rest = uri
+        .strip_prefix("s3://")
+        .ok_or_else(|| format!("expected an s3://bucket/key URI, got '{}'", uri))?;
+    let (bucket, key) = rest.split_once('/').unwrap_or((rest, ""));
+    if bucket.is_empty() {
+        return Err(format!("{} has no bucket", uri));
+    }
+    Ok((bucket.to_string(), key.to_string()))
+}
+
+/// A client with the configured transfer limits; a broken config file falls back to the defaults
+async fn s3_client() -> S3Client {
+    let aws_config = AWS::new().await;
+    let s3_client = S3Client::new(&aws_config.config);
+    Config::load()
+        .unwrap_or_default()
+        .apply_limits(s3_client.limits());
+    s3_client
+}
+
+/// Run a command that does not need the terminal UI
+pub async fn run(command: CliCommand) -> Result<()> {
+    match command {
+        CliCommand::Tui { .. } => Ok(()),
+        CliCommand::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        CliCommand::Cat { bucket, key } => {
+            let s3_client = s3_client().await;
+            match s3_client
+                .stream_object(&bucket, &key, &mut tokio::io::stdout(), &())
+                .await
+            {
+                // The reader went away, e.g. `| head`, which is not an error for us
+                Err(e)
+                    if e.downcast_ref::<std::io::Error>()
+                        .is_some_and(|e| e.kind() == std::io::ErrorKind::BrokenPipe) =>
+                {
+                    Ok(())
+                }
+                result => result.map(|_| ()),
+            }
+        }
+        CliCommand::Verify { path, bucket, key } => {
+            let s3_client = s3_client().await;
+            let verification = s3_client.verify_file(&bucket, &key, &path).await?;
+            println!("{}", verification);
+            match verification {
+                Verification::Mismatch(_) => Err(eyre!(
+                    "{} does not match s3://{}/{}",
+                    path.display(),
+                    bucket,
+                    key
+                )),
+                _ => Ok(()),
+            }
+        }
+        CliCommand::Sync {
+            target,
+            options,
+            yes,
+            read_only,
+        } => {
+            // Uploads go through the same checks as in the terminal UI
+            let mut protected = None;
+            if options.direction == SyncDirection::Upload {
+                let safety = Config::load()
+                    .map_err(|e| eyre!(e))?
+                    .safety(&AWS::profile_name(), read_only);
+                let buckets = vec![target.bucket.clone()];
+                match safety.check(&Mutation::new("sync local files up", buckets, true)) {
+                    Verdict::Allow => {}
+                    Verdict::Block(reason) => return Err(eyre!(reason)),
+                    Verdict::Confirm(bucket) => protected = Some(bucket),
+                }
+            }
+            let s3_client = Arc::new(s3_client().await);
+            run_sync(s3_client, target, options, yes, protected).await
+        }
+    }
+}
+
+/// Asks a question on the terminal; without one there is nobody to answer
+fn ask(question: &str) -> Result<String> {
+    if !io::stdin().is_terminal() {
+        return Err(eyre!("No terminal to confirm the sync on"));
+    }
+    print!("{} ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(answer)
+}
+
+/// Print the plan, then carry it out unless it is a dry run or is not confirmed. Deletes
+/// need a yes or `--yes`; a `protected` bucket always needs its name typed.
+async fn run_sync(
+    s3_client: Arc<S3Client>,
+    target: SyncTarget,
+    options: SyncOptions,
+    yes: bool,
+    protected: Option<String>,
+) -> Result<()> {
+    let actions = target.plan(&s3_client, &options).await?;
+    if actions.is_empty() {
+        println!("Already in sync");
+        return Ok(());
+    }
+    for action in &actions {
+        println!(
+            "{:12} {} ({})",
+            action.kind.to_string(),
+            action.relative,
+            format_size(action.size as i64)
+        );
+    }
+    if options.dry_run {
+        println!(
+            "Dry run: {} action(s) planned, nothing changed",
+            actions.len()
+        );
+        return Ok(());
+    }
+    let deletes = actions.iter().filter(|a| !a.kind.transfers()).count();
+    let confirmed = if let Some(bucket) = &protected {
+        let question = format!("{} is a protected bucket; type its name to sync:", bucket);
+        confirms(bucket, &ask(&question)?)
+    } else if deletes > 0 && !yes {
+        let question = format!("Delete {} file(s) on the destination? [y/N]", deletes);
+        let answer = ask(&question).map_err(|e| eyre!("{}; pass --yes to run it anyway", e))?;
+        matches!(answer.trim(), "y" | "Y" | "yes")
+    } else {
+        true
+    };
+    if !confirmed {
+        return Err(eyre!("Sync cancelled; nothing changed"));
+    }
+
+    let total = actions.len();
+    let target = Arc::new(target);
+    let mut running = JoinSet::new();
+    let mut failures = 0;
+    for action in actions {
+        if running.len() >= s3_client.limits().requests.limit()
+            && let Some(result) = running.join_next().await
+            && !matches!(result, Ok(true))
+        {
+            failures += 1;
+        }
+        let s3_client = s3_client.clone();
+        let target = target.clone();
+        running.spawn(async move {
+            match target.run(&s3_client, &action, &()).await {
+                Ok(()) => true,
+                Err(e) => {
+                    eprintln!("{} {} failed: {}", action.kind, action.relative, e);
+                    false
+                }
+            }
+        });
+    }
+    while let Some(result) = running.join_next().await {
+        if !matches!(result, Ok(true)) {
+            failures += 1;
+        }
+    }
+
+    if failures > 0 {
+        return Err(eyre!("{} of {} sync action(s) failed", failures, total));
+    }
+    println!("Synced {} action(s)", total);
+    Ok(())
+}
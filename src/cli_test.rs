@@ -0,0 +1,110 @@
+use crate::cli::{CliCommand, parse_args, parse_s3_uri};
+use crate::sync::SyncDirection;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_s3_uri() {
+        assert_eq!(
+            parse_s3_uri("s3://bucket/logs/app.log"),
+            Ok(("bucket".to_string(), "logs/app.log".to_string()))
+        );
+        assert_eq!(
+            parse_s3_uri("s3://bucket"),
+            Ok(("bucket".to_string(), String::new()))
+        );
+        assert!(parse_s3_uri("s3:///key").is_err());
+        assert!(parse_s3_uri("bucket/key").is_err());
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert!(matches!(
+            parse_args(&[]),
+            Ok(CliCommand::Tui { read_only: false })
+        ));
+        assert!(matches!(
+            parse_args(&args(&["--read-only"])),
+            Ok(CliCommand::Tui { read_only: true })
+        ));
+        assert!(matches!(
+            parse_args(&args(&["cat", "s3://b/x.csv", "--read-only"])),
+            Ok(CliCommand::Cat { .. })
+        ));
+        assert!(matches!(
+            parse_args(&args(&["--help"])),
+            Ok(CliCommand::Help)
+        ));
+        assert!(matches!(
+            parse_args(&args(&["cat", "s3://b/data/x.csv"])),
+            Ok(CliCommand::Cat { bucket, key }) if bucket == "b" && key == "data/x.csv"
+        ));
+        assert!(parse_args(&args(&["cat", "s3://b/data/"])).is_err());
+        assert!(parse_args(&args(&["cat"])).is_err());
+        assert!(parse_args(&args(&["fetch", "s3://b/k"])).is_err());
+        assert!(matches!(
+            parse_args(&args(&["verify", "/tmp/x.csv", "s3://b/data/x.csv"])),
+            Ok(CliCommand::Verify { path, bucket, key })
+                if path == Path::new("/tmp/x.csv") && bucket == "b" && key == "data/x.csv"
+        ));
+        assert!(parse_args(&args(&["verify", "/tmp/x.csv", "s3://b/"])).is_err());
+        assert!(parse_args(&args(&["verify", "/tmp/x.csv"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_sync_args() {
+        let Ok(CliCommand::Sync {
+            target,
+            options,
+            yes,
+            read_only,
+        }) = parse_args(&args(&[
+            "sync",
+            "s3://b/data/2024",
+            "/tmp/data",
+            "--delete",
+            "--exclude",
+            "*.tmp",
+        ]))
+        else {
+            panic!("expected a sync command");
+        };
+        assert_eq!(target.bucket, "b");
+        assert_eq!(target.folder, "data/2024/");
+        assert_eq!(target.local_dir, PathBuf::from("/tmp/data"));
+        assert_eq!(options.direction, SyncDirection::Download);
+        assert!(options.delete && !options.dry_run && !yes && !read_only);
+        assert!(!options.filter.is_included("a/b.tmp"));
+
+        let Ok(CliCommand::Sync {
+            target,
+            options,
+            yes,
+            read_only,
+        }) = parse_args(&args(&[
+            "--read-only",
+            "sync",
+            "./site",
+            "s3://b",
+            "--dry-run",
+            "--yes",
+        ]))
+        else {
+            panic!("expected a sync command");
+        };
+        assert_eq!(target.folder, "");
+        assert_eq!(options.direction, SyncDirection::Upload);
+        assert!(options.dry_run && yes && read_only);
+
+        assert!(parse_args(&args(&["sync", "a", "b"])).is_err());
+        assert!(parse_args(&args(&["sync", "a", "s3://b", "--exclude"])).is_err());
+        assert!(parse_args(&args(&["sync", "a", "s3://b", "--force"])).is_err());
+    }
+}
@@ -0,0 +1,261 @@
+use crate::safety::Safety;
+use crate::throttle::{DEFAULT_MAX_REQUESTS, TransferLimits, parse_rate};
+use crate::util::{expand_tilde, parse_duration, split_shell_words};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// SigV4 presigned URLs are valid for at most a week
+pub const MAX_PRESIGN_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// A program to open downloaded objects with, chosen by extension or content type
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct OpenWithRule {
+    /// Extensions without the dot, e.g. `["png", "jpg"]`
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Content types, where `image/*` matches any image type
+    #[serde(default)]
+    pub content_types: Vec<String>,
+    /// Command line, split into words like a shell does; `{path}` is replaced by the downloaded
+    /// file, or the path is appended
+    pub command: String,
+    /// Runs in the terminal with the TUI suspended, rather than detached
+    #[serde(default)]
+    pub terminal: bool,
+}
+
+impl OpenWithRule {
+    /// A rule without extensions or content types matches everything
+    pub fn matches(&self, key: &str, content_type: Option<&str>) -> bool {
+        if self.extensions.is_empty() && self.content_types.is_empty() {
+            return true;
+        }
+
+        let file_name = key.rsplit('/').next().unwrap_or(key);
+        let extension_matches = file_name.rsplit_once('.').is_some_and(|(_, extension)| {
+            self.extensions.iter().any(|wanted| {
+                wanted
+                    .trim_start_matches('.')
+                    .eq_ignore_ascii_case(extension)
+            })
+        });
+
+        // Ignore parameters such as `; charset=utf-8`
+        let content_type = content_type.map(|ct| ct.split(';').next().unwrap_or(ct).trim());
+        let content_type_matches = content_type.is_some_and(|actual| {
+            self.content_types
+                .iter()
+                .any(|wanted| match wanted.strip_suffix("/*") {
+                    Some(family) => actual
+                        .split_once('/')
+                        .is_some_and(|(f, _)| f.eq_ignore_ascii_case(family)),
+                    None => wanted.eq_ignore_ascii_case(actual),
+                })
+        });
+
+        extension_matches || content_type_matches
+    }
+
+    /// The program and its arguments for opening `path`
+    pub fn command_line(&self, path: &str) -> Result<Vec<String>, String> {
+        let mut parts = split_shell_words(&self.command)
+            .map_err(|e| format!("invalid command '{}': {}", self.command, e))?;
+        if parts.iter().any(|part| part.contains("{path}")) {
+            for part in &mut parts {
+                *part = part.replace("{path}", path);
+            }
+        } else {
+            parts.push(path.to_string());
+        }
+        Ok(parts)
+    }
+}
+
+/// User settings read from `config.json` in the platform config directory
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Checked in order; the first matching rule is used
+    pub open_with: Vec<OpenWithRule>,
+    /// How long presigned URLs stay valid, e.g. `15m`, `12h` or `7d`
+    pub presign_expiry: String,
+    /// Where downloads go when a bucket has no remembered directory; `~` is expanded
+    pub download_dir: Option<String>,
+    /// Cap on the combined speed of all transfers, e.g. `10MB` per second
+    pub bandwidth_limit: Option<String>,
+    /// Requests moving object data (GETs, PUTs, parts, copies) allowed at the same time
+    pub max_concurrent_requests: usize,
+    /// Refuse every action that changes S3, like `--read-only`
+    pub read_only: bool,
+    /// Bucket name globs per AWS profile; deletes and overwrites in them need the name typed
+    pub protected_buckets: HashMap<String, Vec<String>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let system_opener = if cfg!(target_os = "macos") {
+            "open"
+        } else {
+            "xdg-open"
+        };
+        Self {
+            open_with: vec![
+                OpenWithRule {
+                    extensions: vec!["txt".into(), "log".into(), "csv".into(), "md".into()],
+                    content_types: vec!["text/*".into(), "application/json".into()],
+                    command: "less {path}".into(),
+                    terminal: true,
+                },
+                OpenWithRule {
+                    extensions: Vec::new(),
+                    content_types: Vec::new(),
+                    command: system_opener.into(),
+                    terminal: false,
+                },
+            ],
+            presign_expiry: "1h".into(),
+            download_dir: None,
+            bandwidth_limit: None,
+            max_concurrent_requests: DEFAULT_MAX_REQUESTS,
+            read_only: false,
+            protected_buckets: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("aws_tui_toolkit").join("config.json"))
+    }
+
+    /// Read the config file, falling back to defaults when there is none
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => Self::parse(&contents)
+                .map_err(|e| format!("Invalid config {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("Could not read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let config: Self = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+        parse_presign_expiry(&config.presign_expiry)
+            .map_err(|e| format!("presign_expiry: {}", e))?;
+        if let Some(limit) = &config.bandwidth_limit
+            && parse_rate(limit).is_none()
+        {
+            return Err(format!(
+                "bandwidth_limit: expected a rate like 10MB or 512KB, got '{}'",
+                limit
+            ));
+        }
+        if config.max_concurrent_requests == 0 {
+            return Err("max_concurrent_requests: must be at least 1".to_string());
+        }
+        for (profile, patterns) in &config.protected_buckets {
+            Safety::new(false, patterns)
+                .map_err(|e| format!("protected_buckets.{}: {}", profile, e))?;
+        }
+        Ok(config)
+    }
+
+    pub fn presign_expiry(&self) -> Duration {
+        parse_presign_expiry(&self.presign_expiry).unwrap_or(Duration::from_secs(60 * 60))
+    }
+
+    /// Bytes per second for all transfers together; 0 is unlimited
+    pub fn bandwidth_limit(&self) -> u64 {
+        self.bandwidth_limit
+            .as_deref()
+            .and_then(parse_rate)
+            .unwrap_or(0)
+    }
+
+    /// Start transfers with the configured bandwidth and request limits
+    pub fn apply_limits(&self, limits: &TransferLimits) {
+        limits.bandwidth.set_limit(self.bandwidth_limit());
+        limits.requests.set_limit(self.max_concurrent_requests);
+    }
+
+    /// The configured download directory, then the OS downloads folder, then `.`
+    pub fn download_dir(&self) -> PathBuf {
+        self.download_dir
+            .as_deref()
+            .map(expand_tilde)
+            .or_else(dirs::download_dir)
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    /// Read-only mode (from the flag or the config) and the protected buckets of `profile`
+    pub fn safety(&self, profile: &str, read_only: bool) -> Safety {
+        let patterns = self
+            .protected_buckets
+            .get(profile)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let read_only = read_only || self.read_only;
+        // Patterns are checked when the config is parsed
+        Safety::new(read_only, patterns)
+            .or_else(|_| Safety::new(read_only, &[]))
+            .unwrap_or_default()
+    }
+
+    pub fn open_with_rule(&self, key: &str, content_type: Option<&str>) -> Option<&OpenWithRule> {
+        self.open_with
+            .iter()
+            .find(|rule| rule.matches(key, content_type))
+    }
+}
+
+/// Parses an expiry such as `15m` or `12h`, within what presigned URLs allow
+pub fn parse_presign_expiry(value: &str) -> Result<Duration, String> {
+    let expiry = parse_duration(value)
+        .ok_or_else(|| format!("expected a duration like 15m, 12h or 7d, got '{}'", value))?;
+    if expiry.is_zero() || expiry > MAX_PRESIGN_EXPIRY {
+        return Err("presigned URLs can last from 1s to 7d".to_string());
+    }
+    Ok(expiry)
+}
+
+/// The last directory downloaded to from each bucket, kept between runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecentDirs {
+    dirs: HashMap<String, PathBuf>,
+}
+
+impl RecentDirs {
+    fn path() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|dir| dir.join("aws_tui_toolkit").join("recent_dirs.json"))
+    }
+
+    /// Missing or unreadable history just means nothing is remembered yet
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn get(&self, bucket: &str) -> Option<&Path> {
+        self.dirs.get(bucket).map(PathBuf::as_path)
+    }
+
+    /// Remember `dir` for `bucket` and write the history back, ignoring failures
+    pub fn remember(&mut self, bucket: &str, dir: &Path) {
+        self.dirs.insert(bucket.to_string(), dir.to_path_buf());
+        let Some(path) = Self::path() else {
+            return;
+        };
+        if let (Some(parent), Ok(contents)) = (path.parent(), serde_json::to_string_pretty(self)) {
+            let _ = fs::create_dir_all(parent);
+            let _ = fs::write(path, contents);
+        }
+    }
+}
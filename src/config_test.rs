@@ -0,0 +1,99 @@
+use crate::config::{Config, OpenWithRule, parse_presign_expiry};
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(extensions: &[&str], content_types: &[&str], command: &str) -> OpenWithRule {
+        OpenWithRule {
+            extensions: extensions.iter().map(|e| e.to_string()).collect(),
+            content_types: content_types.iter().map(|c| c.to_string()).collect(),
+            command: command.to_string(),
+            terminal: false,
+        }
+    }
+
+    #[test]
+    fn test_open_with_rule_matching() {
+        let images = rule(&["png", ".JPG"], &["image/*"], "feh");
+        assert!(images.matches("photos/cat.png", None));
+        assert!(images.matches("photos/cat.jpg", None));
+        assert!(images.matches("photos/cat", Some("image/webp")));
+        assert!(!images.matches("photos/cat.txt", Some("text/plain")));
+        assert!(!images.matches("photos.png/cat", None));
+
+        let json = rule(&[], &["application/json"], "jq . {path}");
+        assert!(json.matches("a", Some("application/json; charset=utf-8")));
+        assert!(!json.matches("a.json", None));
+
+        assert!(rule(&[], &[], "xdg-open").matches("anything", None));
+    }
+
+    #[test]
+    fn test_open_with_command_line() {
+        assert_eq!(
+            rule(&[], &[], r#"duckdb -c "SELECT * FROM '{path}'""#)
+                .command_line("/tmp/my data/x.parquet"),
+            Ok(vec![
+                "duckdb".to_string(),
+                "-c".to_string(),
+                "SELECT * FROM '/tmp/my data/x.parquet'".to_string()
+            ])
+        );
+        assert_eq!(
+            rule(&[], &[], "less").command_line("/tmp/a b.log"),
+            Ok(vec!["less".to_string(), "/tmp/a b.log".to_string()])
+        );
+        assert!(rule(&[], &[], "jq '.a").command_line("/tmp/x").is_err());
+    }
+
+    #[test]
+    fn test_config_parse() {
+        let config = Config::parse(
+            r#"{"open_with": [{"extensions": ["parquet"], "command": "parquet-tools show", "terminal": true}]}"#,
+        )
+        .unwrap();
+        let rule = config.open_with_rule("data/part-0.parquet", None).unwrap();
+        assert_eq!(rule.command, "parquet-tools show");
+        assert!(rule.terminal);
+        assert!(config.open_with_rule("data/readme.md", None).is_none());
+
+        // Omitted settings keep their defaults
+        let config = Config::parse("{}").unwrap();
+        assert!(config.open_with_rule("x.bin", None).is_some());
+
+        assert!(Config::parse(r#"{"open_with": [{"terminal": true}]}"#).is_err());
+    }
+
+    #[test]
+    fn test_presign_expiry() {
+        assert_eq!(
+            Config::default().presign_expiry(),
+            Duration::from_secs(3600)
+        );
+        let config = Config::parse(r#"{"presign_expiry": "15m"}"#).unwrap();
+        assert_eq!(config.presign_expiry(), Duration::from_secs(900));
+
+        assert_eq!(parse_presign_expiry("7d"), Ok(Duration::from_secs(604_800)));
+        assert!(parse_presign_expiry("8d").is_err());
+        assert!(parse_presign_expiry("0s").is_err());
+        assert!(Config::parse(r#"{"presign_expiry": "soon"}"#).is_err());
+    }
+
+    #[test]
+    fn test_safety_settings() {
+        let config =
+            Config::parse(r#"{"protected_buckets": {"prod": ["prod-*"], "default": []}}"#).unwrap();
+        let safety = config.safety("prod", false);
+        assert!(!safety.read_only);
+        assert_eq!(safety.protecting("prod-logs"), Some("prod-*"));
+        assert_eq!(config.safety("dev", false).protecting("prod-logs"), None);
+        assert!(config.safety("dev", true).read_only);
+
+        let config = Config::parse(r#"{"read_only": true}"#).unwrap();
+        assert!(config.safety("default", false).read_only);
+
+        assert!(Config::parse(r#"{"protected_buckets": {"prod": ["prod-[x"]}}"#).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use crate::util::{parse_duration, parse_size};
+use aws_sdk_s3::primitives::{DateTime, DateTimeFormat};
+use aws_sdk_s3::types::Object;
+use globset::{GlobBuilder, GlobMatcher};
+use std::time::SystemTime;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Glob,
+}
+
+impl Comparison {
+    /// Longest operators first so `<=` is not read as `<`
+    const OPERATORS: [(&'static str, Comparison); 7] = [
+        ("<=", Comparison::Le),
+        (">=", Comparison::Ge),
+        ("!=", Comparison::Ne),
+        ("<", Comparison::Lt),
+        (">", Comparison::Gt),
+        ("=", Comparison::Eq),
+        ("~", Comparison::Glob),
+    ];
+
+    fn symbol(self) -> &'static str {
+        Self::OPERATORS
+            .iter()
+            .find(|(_, op)| *op == self)
+            .map(|(symbol, _)| *symbol)
+            .unwrap_or("?")
+    }
+
+    /// Compares a value against an inclusive `[start, end]` range
+    fn in_range(self, value: i64, start: i64, end: i64) -> bool {
+        match self {
+            Self::Eq => value >= start && value <= end,
+            Self::Ne => value < start || value > end,
+            Self::Lt => value < start,
+            Self::Le => value <= end,
+            Self::Gt => value > end,
+            Self::Ge => value >= start,
+            Self::Glob => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Clause {
+    Size(Comparison, i64),
+    /// Last-modified compared against an inclusive range of epoch seconds
+    Modified(Comparison, i64, i64),
+    Class(Comparison, String),
+    Key(Comparison, String),
+    KeyGlob(GlobMatcher),
+}
+
+impl Clause {
+    fn matches(&self, object: &Object) -> bool {
+        match self {
+            Self::Size(cmp, size) => cmp.in_range(object.size().unwrap_or(0), *size, *size),
+            Self::Modified(cmp, start, end) => match object.last_modified() {
+                Some(modified) => cmp.in_range(modified.secs(), *start, *end),
+                None => false,
+            },
+            Self::Class(cmp, class) => {
+                // Listings omit the class for STANDARD objects in some S3-compatible stores
+                let actual = object
+                    .storage_class()
+                    .map(|c| c.as_str())
+                    .unwrap_or("STANDARD");
+                let equal = actual.eq_ignore_ascii_case(class);
+                if *cmp == Comparison::Ne {
+                    !equal
+                } else {
+                    equal
+                }
+            }
+            Self::Key(cmp, key) => {
+                let equal = object.key() == Some(key.as_str());
+                if *cmp == Comparison::Ne {
+                    !equal
+                } else {
+                    equal
+                }
+            }
+            Self::KeyGlob(glob) => object.key().is_some_and(|key| glob.is_match(key)),
+        }
+    }
+}
+
+/// A conjunction of metadata conditions over listed objects, e.g.
+/// `size>1G modified<2024-01-01 class=STANDARD key~*.csv`
+#[derive(Debug)]
+pub struct ObjectFilter {
+    clauses: Vec<Clause>,
+}
+
+impl ObjectFilter {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let clauses = expression
+            .split_whitespace()
+            .map(parse_clause)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { clauses })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    pub fn matches(&self, object: &Object) -> bool {
+        self.clauses.iter().all(|clause| clause.matches(object))
+    }
+}
+
+fn parse_clause(token: &str) -> Result<Clause, String> {
+    let field_end = token
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .ok_or_else(|| format!("missing operator in '{}'", token))?;
+    let (field, rest) = token.split_at(field_end);
+
+    let (symbol, cmp) = Comparison::OPERATORS
+        .iter()
+        .find(|(symbol, _)| rest.starts_with(symbol))
+        .ok_or_else(|| format!("missing operator in '{}'", token))?;
+    let value = &rest[symbol.len()..];
+    if value.is_empty() {
+        return Err(format!("missing value in '{}'", token));
+    }
+
+    let unsupported = || format!("'{}' does not support '{}'", field, cmp.symbol());
+
+    match field.to_ascii_lowercase().as_str() {
+        "size" => {
+            if *cmp == Comparison::Glob {
+                return Err(unsupported());
+            }
+            Ok(Clause::Size(*cmp, parse_size(value)?))
+        }
+        "modified" | "mtime" => {
+            if *cmp == Comparison::Glob {
+                return Err(unsupported());
+            }
+            let (start, end) = parse_time(value)?;
+            Ok(Clause::Modified(*cmp, start, end))
+        }
+        "class" | "storage" => match cmp {
+            Comparison::Eq | Comparison::Ne => Ok(Clause::Class(*cmp, value.to_string())),
+            _ => Err(unsupported()),
+        },
+        "key" => match cmp {
+            Comparison::Eq | Comparison::Ne => Ok(Clause::Key(*cmp, value.to_string())),
+            // `*` crosses `/` as in sync filters, so `*.csv` matches at any depth
+            Comparison::Glob => GlobBuilder::new(value)
+                .literal_separator(false)
+                .build()
+                .map(|glob| Clause::KeyGlob(glob.compile_matcher()))
+                .map_err(|e| format!("invalid glob '{}': {}", value, e)),
+            _ => Err(unsupported()),
+        },
+        _ => Err(format!("unknown field '{}'", field)),
+    }
+}
+
+/// Parses a point in time into an inclusive range of epoch seconds.
+/// Accepts `YYYY-MM-DD` (the whole day), RFC 3339 timestamps, or an age such as `30d`, `12h` or `2w`
+/// (see `parse_duration`).
+fn parse_time(value: &str) -> Result<(i64, i64), String> {
+    if let Some(age) = parse_duration(value) {
+        let instant = SystemTime::now()
+            .checked_sub(age)
+            .map(DateTime::from)
+            .ok_or_else(|| format!("age '{}' is too large", value))?;
+        return Ok((instant.secs(), instant.secs()));
+    }
+
+    if let Ok(day) = DateTime::from_str(&format!("{}T00:00:00Z", value), DateTimeFormat::DateTime) {
+        return Ok((day.secs(), day.secs() + SECONDS_PER_DAY - 1));
+    }
+
+    DateTime::from_str(value, DateTimeFormat::DateTime)
+        .map(|instant| (instant.secs(), instant.secs()))
+        .map_err(|_| format!("invalid date '{}'", value))
+}
@@ -0,0 +1,129 @@
+use crate::filter::ObjectFilter;
+use aws_sdk_s3::primitives::{DateTime, DateTimeFormat};
+use aws_sdk_s3::types::{Object, ObjectStorageClass};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(key: &str, size: i64, modified: &str, class: ObjectStorageClass) -> Object {
+        Object::builder()
+            .key(key)
+            .size(size)
+            .last_modified(DateTime::from_str(modified, DateTimeFormat::DateTime).unwrap())
+            .storage_class(class)
+            .build()
+    }
+
+    fn sample() -> Vec<Object> {
+        vec![
+            object(
+                "logs/2023/big.csv",
+                2 * 1024 * 1024 * 1024,
+                "2023-06-01T10:00:00Z",
+                ObjectStorageClass::Standard,
+            ),
+            object(
+                "logs/2024/small.csv",
+                10 * 1024,
+                "2024-02-01T10:00:00Z",
+                ObjectStorageClass::Standard,
+            ),
+            object(
+                "archive/old.parquet",
+                5 * 1024 * 1024 * 1024,
+                "2022-01-01T00:00:00Z",
+                ObjectStorageClass::Glacier,
+            ),
+        ]
+    }
+
+    fn matching_keys(expression: &str) -> Vec<String> {
+        let filter = ObjectFilter::parse(expression).unwrap();
+        sample()
+            .iter()
+            .filter(|o| filter.matches(o))
+            .map(|o| o.key().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_empty_expression_matches_everything() {
+        let filter = ObjectFilter::parse("   ").unwrap();
+        assert!(filter.is_empty());
+        assert_eq!(matching_keys("").len(), 3);
+    }
+
+    #[test]
+    fn test_size_comparisons() {
+        assert_eq!(
+            matching_keys("size>1G"),
+            vec!["logs/2023/big.csv", "archive/old.parquet"]
+        );
+        assert_eq!(matching_keys("size<=10K"), vec!["logs/2024/small.csv"]);
+        assert_eq!(matching_keys("size=10240"), vec!["logs/2024/small.csv"]);
+        assert_eq!(matching_keys("size>=1.5GB").len(), 2);
+    }
+
+    #[test]
+    fn test_modified_comparisons() {
+        assert_eq!(
+            matching_keys("modified<2024-01-01"),
+            vec!["logs/2023/big.csv", "archive/old.parquet"]
+        );
+        // A bare date covers the whole day
+        assert_eq!(
+            matching_keys("modified=2024-02-01"),
+            vec!["logs/2024/small.csv"]
+        );
+        assert_eq!(
+            matching_keys("mtime>2023-06-01T09:00:00Z"),
+            vec!["logs/2023/big.csv", "logs/2024/small.csv"]
+        );
+        // Everything in the sample is older than a week
+        assert!(matching_keys("modified>7d").is_empty());
+        assert!(matching_keys("modified>90m").is_empty());
+    }
+
+    #[test]
+    fn test_class_and_key() {
+        assert_eq!(matching_keys("class=glacier"), vec!["archive/old.parquet"]);
+        assert_eq!(matching_keys("class!=GLACIER").len(), 2);
+        assert_eq!(
+            matching_keys("key~**/*.csv"),
+            vec!["logs/2023/big.csv", "logs/2024/small.csv"]
+        );
+        assert_eq!(
+            matching_keys("key~*.csv"),
+            vec!["logs/2023/big.csv", "logs/2024/small.csv"]
+        );
+        assert_eq!(
+            matching_keys("key~logs/*/small.*"),
+            vec!["logs/2024/small.csv"]
+        );
+        assert_eq!(
+            matching_keys("key=archive/old.parquet"),
+            vec!["archive/old.parquet"]
+        );
+    }
+
+    #[test]
+    fn test_clauses_are_combined() {
+        assert_eq!(
+            matching_keys("size>1G modified<2024-01-01 class=STANDARD key~*.csv"),
+            vec!["logs/2023/big.csv"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        assert!(ObjectFilter::parse("size").is_err());
+        assert!(ObjectFilter::parse("size>").is_err());
+        assert!(ObjectFilter::parse("size>1X").is_err());
+        assert!(ObjectFilter::parse("size~1G").is_err());
+        assert!(ObjectFilter::parse("owner=me").is_err());
+        assert!(ObjectFilter::parse("modified<yesterday").is_err());
+        assert!(ObjectFilter::parse("class>STANDARD").is_err());
+        assert!(ObjectFilter::parse("key~[a-").is_err());
+    }
+}
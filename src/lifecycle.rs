@@ -0,0 +1,613 @@
+use crate::util::format_size;
+use aws_sdk_s3::primitives::{DateTime, DateTimeFormat};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Storage classes objects can transition to
+pub const TRANSITION_CLASSES: [&str; 6] = [
+    "STANDARD_IA",
+    "ONEZONE_IA",
+    "INTELLIGENT_TIERING",
+    "GLACIER_IR",
+    "GLACIER",
+    "DEEP_ARCHIVE",
+];
+
+/// The infrequent access classes take objects only after 30 days in the bucket
+const MIN_DAYS_30: [&str; 2] = ["STANDARD_IA", "ONEZONE_IA"];
+
+/// A bucket's lifecycle rules, in the JSON shape `aws s3api get-bucket-lifecycle-configuration`
+/// prints
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct LifecycleConfig {
+    pub rules: Vec<LifecycleRule>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct LifecycleRule {
+    #[serde(rename = "ID", default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// `Enabled` or `Disabled`
+    pub status: String,
+    #[serde(default)]
+    pub filter: RuleFilter,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transitions: Vec<Transition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiration: Option<Expiration>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub noncurrent_version_transitions: Vec<NoncurrentTransition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub noncurrent_version_expiration: Option<NoncurrentExpiration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abort_incomplete_multipart_upload: Option<AbortIncompleteUpload>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct RuleTag {
+    pub key: String,
+    pub value: String,
+}
+
+/// Which objects a rule applies to; more than one condition goes under `And`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct RuleFilter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<RuleTag>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object_size_greater_than: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object_size_less_than: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub and: Option<AndFilter>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct AndFilter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<RuleTag>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object_size_greater_than: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object_size_less_than: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct Transition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days: Option<i32>,
+    /// `YYYY-MM-DD`, midnight UTC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    pub storage_class: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct Expiration {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expired_object_delete_marker: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct NoncurrentTransition {
+    pub noncurrent_days: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub newer_noncurrent_versions: Option<i32>,
+    pub storage_class: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct NoncurrentExpiration {
+    pub noncurrent_days: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub newer_noncurrent_versions: Option<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct AbortIncompleteUpload {
+    pub days_after_initiation: i32,
+}
+
+/// Parses a lifecycle date, `YYYY-MM-DD` or a midnight UTC timestamp
+pub fn parse_lifecycle_date(value: &str) -> Result<DateTime, String> {
+    let timestamp = if value.len() == 10 {
+        format!("{}T00:00:00Z", value)
+    } else {
+        value.to_string()
+    };
+    let date = DateTime::from_str(&timestamp, DateTimeFormat::DateTime)
+        .map_err(|_| format!("'{}' is not a date like 2030-01-01", value))?;
+    if date.secs() % (24 * 60 * 60) != 0 {
+        return Err(format!("'{}' must be midnight UTC", value));
+    }
+    Ok(date)
+}
+
+/// `2030-01-01` for a lifecycle date
+pub fn format_lifecycle_date(date: &DateTime) -> String {
+    date.fmt(DateTimeFormat::DateTime)
+        .map(|timestamp| timestamp[..10].to_string())
+        .unwrap_or_default()
+}
+
+impl RuleFilter {
+    /// A filter with the conditions given, using `And` only when there is more than one
+    pub fn new(
+        prefix: Option<String>,
+        tags: Vec<RuleTag>,
+        min_size: Option<i64>,
+        max_size: Option<i64>,
+    ) -> Self {
+        let conditions = usize::from(prefix.is_some())
+            + tags.len()
+            + usize::from(min_size.is_some())
+            + usize::from(max_size.is_some());
+        if conditions > 1 {
+            return Self {
+                and: Some(AndFilter {
+                    prefix,
+                    tags,
+                    object_size_greater_than: min_size,
+                    object_size_less_than: max_size,
+                }),
+                ..Self::default()
+            };
+        }
+        Self {
+            prefix,
+            tag: tags.into_iter().next(),
+            object_size_greater_than: min_size,
+            object_size_less_than: max_size,
+            and: None,
+        }
+    }
+
+    pub fn prefix(&self) -> Option<&str> {
+        self.and
+            .as_ref()
+            .map_or(self.prefix.as_deref(), |and| and.prefix.as_deref())
+            .filter(|prefix| !prefix.is_empty())
+    }
+
+    pub fn tags(&self) -> Vec<RuleTag> {
+        match &self.and {
+            Some(and) => and.tags.clone(),
+            None => self.tag.iter().cloned().collect(),
+        }
+    }
+
+    /// Objects must be larger than the first and smaller than the second
+    pub fn size_range(&self) -> (Option<i64>, Option<i64>) {
+        match &self.and {
+            Some(and) => (and.object_size_greater_than, and.object_size_less_than),
+            None => (self.object_size_greater_than, self.object_size_less_than),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let top_level = usize::from(self.prefix.is_some())
+            + usize::from(self.tag.is_some())
+            + usize::from(self.object_size_greater_than.is_some())
+            + usize::from(self.object_size_less_than.is_some())
+            + usize::from(self.and.is_some());
+        if top_level > 1 {
+            return Err("the filter combines conditions without And".to_string());
+        }
+        if let (Some(min), Some(max)) = self.size_range()
+            && min >= max
+        {
+            return Err("ObjectSizeGreaterThan must be below ObjectSizeLessThan".to_string());
+        }
+        let mut keys = HashSet::new();
+        if let Some(tag) = self.tags().iter().find(|tag| !keys.insert(tag.key.clone())) {
+            return Err(format!("the tag {} is given twice", tag.key));
+        }
+        Ok(())
+    }
+}
+
+impl LifecycleRule {
+    /// Reads like `objects under logs/ tagged tier=cold → GLACIER after 30d, expire after 365d`
+    pub fn describe(&self) -> String {
+        let filter = &self.filter;
+        let mut scope = match filter.prefix() {
+            Some(prefix) => format!("objects under {}", prefix),
+            None => "all objects".to_string(),
+        };
+        let tags = filter.tags();
+        if !tags.is_empty() {
+            let tags: Vec<String> = tags
+                .iter()
+                .map(|tag| format!("{}={}", tag.key, tag.value))
+                .collect();
+            scope.push_str(&format!(" tagged {}", tags.join(",")));
+        }
+        match filter.size_range() {
+            (Some(min), Some(max)) => {
+                scope.push_str(&format!(" of {} to {}", format_size(min), format_size(max)))
+            }
+            (Some(min), None) => scope.push_str(&format!(" over {}", format_size(min))),
+            (None, Some(max)) => scope.push_str(&format!(" under {}", format_size(max))),
+            (None, None) => {}
+        }
+
+        let when = |days: Option<i32>, date: &Option<String>| match (days, date) {
+            (_, Some(date)) => format!("on {}", date),
+            (Some(days), None) => format!("after {}d", days),
+            (None, None) => "?".to_string(),
+        };
+        let mut actions: Vec<String> = self
+            .transitions
+            .iter()
+            .map(|t| format!("{} {}", t.storage_class, when(t.days, &t.date)))
+            .collect();
+        if let Some(expiration) = &self.expiration {
+            if expiration.days.is_some() || expiration.date.is_some() {
+                actions.push(format!(
+                    "expire {}",
+                    when(expiration.days, &expiration.date)
+                ));
+            }
+            if expiration.expired_object_delete_marker == Some(true) {
+                actions.push("remove expired delete markers".to_string());
+            }
+        }
+        for transition in &self.noncurrent_version_transitions {
+            actions.push(format!(
+                "noncurrent versions {} after {}d",
+                transition.storage_class, transition.noncurrent_days
+            ));
+        }
+        if let Some(expiration) = &self.noncurrent_version_expiration {
+            let kept = expiration
+                .newer_noncurrent_versions
+                .map_or(String::new(), |n| format!(" (keeping {} newer)", n));
+            actions.push(format!(
+                "delete noncurrent versions after {}d{}",
+                expiration.noncurrent_days, kept
+            ));
+        }
+        if let Some(abort) = &self.abort_incomplete_multipart_upload {
+            actions.push(format!(
+                "abort incomplete uploads after {}d",
+                abort.days_after_initiation
+            ));
+        }
+
+        let description = format!("{} → {}", scope, actions.join(", "));
+        if self.status == "Enabled" {
+            description
+        } else {
+            format!("[disabled] {}", description)
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let name = self.id.as_deref().unwrap_or("(no ID)");
+        self.check().map_err(|e| format!("rule {}: {}", name, e))
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.id.as_ref().is_some_and(|id| id.len() > 255) {
+            return Err("the ID is longer than 255 characters".to_string());
+        }
+        if self.status != "Enabled" && self.status != "Disabled" {
+            return Err("Status must be Enabled or Disabled".to_string());
+        }
+        self.filter.validate()?;
+        let tagged = !self.filter.tags().is_empty();
+
+        if self.transitions.is_empty()
+            && self.expiration.is_none()
+            && self.noncurrent_version_transitions.is_empty()
+            && self.noncurrent_version_expiration.is_none()
+            && self.abort_incomplete_multipart_upload.is_none()
+        {
+            return Err("it has no transition or expiration".to_string());
+        }
+
+        let mut classes = HashSet::new();
+        for transition in &self.transitions {
+            let class = transition.storage_class.as_str();
+            if !TRANSITION_CLASSES.contains(&class) {
+                return Err(format!("{} is not a storage class to transition to", class));
+            }
+            if !classes.insert(class) {
+                return Err(format!("two transitions to {}", class));
+            }
+            match (transition.days, &transition.date) {
+                (Some(days), None) => {
+                    if days < 0 {
+                        return Err("transition days must not be negative".to_string());
+                    }
+                    if MIN_DAYS_30.contains(&class) && days < 30 {
+                        return Err(format!("{} needs at least 30 days", class));
+                    }
+                }
+                (None, Some(date)) => {
+                    parse_lifecycle_date(date)?;
+                }
+                _ => return Err("each transition needs either Days or Date".to_string()),
+            }
+        }
+
+        if let Some(expiration) = &self.expiration {
+            let set = usize::from(expiration.days.is_some())
+                + usize::from(expiration.date.is_some())
+                + usize::from(expiration.expired_object_delete_marker.is_some());
+            if set != 1 {
+                return Err(
+                    "Expiration needs exactly one of Days, Date or ExpiredObjectDeleteMarker"
+                        .to_string(),
+                );
+            }
+            if let Some(days) = expiration.days {
+                if days <= 0 {
+                    return Err("expiration days must be above 0".to_string());
+                }
+                if let Some(last) = self.transitions.iter().filter_map(|t| t.days).max()
+                    && days <= last
+                {
+                    return Err("objects must expire after their last transition".to_string());
+                }
+            }
+            if let Some(date) = &expiration.date {
+                parse_lifecycle_date(date)?;
+            }
+            if expiration.expired_object_delete_marker.is_some() && tagged {
+                return Err("ExpiredObjectDeleteMarker cannot be used with tags".to_string());
+            }
+        }
+
+        let mut classes = HashSet::new();
+        for transition in &self.noncurrent_version_transitions {
+            let class = transition.storage_class.as_str();
+            if !TRANSITION_CLASSES.contains(&class) {
+                return Err(format!("{} is not a storage class to transition to", class));
+            }
+            if !classes.insert(class) {
+                return Err(format!("two noncurrent transitions to {}", class));
+            }
+            if transition.noncurrent_days <= 0 {
+                return Err("NoncurrentDays must be above 0".to_string());
+            }
+        }
+        if let Some(expiration) = &self.noncurrent_version_expiration
+            && expiration.noncurrent_days <= 0
+        {
+            return Err("NoncurrentDays must be above 0".to_string());
+        }
+        if let Some(abort) = &self.abort_incomplete_multipart_upload {
+            if abort.days_after_initiation <= 0 {
+                return Err("DaysAfterInitiation must be above 0".to_string());
+            }
+            if tagged {
+                return Err("incomplete uploads cannot be aborted by a tag filter".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LifecycleConfig {
+    /// Parses and validates the JSON form
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let config: Self =
+            serde_json::from_str(text).map_err(|e| format!("invalid lifecycle JSON: {}", e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.rules.len() > 1000 {
+            return Err("a bucket takes at most 1000 rules".to_string());
+        }
+        let mut ids = HashSet::new();
+        for rule in &self.rules {
+            rule.validate()?;
+            if let Some(id) = &rule.id
+                && !ids.insert(id)
+            {
+                return Err(format!("two rules have the ID {}", id));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// Parses a day count for the rule form; empty or `off` clears it
+pub fn parse_days(input: &str) -> Result<Option<i32>, String> {
+    match input.trim() {
+        "" | "off" => Ok(None),
+        days => days
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("'{}' is not a number of days", days)),
+    }
+}
+
+/// Parses `tier=cold,team=ops`
+pub fn parse_tags(input: &str) -> Result<Vec<RuleTag>, String> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| match tag.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok(RuleTag {
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+            _ => Err(format!("expected key=value, got '{}'", tag)),
+        })
+        .collect()
+}
+
+pub fn format_tags(tags: &[RuleTag]) -> String {
+    let tags: Vec<String> = tags
+        .iter()
+        .map(|tag| format!("{}={}", tag.key, tag.value))
+        .collect();
+    tags.join(",")
+}
+
+/// Splits `WHEN:CLASS`, upper-casing the class
+fn split_class(word: &str) -> Result<(&str, String), String> {
+    match word.rsplit_once(':') {
+        Some((when, class)) if !when.is_empty() && !class.is_empty() => {
+            Ok((when, class.to_ascii_uppercase()))
+        }
+        _ => Err(format!("expected DAYS:CLASS, got '{}'", word)),
+    }
+}
+
+/// Parses `30:STANDARD_IA 90:GLACIER`, or dates such as `2030-01-01:GLACIER`
+pub fn parse_transitions(input: &str) -> Result<Vec<Transition>, String> {
+    input
+        .split_whitespace()
+        .map(|word| {
+            let (when, storage_class) = split_class(word)?;
+            let (days, date) = match when.parse() {
+                Ok(days) => (Some(days), None),
+                Err(_) => {
+                    parse_lifecycle_date(when)?;
+                    (None, Some(when.to_string()))
+                }
+            };
+            Ok(Transition {
+                days,
+                date,
+                storage_class,
+            })
+        })
+        .collect()
+}
+
+pub fn format_transitions(transitions: &[Transition]) -> String {
+    let words: Vec<String> = transitions
+        .iter()
+        .map(|t| {
+            let when = t
+                .date
+                .clone()
+                .unwrap_or_else(|| t.days.unwrap_or_default().to_string());
+            format!("{}:{}", when, t.storage_class)
+        })
+        .collect();
+    words.join(" ")
+}
+
+/// Parses `30:GLACIER 180:DEEP_ARCHIVE` for noncurrent versions
+pub fn parse_noncurrent_transitions(input: &str) -> Result<Vec<NoncurrentTransition>, String> {
+    input
+        .split_whitespace()
+        .map(|word| {
+            let (days, storage_class) = split_class(word)?;
+            Ok(NoncurrentTransition {
+                noncurrent_days: days
+                    .parse()
+                    .map_err(|_| format!("'{}' is not a number of days", days))?,
+                newer_noncurrent_versions: None,
+                storage_class,
+            })
+        })
+        .collect()
+}
+
+pub fn format_noncurrent_transitions(transitions: &[NoncurrentTransition]) -> String {
+    let words: Vec<String> = transitions
+        .iter()
+        .map(|t| format!("{}:{}", t.noncurrent_days, t.storage_class))
+        .collect();
+    words.join(" ")
+}
+
+/// Parses `365` (days), `2030-01-01` or `markers` (remove expired delete markers)
+pub fn parse_expiration(input: &str) -> Result<Option<Expiration>, String> {
+    let input = input.trim();
+    if input == "markers" {
+        return Ok(Some(Expiration {
+            expired_object_delete_marker: Some(true),
+            ..Expiration::default()
+        }));
+    }
+    if let Ok(days) = parse_days(input) {
+        return Ok(days.map(|days| Expiration {
+            days: Some(days),
+            ..Expiration::default()
+        }));
+    }
+    parse_lifecycle_date(input)?;
+    Ok(Some(Expiration {
+        date: Some(input.to_string()),
+        ..Expiration::default()
+    }))
+}
+
+pub fn format_expiration(expiration: Option<&Expiration>) -> String {
+    match expiration {
+        Some(Expiration {
+            days: Some(days), ..
+        }) => days.to_string(),
+        Some(Expiration {
+            date: Some(date), ..
+        }) => date.clone(),
+        Some(Expiration {
+            expired_object_delete_marker: Some(true),
+            ..
+        }) => "markers".to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Parses `90` or `90 keep=3` (keep the 3 newest noncurrent versions)
+pub fn parse_noncurrent_expiration(input: &str) -> Result<Option<NoncurrentExpiration>, String> {
+    let mut words = input.split_whitespace();
+    let Some(days) = parse_days(words.next().unwrap_or_default())? else {
+        return Ok(None);
+    };
+    let newer_noncurrent_versions = match words.next() {
+        Some(word) => Some(
+            word.strip_prefix("keep=")
+                .and_then(|n| n.parse().ok())
+                .ok_or_else(|| format!("expected keep=N, got '{}'", word))?,
+        ),
+        None => None,
+    };
+    Ok(Some(NoncurrentExpiration {
+        noncurrent_days: days,
+        newer_noncurrent_versions,
+    }))
+}
+
+pub fn format_noncurrent_expiration(expiration: Option<&NoncurrentExpiration>) -> String {
+    match expiration {
+        Some(NoncurrentExpiration {
+            noncurrent_days,
+            newer_noncurrent_versions: Some(keep),
+        }) => format!("{} keep={}", noncurrent_days, keep),
+        Some(expiration) => expiration.noncurrent_days.to_string(),
+        None => String::new(),
+    }
+}
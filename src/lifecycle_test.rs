@@ -0,0 +1,157 @@
+use crate::aws::s3_client::{rule_from_sdk, rule_to_sdk};
+use crate::lifecycle::{
+    Expiration, LifecycleConfig, LifecycleRule, RuleFilter, RuleTag, Transition, format_expiration,
+    format_noncurrent_expiration, format_transitions, parse_expiration,
+    parse_noncurrent_expiration, parse_tags, parse_transitions,
+};
+use aws_sdk_s3::types::{ExpirationStatus, LifecycleExpiration};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule() -> LifecycleRule {
+        LifecycleRule {
+            id: Some("archive-logs".to_string()),
+            status: "Enabled".to_string(),
+            filter: RuleFilter::new(
+                Some("logs/".to_string()),
+                vec![RuleTag {
+                    key: "tier".to_string(),
+                    value: "cold".to_string(),
+                }],
+                None,
+                None,
+            ),
+            transitions: vec![Transition {
+                days: Some(30),
+                date: None,
+                storage_class: "GLACIER".to_string(),
+            }],
+            expiration: Some(Expiration {
+                days: Some(365),
+                ..Expiration::default()
+            }),
+            ..LifecycleRule::default()
+        }
+    }
+
+    #[test]
+    fn test_describe_rule() {
+        let mut rule = rule();
+        assert!(rule.filter.and.is_some());
+        assert_eq!(
+            rule.describe(),
+            "objects under logs/ tagged tier=cold → GLACIER after 30d, expire after 365d"
+        );
+
+        rule.status = "Disabled".to_string();
+        rule.filter = RuleFilter::default();
+        rule.expiration = None;
+        assert_eq!(
+            rule.describe(),
+            "[disabled] all objects → GLACIER after 30d"
+        );
+    }
+
+    #[test]
+    fn test_validate_rule() {
+        assert_eq!(rule().validate(), Ok(()));
+
+        let mut early = rule();
+        early.transitions[0].storage_class = "STANDARD_IA".to_string();
+        early.transitions[0].days = Some(10);
+        assert!(early.validate().is_err());
+
+        let mut expires_first = rule();
+        expires_first.expiration = Some(Expiration {
+            days: Some(20),
+            ..Expiration::default()
+        });
+        assert_eq!(
+            expires_first.validate().unwrap_err(),
+            "rule archive-logs: objects must expire after their last transition"
+        );
+
+        let mut nothing = rule();
+        nothing.transitions.clear();
+        nothing.expiration = None;
+        assert!(nothing.validate().is_err());
+
+        let mut class = rule();
+        class.transitions[0].storage_class = "STANDARD".to_string();
+        assert!(class.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_json_round_trip() {
+        let config = LifecycleConfig {
+            rules: vec![rule()],
+        };
+        assert_eq!(
+            LifecycleConfig::parse(&config.to_json()),
+            Ok(config.clone())
+        );
+
+        let duplicate = LifecycleConfig {
+            rules: vec![rule(), rule()],
+        };
+        assert!(LifecycleConfig::parse(&duplicate.to_json()).is_err());
+        assert!(LifecycleConfig::parse(r#"{"Rules": [{"Status": "Enabled", "Foo": 1}]}"#).is_err());
+        assert!(
+            LifecycleConfig::parse("{")
+                .unwrap_err()
+                .starts_with("invalid lifecycle JSON")
+        );
+    }
+
+    #[test]
+    fn test_form_fields() {
+        let transitions = parse_transitions("30:standard_ia 2030-01-01:GLACIER").unwrap();
+        assert_eq!(transitions[0].days, Some(30));
+        assert_eq!(transitions[0].storage_class, "STANDARD_IA");
+        assert_eq!(transitions[1].date.as_deref(), Some("2030-01-01"));
+        assert_eq!(
+            format_transitions(&transitions),
+            "30:STANDARD_IA 2030-01-01:GLACIER"
+        );
+        assert!(parse_transitions("soon:GLACIER").is_err());
+
+        assert_eq!(parse_tags("tier=cold, team=ops").unwrap().len(), 2);
+        assert!(parse_tags("tier").is_err());
+
+        let expiration = parse_expiration("markers").unwrap();
+        assert_eq!(format_expiration(expiration.as_ref()), "markers");
+        assert_eq!(parse_expiration("off"), Ok(None));
+
+        let noncurrent = parse_noncurrent_expiration("90 keep=3").unwrap();
+        assert_eq!(
+            format_noncurrent_expiration(noncurrent.as_ref()),
+            "90 keep=3"
+        );
+        assert!(parse_noncurrent_expiration("90 keep").is_err());
+    }
+
+    #[test]
+    fn test_legacy_prefix_round_trip() {
+        #[allow(deprecated)]
+        let legacy = aws_sdk_s3::types::LifecycleRule::builder()
+            .id("tmp")
+            .prefix("tmp/")
+            .status(ExpirationStatus::Enabled)
+            .expiration(LifecycleExpiration::builder().days(1).build())
+            .build()
+            .unwrap();
+
+        let rule = rule_from_sdk(&legacy);
+        assert_eq!(rule.filter.prefix.as_deref(), Some("tmp/"));
+        assert!(!rule.describe().contains("all objects"));
+
+        let written = rule_to_sdk(&rule).unwrap();
+        assert_eq!(
+            written.filter().and_then(|filter| filter.prefix()),
+            Some("tmp/")
+        );
+        assert_eq!(rule_from_sdk(&written), rule);
+    }
+}
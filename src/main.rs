@@ -1,18 +1,70 @@
 use app::App;
+use cli::CliCommand;
 use color_eyre::Result;
 use tracing_subscriber::{self, layer::SubscriberExt, util::SubscriberInitExt};
 use tui_logger::{LevelFilter, init_logger, set_default_level};
 
 mod app;
+mod archive;
+#[cfg(test)]
+mod archive_test;
 mod aws;
+mod bucket;
+#[cfg(test)]
+mod bucket_test;
+mod checksum;
+#[cfg(test)]
+mod checksum_test;
+mod cli;
+#[cfg(test)]
+mod cli_test;
+mod config;
+#[cfg(test)]
+mod config_test;
+mod filter;
+#[cfg(test)]
+mod filter_test;
+mod lifecycle;
+#[cfg(test)]
+mod lifecycle_test;
+mod policy;
+#[cfg(test)]
+mod policy_test;
+mod safety;
+#[cfg(test)]
+mod safety_test;
 mod search;
 #[cfg(test)]
 mod search_test;
+mod sync;
+#[cfg(test)]
+mod sync_test;
+mod throttle;
+#[cfg(test)]
+mod throttle_test;
 mod ui;
+mod usage;
+#[cfg(test)]
+mod usage_test;
 mod util;
+#[cfg(test)]
+mod util_test;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let read_only = match cli::parse_args(&args) {
+        Ok(CliCommand::Tui { read_only }) => read_only,
+        Ok(command) => {
+            color_eyre::install()?;
+            return cli::run(command).await;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
     tracing_subscriber::registry()
         .with(tui_logger::TuiTracingSubscriberLayer)
         .init();
@@ -22,7 +74,7 @@ async fn main() -> Result<()> {
     color_eyre::install()?;
 
     let mut terminal = ratatui::init();
-    let app_result = App::new().await?.run(&mut terminal).await;
+    let app_result = App::new(read_only).await?.run(&mut terminal).await;
     ratatui::restore();
 
     app_result
@@ -0,0 +1,250 @@
+use serde_json::{Map, Value};
+
+const VERSIONS: [&str; 2] = ["2012-10-17", "2008-10-17"];
+const POLICY_KEYS: [&str; 3] = ["Version", "Id", "Statement"];
+const STATEMENT_KEYS: [&str; 9] = [
+    "Sid",
+    "Effect",
+    "Principal",
+    "NotPrincipal",
+    "Action",
+    "NotAction",
+    "Resource",
+    "NotResource",
+    "Condition",
+];
+const PRINCIPAL_TYPES: [&str; 4] = ["AWS", "Service", "Federated", "CanonicalUser"];
+
+/// A bucket policy that parsed and follows the IAM policy grammar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Policy {
+    /// The policy as pretty-printed JSON, keeping the key order it was written in; this is
+    /// also what gets uploaded
+    pub pretty: String,
+    /// Statements that allow anyone, e.g. `"Principal": "*"`
+    pub warnings: Vec<String>,
+}
+
+impl Policy {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let value: Value =
+            serde_json::from_str(text).map_err(|e| format!("invalid JSON: {}", e))?;
+        let warnings = check_policy(&value)?;
+        let pretty = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
+        Ok(Self { pretty, warnings })
+    }
+}
+
+/// A starting point for buckets without a policy
+pub fn policy_template(bucket: &str) -> String {
+    format!(
+        r#"{{
+  "Version": "2012-10-17",
+  "Statement": [
+    {{
+      "Sid": "Example",
+      "Effect": "Allow",
+      "Principal": {{ "AWS": "arn:aws:iam::ACCOUNT_ID:root" }},
+      "Action": "s3:GetObject",
+      "Resource": "arn:aws:s3:::{}/*"
+    }}
+  ]
+}}
+"#,
+        bucket
+    )
+}
+
+fn check_keys(object: &Map<String, Value>, allowed: &[&str], what: &str) -> Result<(), String> {
+    match object.keys().find(|key| !allowed.contains(&key.as_str())) {
+        Some(key) => Err(format!("{}: unknown element \"{}\"", what, key)),
+        None => Ok(()),
+    }
+}
+
+/// A string, or a non-empty array of strings
+fn check_strings(value: &Value, what: &str) -> Result<(), String> {
+    match value {
+        Value::String(_) => Ok(()),
+        Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_string) => Ok(()),
+        _ => Err(format!("{} must be a string or a list of strings", what)),
+    }
+}
+
+/// Whether the principal is `"*"`, or has `"*"` among its AWS principals
+fn is_wildcard(principal: &Value) -> bool {
+    let is_star = |value: &Value| match value {
+        Value::String(s) => s == "*",
+        Value::Array(items) => items.iter().any(|item| item == "*"),
+        _ => false,
+    };
+    match principal {
+        Value::Object(types) => types.get("AWS").is_some_and(is_star),
+        other => is_star(other),
+    }
+}
+
+fn check_principal(principal: &Value, what: &str) -> Result<(), String> {
+    match principal {
+        Value::String(s) if s == "*" => Ok(()),
+        Value::Object(types) if !types.is_empty() => {
+            check_keys(types, &PRINCIPAL_TYPES, what)?;
+            types
+                .iter()
+                .try_for_each(|(kind, value)| check_strings(value, &format!("{} {}", what, kind)))
+        }
+        _ => Err(format!(
+            "{} must be \"*\" or an object of AWS, Service, Federated or CanonicalUser",
+            what
+        )),
+    }
+}
+
+fn check_condition(condition: &Value, what: &str) -> Result<(), String> {
+    let Value::Object(operators) = condition else {
+        return Err(format!("{} must be an object", what));
+    };
+    for (operator, keys) in operators {
+        let Value::Object(keys) = keys else {
+            return Err(format!("{} {} must be an object of keys", what, operator));
+        };
+        for (key, value) in keys {
+            let scalar =
+                |value: &Value| value.is_string() || value.is_boolean() || value.is_number();
+            let valid = match value {
+                Value::Array(values) => !values.is_empty() && values.iter().all(scalar),
+                value => scalar(value),
+            };
+            if !valid {
+                return Err(format!(
+                    "{} {} {} must be a value or a list of values",
+                    what, operator, key
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Exactly one of an element and its `Not` form
+fn one_of<'a>(
+    statement: &'a Map<String, Value>,
+    name: &str,
+    what: &str,
+) -> Result<&'a Value, String> {
+    let not_name = format!("Not{}", name);
+    match (statement.get(name), statement.get(&not_name)) {
+        (Some(value), None) | (None, Some(value)) => Ok(value),
+        (Some(_), Some(_)) => Err(format!("{} has both {} and {}", what, name, not_name)),
+        (None, None) => Err(format!("{} needs {} or {}", what, name, not_name)),
+    }
+}
+
+fn check_statement(index: usize, statement: &Value) -> Result<Option<String>, String> {
+    let Value::Object(statement) = statement else {
+        return Err(format!("Statement {} must be an object", index + 1));
+    };
+    let what = match statement.get("Sid") {
+        Some(Value::String(sid)) => format!("Statement {} ({})", index + 1, sid),
+        Some(_) => return Err(format!("Statement {}: Sid must be a string", index + 1)),
+        None => format!("Statement {}", index + 1),
+    };
+    check_keys(statement, &STATEMENT_KEYS, &what)?;
+
+    let allow = match statement.get("Effect").and_then(Value::as_str) {
+        Some("Allow") => true,
+        Some("Deny") => false,
+        _ => return Err(format!("{}: Effect must be \"Allow\" or \"Deny\"", what)),
+    };
+    let principal = one_of(statement, "Principal", &what)?;
+    check_principal(principal, &format!("{} principal", what))?;
+    check_strings(
+        one_of(statement, "Action", &what)?,
+        &format!("{} action", what),
+    )?;
+    check_strings(
+        one_of(statement, "Resource", &what)?,
+        &format!("{} resource", what),
+    )?;
+    if let Some(condition) = statement.get("Condition") {
+        check_condition(condition, &format!("{} condition", what))?;
+    }
+
+    let anyone = statement.contains_key("Principal") && is_wildcard(principal);
+    Ok((allow && anyone).then(|| {
+        if statement.contains_key("Condition") {
+            format!(
+                "{} allows any principal (\"*\"), limited by its Condition",
+                what
+            )
+        } else {
+            format!("{} allows any principal (\"*\")", what)
+        }
+    }))
+}
+
+/// Checks the policy grammar, returning a warning for each statement open to anyone
+fn check_policy(policy: &Value) -> Result<Vec<String>, String> {
+    let Value::Object(policy) = policy else {
+        return Err("the policy must be a JSON object".to_string());
+    };
+    check_keys(policy, &POLICY_KEYS, "Policy")?;
+    match policy.get("Version") {
+        Some(Value::String(version)) if VERSIONS.contains(&version.as_str()) => {}
+        Some(_) => return Err("Version must be \"2012-10-17\" or \"2008-10-17\"".to_string()),
+        None => return Err("Version is missing, use \"2012-10-17\"".to_string()),
+    }
+    let statements = match policy.get("Statement") {
+        Some(Value::Array(statements)) if !statements.is_empty() => statements.clone(),
+        Some(statement @ Value::Object(_)) => vec![statement.clone()],
+        _ => return Err("Statement must be an object or a non-empty list".to_string()),
+    };
+
+    let mut warnings = Vec::new();
+    for (index, statement) in statements.iter().enumerate() {
+        warnings.extend(check_statement(index, statement)?);
+    }
+    Ok(warnings)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Same,
+    Added,
+    Removed,
+}
+
+/// Line-by-line differences between two texts, in order
+pub fn diff_lines(old: &str, new: &str) -> Vec<(Change, String)> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Longest common subsequence lengths of every pair of suffixes
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((Change::Same, old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lengths[i][j + 1] >= lengths[i + 1][j]) {
+            lines.push((Change::Added, new[j].to_string()));
+            j += 1;
+        } else {
+            lines.push((Change::Removed, old[i].to_string()));
+            i += 1;
+        }
+    }
+    lines
+}
@@ -0,0 +1,97 @@
+use crate::policy::{Change, Policy, diff_lines, policy_template};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_parse_valid() {
+        let policy = Policy::parse(
+            r#"{"Version": "2012-10-17", "Statement": {
+                "Effect": "Deny", "Principal": "*", "Action": ["s3:*"],
+                "Resource": "arn:aws:s3:::b/*",
+                "Condition": {"Bool": {"aws:SecureTransport": false}}}}"#,
+        )
+        .unwrap();
+        assert!(policy.pretty.contains("\n  \"Statement\": {"));
+        // Denying anyone is not a warning
+        assert!(policy.warnings.is_empty());
+
+        // Keys stay in the order they were written, not sorted
+        let effect = policy.pretty.find("\"Effect\"").unwrap();
+        assert!(effect < policy.pretty.find("\"Action\"").unwrap());
+        assert!(policy.pretty.find("\"Version\"").unwrap() < effect);
+
+        let template = Policy::parse(&policy_template("my-bucket")).unwrap();
+        assert!(template.pretty.contains("arn:aws:s3:::my-bucket/*"));
+    }
+
+    #[test]
+    fn test_policy_wildcard_warnings() {
+        let policy = Policy::parse(
+            r#"{"Version": "2012-10-17", "Statement": [
+                {"Sid": "Public", "Effect": "Allow", "Principal": {"AWS": ["*"]},
+                 "Action": "s3:GetObject", "Resource": "arn:aws:s3:::b/*"},
+                {"Effect": "Allow", "Principal": "*", "Action": "s3:GetObject",
+                 "Resource": "arn:aws:s3:::b/*",
+                 "Condition": {"IpAddress": {"aws:SourceIp": "10.0.0.0/8"}}},
+                {"Effect": "Allow", "Principal": {"Service": "logging.s3.amazonaws.com"},
+                 "Action": "s3:PutObject", "Resource": "arn:aws:s3:::b/*"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            policy.warnings,
+            vec![
+                "Statement 1 (Public) allows any principal (\"*\")".to_string(),
+                "Statement 2 allows any principal (\"*\"), limited by its Condition".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_policy_parse_errors() {
+        let statement = |body: &str| {
+            Policy::parse(&format!(
+                r#"{{"Version": "2012-10-17", "Statement": [{}]}}"#,
+                body
+            ))
+        };
+        assert!(Policy::parse("{").unwrap_err().starts_with("invalid JSON"));
+        assert!(Policy::parse(r#"{"Statement": []}"#).is_err());
+        assert!(Policy::parse(r#"{"Version": "2012-10-17", "Statement": []}"#).is_err());
+        assert!(
+            statement(
+                r#"{"Effect": "Permit", "Principal": "*", "Action": "s3:*", "Resource": "*"}"#
+            )
+            .is_err()
+        );
+        assert!(statement(r#"{"Effect": "Allow", "Action": "s3:*", "Resource": "*"}"#).is_err());
+        assert!(statement(r#"{"Effect": "Allow", "Principal": "*", "Action": "s3:*", "NotAction": "s3:Get*", "Resource": "*"}"#).is_err());
+        assert!(statement(r#"{"Effect": "Allow", "Principal": {"User": "x"}, "Action": "s3:*", "Resource": "*"}"#).is_err());
+        assert!(
+            statement(r#"{"Effect": "Allow", "Principal": "*", "Action": [], "Resource": "*"}"#)
+                .is_err()
+        );
+        assert!(statement(r#"{"Effect": "Allow", "Principal": "*", "Action": "s3:*", "Resource": "*", "Condition": {"Bool": true}}"#).is_err());
+        assert_eq!(
+            statement(r#"{"Effect": "Allow", "Principal": "*", "Action": "s3:*", "Resource": "*", "Extra": 1}"#)
+                .unwrap_err(),
+            "Statement 1: unknown element \"Extra\""
+        );
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let diff = diff_lines("a\nb\nc\n", "a\nc\nd\n");
+        assert_eq!(
+            diff,
+            vec![
+                (Change::Same, "a".to_string()),
+                (Change::Removed, "b".to_string()),
+                (Change::Same, "c".to_string()),
+                (Change::Added, "d".to_string()),
+            ]
+        );
+        assert_eq!(diff_lines("", "x"), vec![(Change::Added, "x".to_string())]);
+    }
+}
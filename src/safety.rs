@@ -0,0 +1,88 @@
+use globset::{Glob, GlobMatcher};
+
+/// An action that changes something in S3, checked before it is dispatched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mutation {
+    /// What the action does, e.g. "delete objects"
+    pub description: String,
+    /// The buckets it writes to
+    pub buckets: Vec<String>,
+    /// Deletes or overwrites data, so protected buckets need their name typed first
+    pub destructive: bool,
+}
+
+impl Mutation {
+    pub fn new(description: &str, buckets: Vec<String>, destructive: bool) -> Self {
+        Self {
+            description: description.to_string(),
+            buckets,
+            destructive,
+        }
+    }
+}
+
+/// What to do with a mutation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Allow,
+    /// Refused, with the reason to show
+    Block(String),
+    /// Runs once the name of this bucket is typed
+    Confirm(String),
+}
+
+/// Read-only mode and the protected buckets of the current profile
+#[derive(Debug, Clone, Default)]
+pub struct Safety {
+    pub read_only: bool,
+    protected: Vec<(String, GlobMatcher)>,
+}
+
+impl Safety {
+    /// `patterns` are bucket name globs such as `prod-*`
+    pub fn new(read_only: bool, patterns: &[String]) -> Result<Self, String> {
+        let protected = patterns
+            .iter()
+            .map(|pattern| {
+                Glob::new(pattern)
+                    .map(|glob| (pattern.clone(), glob.compile_matcher()))
+                    .map_err(|e| format!("invalid bucket pattern '{}': {}", pattern, e))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            read_only,
+            protected,
+        })
+    }
+
+    /// The pattern protecting `bucket`, if any
+    pub fn protecting(&self, bucket: &str) -> Option<&str> {
+        self.protected
+            .iter()
+            .find(|(_, matcher)| matcher.is_match(bucket))
+            .map(|(pattern, _)| pattern.as_str())
+    }
+
+    pub fn check(&self, mutation: &Mutation) -> Verdict {
+        if self.read_only {
+            return Verdict::Block(format!(
+                "READ-ONLY MODE: cannot {}; restart without --read-only (and without \"read_only\" in the config) to make changes",
+                mutation.description
+            ));
+        }
+        if mutation.destructive
+            && let Some(bucket) = mutation
+                .buckets
+                .iter()
+                .find(|bucket| self.protecting(bucket).is_some())
+        {
+            return Verdict::Confirm(bucket.clone());
+        }
+        Verdict::Allow
+    }
+}
+
+/// Whether `typed` is the name of the protected bucket
+pub fn confirms(bucket: &str, typed: &str) -> bool {
+    typed.trim() == bucket
+}
@@ -0,0 +1,58 @@
+use crate::safety::{Mutation, Safety, Verdict, confirms};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|p| p.to_string()).collect()
+    }
+
+    fn delete_in(bucket: &str) -> Mutation {
+        Mutation::new("delete objects", vec![bucket.to_string()], true)
+    }
+
+    #[test]
+    fn test_read_only_blocks_everything() {
+        let safety = Safety::new(true, &[]).unwrap();
+        assert!(matches!(
+            safety.check(&delete_in("scratch")),
+            Verdict::Block(_)
+        ));
+        let create = Mutation::new("create a bucket", Vec::new(), false);
+        assert!(
+            matches!(safety.check(&create), Verdict::Block(reason) if reason.contains("create a bucket"))
+        );
+    }
+
+    #[test]
+    fn test_protected_buckets_need_confirmation() {
+        let safety = Safety::new(false, &patterns(&["prod-*", "billing"])).unwrap();
+        assert_eq!(safety.protecting("prod-data"), Some("prod-*"));
+        assert_eq!(safety.protecting("staging-data"), None);
+        assert_eq!(
+            safety.check(&delete_in("prod-data")),
+            Verdict::Confirm("prod-data".to_string())
+        );
+        assert_eq!(safety.check(&delete_in("scratch")), Verdict::Allow);
+
+        // Tagging is not destructive, and a move is checked against both buckets
+        let tags = Mutation::new("edit tags", vec!["billing".to_string()], false);
+        assert_eq!(safety.check(&tags), Verdict::Allow);
+        let buckets = vec!["scratch".to_string(), "billing".to_string()];
+        let paste = Mutation::new("move objects here", buckets, true);
+        assert_eq!(
+            safety.check(&paste),
+            Verdict::Confirm("billing".to_string())
+        );
+
+        assert!(Safety::new(false, &patterns(&["prod-[a"])).is_err());
+    }
+
+    #[test]
+    fn test_confirms() {
+        assert!(confirms("prod-data", " prod-data "));
+        assert!(!confirms("prod-data", "prod-*"));
+        assert!(!confirms("prod-data", ""));
+    }
+}
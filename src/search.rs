@@ -1,11 +1,135 @@
 use fuzzy_matcher::FuzzyMatcher;
 use fuzzy_matcher::skim::SkimMatcherV2;
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
+use std::fmt;
+
+/// How the search query is matched against item names
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    Fuzzy,
+    Substring,
+    Exact,
+    Glob,
+    Regex,
+}
+
+impl SearchMode {
+    /// Next mode when cycling with the toggle key
+    pub fn next(self) -> Self {
+        match self {
+            Self::Fuzzy => Self::Substring,
+            Self::Substring => Self::Exact,
+            Self::Exact => Self::Glob,
+            Self::Glob => Self::Regex,
+            Self::Regex => Self::Fuzzy,
+        }
+    }
+
+    /// Splits a leading mode sigil off the query.
+    /// `'` substring, `=` exact, `~` glob, `%` regex; anything else uses `default`.
+    /// A doubled sigil stands for the character itself, e.g. `''s` searches for `'s`.
+    pub fn from_sigil(query: &str, default: SearchMode) -> (SearchMode, &str) {
+        let mut chars = query.chars();
+        let Some(sigil) = chars.next() else {
+            return (default, query);
+        };
+        let mode = match sigil {
+            '\'' => Self::Substring,
+            '=' => Self::Exact,
+            '~' => Self::Glob,
+            '%' => Self::Regex,
+            _ => return (default, query),
+        };
+        let rest = chars.as_str();
+        if rest.starts_with(sigil) {
+            return (default, rest);
+        }
+        (mode, rest)
+    }
+}
+
+impl fmt::Display for SearchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Fuzzy => "fuzzy",
+            Self::Substring => "substring",
+            Self::Exact => "exact",
+            Self::Glob => "glob",
+            Self::Regex => "regex",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A compiled query, rebuilt whenever the query or mode changes
+enum Matcher {
+    All,
+    Fuzzy(Box<SkimMatcherV2>, String),
+    Substring(String),
+    Exact(String),
+    Glob(GlobMatcher),
+    Regex(Regex),
+}
+
+impl fmt::Debug for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::All => write!(f, "All"),
+            Self::Fuzzy(_, pattern) => write!(f, "Fuzzy({:?})", pattern),
+            Self::Substring(pattern) => write!(f, "Substring({:?})", pattern),
+            Self::Exact(pattern) => write!(f, "Exact({:?})", pattern),
+            Self::Glob(glob) => write!(f, "Glob({:?})", glob.glob().glob()),
+            Self::Regex(regex) => write!(f, "Regex({:?})", regex.as_str()),
+        }
+    }
+}
+
+impl Matcher {
+    fn compile(mode: SearchMode, pattern: &str) -> Result<Self, String> {
+        if pattern.is_empty() {
+            return Ok(Self::All);
+        }
+
+        match mode {
+            SearchMode::Fuzzy => Ok(Self::Fuzzy(Box::default(), pattern.to_string())),
+            SearchMode::Substring => Ok(Self::Substring(pattern.to_lowercase())),
+            SearchMode::Exact => Ok(Self::Exact(pattern.to_string())),
+            SearchMode::Glob => GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .map(|glob| Self::Glob(glob.compile_matcher()))
+                .map_err(|e| format!("invalid glob: {}", e)),
+            SearchMode::Regex => Regex::new(pattern)
+                .map(Self::Regex)
+                .map_err(|e| format!("invalid regex: {}", e)),
+        }
+    }
+
+    fn score(&self, item: &str) -> Option<i64> {
+        match self {
+            Self::All => Some(0),
+            Self::Fuzzy(matcher, pattern) => matcher.fuzzy_match(item, pattern),
+            Self::Substring(pattern) => item.to_lowercase().contains(pattern).then_some(0),
+            Self::Exact(pattern) => (item == pattern).then_some(0),
+            Self::Glob(glob) => glob.is_match(item).then_some(0),
+            Self::Regex(regex) => regex.is_match(item).then_some(0),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct SearchBar {
     pub query: String,
     pub active: bool,
     pub cursor_position: usize,
+    /// Order filtered results by match score instead of keeping the original order
+    pub rank_by_score: bool,
+    /// Mode used when the query has no sigil
+    pub default_mode: SearchMode,
+    /// Set when the current query is not a valid pattern for its mode
+    pub error: Option<String>,
+    matcher: Matcher,
 }
 
 impl Default for SearchBar {
@@ -14,6 +138,10 @@ impl Default for SearchBar {
             query: String::new(),
             active: false,
             cursor_position: 0,
+            rank_by_score: true,
+            default_mode: SearchMode::Fuzzy,
+            error: None,
+            matcher: Matcher::All,
         }
     }
 }
@@ -24,13 +152,24 @@ impl SearchBar {
         if !self.active {
             self.query.clear();
             self.cursor_position = 0;
+            self.recompile();
         }
     }
 
+    pub fn toggle_ranking(&mut self) {
+        self.rank_by_score = !self.rank_by_score;
+    }
+
+    pub fn cycle_mode(&mut self) {
+        self.default_mode = self.default_mode.next();
+        self.recompile();
+    }
+
     pub fn input(&mut self, c: char) {
         if self.active {
             self.query.insert(self.cursor_position, c);
             self.cursor_position += 1;
+            self.recompile();
         }
     }
 
@@ -38,20 +177,64 @@ impl SearchBar {
         if self.active && self.cursor_position > 0 {
             self.cursor_position -= 1;
             self.query.remove(self.cursor_position);
+            self.recompile();
         }
     }
 
     pub fn clear(&mut self) {
         self.query.clear();
         self.cursor_position = 0;
+        self.recompile();
     }
 
-    pub fn matches(&self, item: &str) -> bool {
-        if self.query.is_empty() {
-            return true;
+    /// Replaces the query, e.g. to pre-fill a prompt, and moves the cursor to the end
+    pub fn set_query(&mut self, query: &str) {
+        self.query = query.to_string();
+        self.cursor_position = self.query.len();
+        self.recompile();
+    }
+
+    /// A literal key prefix implied by the query that can narrow a listing, if any
+    pub fn listing_prefix(&self) -> Option<String> {
+        let (mode, pattern) = self.mode_and_pattern();
+        let prefix = match mode {
+            SearchMode::Exact => pattern,
+            // Everything up to the first glob metacharacter
+            SearchMode::Glob => pattern
+                .find(['*', '?', '[', '{', '\\'])
+                .map_or(pattern, |end| &pattern[..end]),
+            _ => "",
+        };
+        (!prefix.is_empty()).then(|| prefix.to_string())
+    }
+
+    /// The effective mode and pattern, after any sigil is applied
+    pub fn mode_and_pattern(&self) -> (SearchMode, &str) {
+        SearchMode::from_sigil(&self.query, self.default_mode)
+    }
+
+    fn recompile(&mut self) {
+        let (mode, pattern) = self.mode_and_pattern();
+        match Matcher::compile(mode, pattern) {
+            Ok(matcher) => {
+                self.matcher = matcher;
+                self.error = None;
+            }
+            Err(e) => {
+                // Leave the list unfiltered until the pattern is valid again
+                self.matcher = Matcher::All;
+                self.error = Some(e);
+            }
         }
+    }
+
+    pub fn matches(&self, item: &str) -> bool {
+        self.score(item).is_some()
+    }
 
-        let matcher = SkimMatcherV2::default();
-        matcher.fuzzy_match(item, &self.query).is_some()
+    /// Returns the match score for `item`, or `None` if it does not match.
+    /// Only fuzzy matching produces meaningful scores; the other modes score every match as 0.
+    pub fn score(&self, item: &str) -> Option<i64> {
+        self.matcher.score(item)
     }
 }
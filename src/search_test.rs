@@ -1,4 +1,5 @@
-use crate::search::SearchBar;
+use crate::search::{SearchBar, SearchMode};
+use crate::ui::ScrollableList;
 
 #[cfg(test)]
 mod tests {
@@ -8,7 +9,7 @@ mod tests {
     fn test_search_bar_default() {
         let search_bar = SearchBar::default();
         assert_eq!(search_bar.query, "");
-        assert_eq!(search_bar.active, false);
+        assert!(!search_bar.active);
         assert_eq!(search_bar.cursor_position, 0);
     }
 
@@ -16,9 +17,9 @@ mod tests {
     fn test_search_bar_toggle() {
         let mut search_bar = SearchBar::default();
         search_bar.toggle();
-        assert_eq!(search_bar.active, true);
+        assert!(search_bar.active);
         search_bar.toggle();
-        assert_eq!(search_bar.active, false);
+        assert!(!search_bar.active);
         assert_eq!(search_bar.query, "");
         assert_eq!(search_bar.cursor_position, 0);
     }
@@ -66,19 +67,230 @@ mod tests {
         search_bar.toggle();
 
         // Empty query matches everything
-        assert_eq!(search_bar.matches("test"), true);
+        assert!(search_bar.matches("test"));
 
         // Exact match
         search_bar.input('t');
         search_bar.input('e');
         search_bar.input('s');
         search_bar.input('t');
-        assert_eq!(search_bar.matches("test"), true);
+        assert!(search_bar.matches("test"));
 
         // Fuzzy match
-        assert_eq!(search_bar.matches("testing"), true);
+        assert!(search_bar.matches("testing"));
 
         // No match
-        assert_eq!(search_bar.matches("foo"), false);
+        assert!(!search_bar.matches("foo"));
+    }
+
+    #[test]
+    fn test_search_bar_score() {
+        let mut search_bar = SearchBar::default();
+        search_bar.toggle();
+        assert_eq!(search_bar.score("anything"), Some(0));
+
+        search_bar.input('l');
+        search_bar.input('o');
+        search_bar.input('g');
+        let exact = search_bar.score("log").unwrap();
+        let scattered = search_bar.score("l_o_g").unwrap();
+        assert!(exact > scattered);
+        assert_eq!(search_bar.score("xyz"), None);
+    }
+
+    fn list_of(items: &[&str]) -> ScrollableList<String> {
+        let mut list = ScrollableList::new("test")
+            .with_items(items.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+        list.filtered_indices = (0..list.items.len()).collect();
+        list.first();
+        list
+    }
+
+    #[test]
+    fn test_apply_search_ranks_by_score() {
+        let mut list = list_of(&["l_o_g_s", "other", "logs", "l_o_g"]);
+        let mut search_bar = SearchBar::default();
+        search_bar.toggle();
+        for c in "log".chars() {
+            search_bar.input(c);
+        }
+
+        list.apply_search(&search_bar, |s| s.clone());
+        assert_eq!(list.filtered_indices[0], 2);
+        assert_eq!(list.filtered_indices.len(), 3);
+        assert!(!list.filtered_indices.contains(&1));
+        // The best match is selected
+        assert_eq!(list.selected_index(), Some(2));
+    }
+
+    #[test]
+    fn test_apply_search_ties_keep_original_order() {
+        let mut list = list_of(&["b/log", "a/log", "c/log"]);
+        let mut search_bar = SearchBar::default();
+        search_bar.toggle();
+        for c in "log".chars() {
+            search_bar.input(c);
+        }
+
+        list.apply_search(&search_bar, |s| s.clone());
+        assert_eq!(list.filtered_indices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_apply_search_stable_order_when_ranking_disabled() {
+        let mut list = list_of(&["l_o_g_s", "other", "logs"]);
+        let mut search_bar = SearchBar::default();
+        search_bar.toggle();
+        search_bar.toggle_ranking();
+        for c in "log".chars() {
+            search_bar.input(c);
+        }
+
+        list.apply_search(&search_bar, |s| s.clone());
+        assert_eq!(list.filtered_indices, vec![0, 2]);
+        assert_eq!(list.selected_index(), Some(0));
+    }
+
+    fn search_bar_with(query: &str) -> SearchBar {
+        let mut search_bar = SearchBar::default();
+        search_bar.toggle();
+        for c in query.chars() {
+            search_bar.input(c);
+        }
+        search_bar
+    }
+
+    #[test]
+    fn test_sigil_selects_mode() {
+        assert_eq!(
+            SearchMode::from_sigil("'abc", SearchMode::Fuzzy),
+            (SearchMode::Substring, "abc")
+        );
+        assert_eq!(
+            SearchMode::from_sigil("=abc", SearchMode::Fuzzy),
+            (SearchMode::Exact, "abc")
+        );
+        assert_eq!(
+            SearchMode::from_sigil("~*.csv", SearchMode::Fuzzy),
+            (SearchMode::Glob, "*.csv")
+        );
+        assert_eq!(
+            SearchMode::from_sigil("%^a+$", SearchMode::Fuzzy),
+            (SearchMode::Regex, "^a+$")
+        );
+        assert_eq!(
+            SearchMode::from_sigil("abc", SearchMode::Glob),
+            (SearchMode::Glob, "abc")
+        );
+    }
+
+    #[test]
+    fn test_doubled_sigil_is_literal() {
+        assert_eq!(
+            SearchMode::from_sigil("''s notes", SearchMode::Fuzzy),
+            (SearchMode::Fuzzy, "'s notes")
+        );
+        assert_eq!(
+            SearchMode::from_sigil("%%20", SearchMode::Substring),
+            (SearchMode::Substring, "%20")
+        );
+        assert_eq!(
+            SearchMode::from_sigil("==", SearchMode::Fuzzy),
+            (SearchMode::Fuzzy, "=")
+        );
+        // Only the leading sigil counts, so a mode can still match its own character
+        assert_eq!(
+            SearchMode::from_sigil("'a''b", SearchMode::Fuzzy),
+            (SearchMode::Substring, "a''b")
+        );
+        assert!(search_bar_with("==v2").matches("config=v2.json"));
+    }
+
+    #[test]
+    fn test_substring_matcher() {
+        let search_bar = search_bar_with("'Host-A");
+        assert!(search_bar.matches("logs/2024/10/01/host-a/app.log"));
+        assert!(!search_bar.matches("logs/2024/10/01/host-b/app.log"));
+        // Fuzzy would accept scattered characters; substring does not
+        assert!(!search_bar.matches("h_o_s_t-a"));
+    }
+
+    #[test]
+    fn test_exact_matcher_is_case_sensitive() {
+        let search_bar = search_bar_with("=logs/app.log");
+        assert!(search_bar.matches("logs/app.log"));
+        assert!(!search_bar.matches("logs/App.log"));
+        assert!(!search_bar.matches("logs/app.log.1"));
+    }
+
+    #[test]
+    fn test_glob_matcher() {
+        let search_bar = search_bar_with("~**/*.parquet");
+        assert!(search_bar.matches("data/2024/10/part-0.parquet"));
+        assert!(search_bar.matches("part-0.parquet"));
+        assert!(!search_bar.matches("data/2024/10/part-0.csv"));
+
+        // A single `*` does not cross `/`
+        let search_bar = search_bar_with("~logs/*.log");
+        assert!(search_bar.matches("logs/app.log"));
+        assert!(!search_bar.matches("logs/host-a/app.log"));
+    }
+
+    #[test]
+    fn test_regex_matcher() {
+        let search_bar = search_bar_with("%host-[ab]/.*\\.log$");
+        assert!(search_bar.matches("logs/host-a/app.log"));
+        assert!(search_bar.matches("logs/host-b/app.log"));
+        assert!(!search_bar.matches("logs/host-c/app.log"));
+        assert!(!search_bar.matches("logs/host-a/app.log.gz"));
+    }
+
+    #[test]
+    fn test_invalid_pattern_reports_error() {
+        let mut search_bar = search_bar_with("%host-(");
+        assert!(search_bar.error.is_some());
+        // An invalid pattern leaves the list unfiltered
+        assert!(search_bar.matches("anything"));
+
+        search_bar.delete();
+        assert!(search_bar.error.is_none());
+
+        let search_bar = search_bar_with("~[a-");
+        assert!(search_bar.error.is_some());
+    }
+
+    #[test]
+    fn test_cycle_mode_changes_default() {
+        let mut search_bar = search_bar_with("a.c");
+        assert!(search_bar.matches("a-.-c"));
+        assert!(!search_bar.matches("abc"));
+
+        search_bar.cycle_mode(); // substring
+        assert!(!search_bar.matches("abc"));
+        assert!(search_bar.matches("xa.cx"));
+
+        search_bar.cycle_mode(); // exact
+        search_bar.cycle_mode(); // glob
+        search_bar.cycle_mode(); // regex
+        assert_eq!(search_bar.default_mode, SearchMode::Regex);
+        assert!(search_bar.matches("abc"));
+
+        search_bar.cycle_mode();
+        assert_eq!(search_bar.default_mode, SearchMode::Fuzzy);
+    }
+
+    #[test]
+    fn test_listing_prefix() {
+        assert_eq!(search_bar_with("logs/").listing_prefix(), None);
+        assert_eq!(
+            search_bar_with("=logs/app.log").listing_prefix(),
+            Some("logs/app.log".to_string())
+        );
+        assert_eq!(
+            search_bar_with("~logs/2024/**/*.csv").listing_prefix(),
+            Some("logs/2024/".to_string())
+        );
+        assert_eq!(search_bar_with("~**/*.csv").listing_prefix(), None);
+        assert_eq!(search_bar_with("%^logs/").listing_prefix(), None);
     }
 }
@@ -0,0 +1,408 @@
+use crate::aws::s3_client::{S3Client, TransferProgress};
+use crate::util::{expand_tilde, local_path_for};
+use aws_sdk_s3::types::Object;
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use globset::{GlobBuilder, GlobMatcher};
+use md5::{Digest, Md5};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Which side is copied onto the other
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncDirection {
+    /// Local directory to S3 prefix
+    Upload,
+    /// S3 prefix to local directory
+    Download,
+}
+
+/// Include/exclude globs matched against paths relative to the synced folder. Everything is
+/// included by default, and later rules take precedence, as with `aws s3 sync`.
+#[derive(Debug, Clone, Default)]
+pub struct SyncFilter {
+    rules: Vec<(bool, GlobMatcher)>,
+}
+
+impl SyncFilter {
+    pub fn add(&mut self, pattern: &str, include: bool) -> Result<(), String> {
+        // `*` crosses `/`, so `*.tmp` excludes temp files at any depth
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(false)
+            .build()
+            .map_err(|e| format!("invalid glob '{}': {}", pattern, e))?;
+        self.rules.push((include, glob.compile_matcher()));
+        Ok(())
+    }
+
+    pub fn is_included(&self, relative: &str) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(_, glob)| glob.is_match(relative))
+            .is_none_or(|(include, _)| *include)
+    }
+}
+
+/// Options shared by the sync prompt and the `sync` subcommand
+#[derive(Debug, Clone)]
+pub struct SyncOptions {
+    pub direction: SyncDirection,
+    pub local_dir: PathBuf,
+    /// Delete files on the destination side that the source does not have
+    pub delete: bool,
+    pub dry_run: bool,
+    pub filter: SyncFilter,
+}
+
+impl SyncOptions {
+    /// Parses `--delete`, `--dry-run`, `--exclude GLOB` and `--include GLOB`
+    pub fn parse_flags<'a>(
+        direction: SyncDirection,
+        local_dir: &str,
+        flags: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, String> {
+        let mut options = Self {
+            direction,
+            local_dir: expand_tilde(local_dir),
+            delete: false,
+            dry_run: false,
+            filter: SyncFilter::default(),
+        };
+        let mut flags = flags.into_iter();
+        while let Some(flag) = flags.next() {
+            match flag {
+                "--delete" => options.delete = true,
+                "--dry-run" => options.dry_run = true,
+                "--exclude" | "--include" => {
+                    let pattern = flags
+                        .next()
+                        .ok_or_else(|| format!("{} needs a glob", flag))?;
+                    options.filter.add(pattern, flag == "--include")?;
+                }
+                other => return Err(format!("unknown sync option '{}'", other)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// A file in the local directory
+#[derive(Debug, Clone)]
+pub struct LocalFile {
+    /// Path relative to the synced directory, with `/` separators
+    pub relative: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// An object under the synced prefix
+#[derive(Debug, Clone)]
+pub struct RemoteFile {
+    pub relative: String,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub etag: Option<String>,
+}
+
+impl RemoteFile {
+    /// Objects under `folder`, skipping zero-byte folder markers
+    pub fn from_objects(objects: &[Object], folder: &str) -> Vec<Self> {
+        objects
+            .iter()
+            .filter_map(|object| {
+                let relative = object.key()?.strip_prefix(folder)?;
+                (!relative.is_empty() && !relative.ends_with('/')).then(|| Self {
+                    relative: relative.to_string(),
+                    size: object.size().unwrap_or(0).max(0) as u64,
+                    modified: object
+                        .last_modified()
+                        .and_then(|time| SystemTime::try_from(*time).ok()),
+                    etag: object
+                        .e_tag()
+                        .map(|etag| etag.trim_matches('"').to_string()),
+                })
+            })
+            .collect()
+    }
+
+    /// The ETag is the MD5 of the content only for single-part uploads without SSE-KMS;
+    /// multipart ETags contain a `-`
+    fn content_md5(&self) -> Option<&str> {
+        self.etag
+            .as_deref()
+            .filter(|etag| etag.len() == 32 && etag.chars().all(|c| c.is_ascii_hexdigit()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncActionKind {
+    Upload,
+    Download,
+    DeleteLocal,
+    DeleteRemote,
+}
+
+impl fmt::Display for SyncActionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Upload => write!(f, "upload"),
+            Self::Download => write!(f, "download"),
+            Self::DeleteLocal => write!(f, "delete local"),
+            Self::DeleteRemote => write!(f, "delete s3"),
+        }
+    }
+}
+
+impl SyncActionKind {
+    /// Whether the action copies data, rather than deleting it
+    pub fn transfers(self) -> bool {
+        matches!(self, Self::Upload | Self::Download)
+    }
+}
+
+/// One step of a sync plan
+#[derive(Debug, Clone)]
+pub struct SyncAction {
+    pub kind: SyncActionKind,
+    pub relative: String,
+    pub size: u64,
+    /// Last modified time of the object, given to downloaded files
+    pub remote_modified: Option<SystemTime>,
+}
+
+/// Whether the source copy differs from the destination copy: by size, then by MD5 where the
+/// ETag is one, and otherwise by the source being newer
+fn changed(
+    local: &LocalFile,
+    remote: &RemoteFile,
+    direction: SyncDirection,
+    local_md5: &dyn Fn(&LocalFile) -> Option<String>,
+) -> bool {
+    if local.size != remote.size {
+        return true;
+    }
+    if let Some(etag) = remote.content_md5()
+        && let Some(md5) = local_md5(local)
+    {
+        return !md5.eq_ignore_ascii_case(etag);
+    }
+    match direction {
+        SyncDirection::Upload => local.modified > remote.modified,
+        SyncDirection::Download => remote.modified > local.modified,
+    }
+}
+
+/// The uploads or downloads (and, with `delete`, deletions) that make the destination match
+/// the source
+pub fn plan(
+    options: &SyncOptions,
+    local: &[LocalFile],
+    remote: &[RemoteFile],
+    local_md5: &dyn Fn(&LocalFile) -> Option<String>,
+) -> Vec<SyncAction> {
+    let local_by_path: HashMap<&str, &LocalFile> =
+        local.iter().map(|f| (f.relative.as_str(), f)).collect();
+    let remote_by_path: HashMap<&str, &RemoteFile> =
+        remote.iter().map(|f| (f.relative.as_str(), f)).collect();
+    let filter = &options.filter;
+
+    let mut actions = Vec::new();
+    match options.direction {
+        SyncDirection::Upload => {
+            for file in local.iter().filter(|f| filter.is_included(&f.relative)) {
+                let needed = match remote_by_path.get(file.relative.as_str()) {
+                    Some(existing) => changed(file, existing, options.direction, local_md5),
+                    None => true,
+                };
+                if needed {
+                    actions.push(SyncAction {
+                        kind: SyncActionKind::Upload,
+                        relative: file.relative.clone(),
+                        size: file.size,
+                        remote_modified: None,
+                    });
+                }
+            }
+            if options.delete {
+                for file in remote.iter().filter(|f| filter.is_included(&f.relative)) {
+                    if !local_by_path.contains_key(file.relative.as_str()) {
+                        actions.push(SyncAction {
+                            kind: SyncActionKind::DeleteRemote,
+                            relative: file.relative.clone(),
+                            size: file.size,
+                            remote_modified: file.modified,
+                        });
+                    }
+                }
+            }
+        }
+        SyncDirection::Download => {
+            for file in remote.iter().filter(|f| filter.is_included(&f.relative)) {
+                let needed = match local_by_path.get(file.relative.as_str()) {
+                    Some(existing) => changed(existing, file, options.direction, local_md5),
+                    None => true,
+                };
+                if needed {
+                    actions.push(SyncAction {
+                        kind: SyncActionKind::Download,
+                        relative: file.relative.clone(),
+                        size: file.size,
+                        remote_modified: file.modified,
+                    });
+                }
+            }
+            if options.delete {
+                for file in local.iter().filter(|f| filter.is_included(&f.relative)) {
+                    if !remote_by_path.contains_key(file.relative.as_str()) {
+                        actions.push(SyncAction {
+                            kind: SyncActionKind::DeleteLocal,
+                            relative: file.relative.clone(),
+                            size: file.size,
+                            remote_modified: None,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    actions.sort_by(|a, b| a.relative.cmp(&b.relative));
+    actions
+}
+
+/// Every regular file under `dir`, with paths relative to it. A missing directory is empty.
+pub fn scan_local(dir: &Path) -> std::io::Result<Vec<LocalFile>> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+
+    let mut pending = vec![(dir.to_path_buf(), String::new())];
+    while let Some((current, relative_dir)) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let entry = entry?;
+            let Ok(name) = entry.file_name().into_string() else {
+                continue;
+            };
+            let relative = format!("{}{}", relative_dir, name);
+            // Symlinks are not followed, so loops and files outside the directory are skipped
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push((entry.path(), format!("{}/", relative)));
+            } else if file_type.is_file() {
+                let metadata = entry.metadata()?;
+                files.push(LocalFile {
+                    relative,
+                    path: entry.path(),
+                    size: metadata.len(),
+                    modified: metadata.modified().ok(),
+                });
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Hex MD5 of a file's content, or `None` if it cannot be read
+pub fn file_md5(path: &Path) -> Option<String> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = Md5::new();
+    let mut buffer = vec![0; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer).ok()?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+/// The prefix as a folder, so `data/2024` syncs `data/2024/...` and not `data/2024-old/...`
+pub fn folder_prefix(prefix: &str) -> String {
+    if prefix.is_empty() || prefix.ends_with('/') {
+        prefix.to_string()
+    } else {
+        format!("{}/", prefix)
+    }
+}
+
+/// Where a sync runs: the bucket and folder prefix on one side, the directory on the other
+#[derive(Debug, Clone)]
+pub struct SyncTarget {
+    pub bucket: String,
+    pub folder: String,
+    pub local_dir: PathBuf,
+}
+
+impl SyncTarget {
+    /// List both sides and work out what needs to change
+    pub async fn plan(
+        &self,
+        s3_client: &S3Client,
+        options: &SyncOptions,
+    ) -> Result<Vec<SyncAction>> {
+        // A missing source would look empty, and `--delete` would then remove everything
+        if options.direction == SyncDirection::Upload && !self.local_dir.is_dir() {
+            return Err(eyre!("{} is not a directory", self.local_dir.display()));
+        }
+        let objects = s3_client
+            .list_all_objects(&self.bucket, &self.folder)
+            .await?;
+        let remote = RemoteFile::from_objects(&objects, &self.folder);
+        let local_dir = self.local_dir.clone();
+        let options = options.clone();
+        // Hashing local files is slow for big trees, so keep it off the async workers
+        tokio::task::spawn_blocking(move || {
+            let local = scan_local(&local_dir)
+                .map_err(|e| eyre!("Failed to read {}: {}", local_dir.display(), e))?;
+            Ok(plan(&options, &local, &remote, &|file| {
+                file_md5(&file.path)
+            }))
+        })
+        .await?
+    }
+
+    pub async fn run<P: TransferProgress>(
+        &self,
+        s3_client: &S3Client,
+        action: &SyncAction,
+        progress: &P,
+    ) -> Result<()> {
+        let key = format!("{}{}", self.folder, action.relative);
+        let path = local_path_for(&self.local_dir, &action.relative)
+            .ok_or_else(|| eyre!("{} is not a safe local path", action.relative))?;
+
+        match action.kind {
+            SyncActionKind::Upload => {
+                s3_client
+                    .upload_file(&self.bucket, &key, &path, progress)
+                    .await
+            }
+            SyncActionKind::Download => {
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                s3_client
+                    .download_to_file(&self.bucket, &key, &path, progress)
+                    .await?;
+                // Matching the object's time keeps the next sync from downloading it again
+                if let Some(modified) = action.remote_modified {
+                    fs::File::options()
+                        .write(true)
+                        .open(&path)
+                        .and_then(|file| file.set_modified(modified))?;
+                }
+                Ok(())
+            }
+            SyncActionKind::DeleteLocal => Ok(tokio::fs::remove_file(&path).await?),
+            SyncActionKind::DeleteRemote => s3_client.delete_object(&self.bucket, &key).await,
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use crate::aws::s3_client::S3Client;
+use crate::sync::{
+    LocalFile, RemoteFile, SyncActionKind, SyncDirection, SyncFilter, SyncOptions, SyncTarget,
+    folder_prefix, plan, scan_local,
+};
+use aws_config::BehaviorVersion;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    fn at(seconds: u64) -> Option<SystemTime> {
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+    }
+
+    fn local(relative: &str, size: u64, modified: u64) -> LocalFile {
+        LocalFile {
+            relative: relative.to_string(),
+            path: PathBuf::from(relative),
+            size,
+            modified: at(modified),
+        }
+    }
+
+    fn remote(relative: &str, size: u64, modified: u64, etag: &str) -> RemoteFile {
+        RemoteFile {
+            relative: relative.to_string(),
+            size,
+            modified: at(modified),
+            etag: Some(etag.to_string()),
+        }
+    }
+
+    fn options(direction: SyncDirection, delete: bool) -> SyncOptions {
+        SyncOptions::parse_flags(direction, "/tmp/x", []).map_or_else(
+            |e| panic!("{}", e),
+            |mut options| {
+                options.delete = delete;
+                options
+            },
+        )
+    }
+
+    fn summary(actions: &[crate::sync::SyncAction]) -> Vec<(SyncActionKind, &str)> {
+        actions
+            .iter()
+            .map(|action| (action.kind, action.relative.as_str()))
+            .collect()
+    }
+
+    const MD5_A: &str = "0cc175b9c0f1b6a831c399e269772661";
+    const MULTIPART: &str = "9b2cf535f27731c974343645a3985328-3";
+
+    #[test]
+    fn test_sync_filter() {
+        let mut filter = SyncFilter::default();
+        assert!(filter.is_included("anything"));
+
+        filter.add("*.tmp", false).unwrap();
+        filter.add("keep/*.tmp", true).unwrap();
+        assert!(!filter.is_included("a/b/c.tmp"));
+        assert!(filter.is_included("keep/c.tmp"));
+        assert!(filter.is_included("c.txt"));
+        assert!(filter.add("[", false).is_err());
+    }
+
+    #[test]
+    fn test_plan_upload() {
+        let locals = [
+            local("new.txt", 1, 100),
+            local("resized.txt", 2, 100),
+            local("same_md5.txt", 1, 900),
+            local("edited_md5.txt", 1, 100),
+            local("big_newer.bin", 5, 900),
+            local("big_older.bin", 5, 100),
+        ];
+        let remotes = [
+            remote("resized.txt", 1, 500, MD5_A),
+            remote("same_md5.txt", 1, 500, MD5_A),
+            remote("edited_md5.txt", 1, 500, MD5_A),
+            remote("big_newer.bin", 5, 500, MULTIPART),
+            remote("big_older.bin", 5, 500, MULTIPART),
+            remote("gone.txt", 1, 500, MD5_A),
+        ];
+        let md5 = |file: &LocalFile| {
+            Some(match file.relative.as_str() {
+                "edited_md5.txt" => "ffffffffffffffffffffffffffffffff".to_string(),
+                _ => MD5_A.to_string(),
+            })
+        };
+
+        let actions = plan(
+            &options(SyncDirection::Upload, false),
+            &locals,
+            &remotes,
+            &md5,
+        );
+        assert_eq!(
+            summary(&actions),
+            vec![
+                (SyncActionKind::Upload, "big_newer.bin"),
+                (SyncActionKind::Upload, "edited_md5.txt"),
+                (SyncActionKind::Upload, "new.txt"),
+                (SyncActionKind::Upload, "resized.txt"),
+            ]
+        );
+
+        let actions = plan(
+            &options(SyncDirection::Upload, true),
+            &locals,
+            &remotes,
+            &md5,
+        );
+        assert!(summary(&actions).contains(&(SyncActionKind::DeleteRemote, "gone.txt")));
+    }
+
+    #[test]
+    fn test_plan_download() {
+        let locals = [local("old.bin", 5, 100), local("extra.txt", 1, 100)];
+        let remotes = [
+            remote("old.bin", 5, 500, MULTIPART),
+            remote("new/file.txt", 1, 500, MD5_A),
+            remote("skip.tmp", 1, 500, MD5_A),
+        ];
+        let mut options = options(SyncDirection::Download, true);
+        options.filter.add("*.tmp", false).unwrap();
+        options.filter.add("extra.txt", false).unwrap();
+
+        let actions = plan(&options, &locals, &remotes, &|_| None);
+        assert_eq!(
+            summary(&actions),
+            vec![
+                (SyncActionKind::Download, "new/file.txt"),
+                (SyncActionKind::Download, "old.bin"),
+            ]
+        );
+        // Downloads carry the object's time so the next sync sees them as current
+        assert_eq!(actions[0].remote_modified, at(500));
+    }
+
+    #[test]
+    fn test_scan_local_and_folder_prefix() {
+        let root = std::env::temp_dir().join(format!("sync_scan_{}", std::process::id()));
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::write(root.join("top.txt"), "1").unwrap();
+        std::fs::write(root.join("a/b/deep.txt"), "22").unwrap();
+
+        let mut files = scan_local(&root).unwrap();
+        files.sort_by(|a, b| a.relative.cmp(&b.relative));
+        let found: Vec<(&str, u64)> = files
+            .iter()
+            .map(|f| (f.relative.as_str(), f.size))
+            .collect();
+        assert_eq!(found, vec![("a/b/deep.txt", 2), ("top.txt", 1)]);
+        assert!(scan_local(&root.join("missing")).unwrap().is_empty());
+        std::fs::remove_dir_all(root).unwrap();
+
+        assert_eq!(folder_prefix(""), "");
+        assert_eq!(folder_prefix("data/2024"), "data/2024/");
+        assert_eq!(folder_prefix("data/"), "data/");
+    }
+
+    #[tokio::test]
+    async fn test_upload_from_missing_directory_fails() {
+        let missing = std::env::temp_dir().join(format!("sync_missing_{}", std::process::id()));
+        let options = SyncOptions::parse_flags(
+            SyncDirection::Upload,
+            missing.to_str().unwrap(),
+            ["--delete"],
+        )
+        .unwrap();
+        let target = SyncTarget {
+            bucket: "b".to_string(),
+            folder: String::new(),
+            local_dir: missing,
+        };
+        // Refused before S3 is asked for anything
+        let config = aws_config::SdkConfig::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .build();
+        let result = target.plan(&S3Client::new(&config), &options).await;
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("is not a directory")
+        );
+    }
+}
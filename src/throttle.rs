@@ -0,0 +1,214 @@
+use crate::util::{format_size, parse_size};
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// Requests moving object data at the same time, unless configured otherwise
+pub const DEFAULT_MAX_REQUESTS: usize = 8;
+
+/// Paces transfers to a byte rate that can be changed while they run
+#[derive(Debug)]
+pub struct Bandwidth {
+    /// Bytes per second; 0 means unlimited
+    limit: AtomicU64,
+    /// When the bytes let through so far will have been sent at the limit
+    busy_until: Mutex<Option<Instant>>,
+}
+
+impl Bandwidth {
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit: AtomicU64::new(limit),
+            busy_until: Mutex::new(None),
+        }
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit.load(Ordering::Relaxed)
+    }
+
+    pub fn set_limit(&self, limit: u64) {
+        self.limit.store(limit, Ordering::Relaxed);
+        // Bytes booked at the old rate should not hold up the new one
+        *self.busy_until.lock().unwrap() = None;
+    }
+
+    /// Books `bytes` at the current limit, returning how long to wait until they are paid for
+    pub fn reserve(&self, bytes: u64, now: Instant) -> Duration {
+        let limit = self.limit();
+        if limit == 0 {
+            return Duration::ZERO;
+        }
+        let mut busy_until = self.busy_until.lock().unwrap();
+        let start = busy_until.map_or(now, |busy| busy.max(now));
+        let end = start + Duration::from_secs_f64(bytes as f64 / limit as f64);
+        *busy_until = Some(end);
+        end - now
+    }
+
+    /// Waits until `bytes` more fit within the limit
+    pub async fn take(&self, bytes: u64) {
+        let wait = self.reserve(bytes, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Slots {
+    limit: usize,
+    in_flight: usize,
+}
+
+/// Caps the requests in flight; lowering the cap lets running requests finish
+#[derive(Debug)]
+pub struct RequestLimit {
+    slots: Mutex<Slots>,
+    released: Notify,
+}
+
+/// A request slot, given back when dropped
+pub struct RequestPermit<'a> {
+    limit: &'a RequestLimit,
+}
+
+impl Drop for RequestPermit<'_> {
+    fn drop(&mut self) {
+        self.limit.slots.lock().unwrap().in_flight -= 1;
+        self.limit.released.notify_waiters();
+    }
+}
+
+impl RequestLimit {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            slots: Mutex::new(Slots {
+                limit: limit.max(1),
+                in_flight: 0,
+            }),
+            released: Notify::new(),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.slots.lock().unwrap().limit
+    }
+
+    pub fn set_limit(&self, limit: usize) {
+        self.slots.lock().unwrap().limit = limit.max(1);
+        self.released.notify_waiters();
+    }
+
+    pub fn try_acquire(&self) -> Option<RequestPermit<'_>> {
+        let mut slots = self.slots.lock().unwrap();
+        if slots.in_flight >= slots.limit {
+            return None;
+        }
+        slots.in_flight += 1;
+        Some(RequestPermit { limit: self })
+    }
+
+    /// Waits for a free slot
+    pub async fn acquire(&self) -> RequestPermit<'_> {
+        loop {
+            // Listen before checking, so a slot freed in between is not missed
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            if let Some(permit) = self.try_acquire() {
+                return permit;
+            }
+            released.await;
+        }
+    }
+}
+
+/// Limits shared by every transfer that goes through `S3Client`
+#[derive(Debug)]
+pub struct TransferLimits {
+    pub bandwidth: Bandwidth,
+    pub requests: RequestLimit,
+}
+
+impl TransferLimits {
+    pub fn new(bandwidth: u64, max_requests: usize) -> Self {
+        Self {
+            bandwidth: Bandwidth::new(bandwidth),
+            requests: RequestLimit::new(max_requests),
+        }
+    }
+}
+
+impl Default for TransferLimits {
+    fn default() -> Self {
+        Self::new(0, DEFAULT_MAX_REQUESTS)
+    }
+}
+
+impl fmt::Display for TransferLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bandwidth {}, {} requests at a time",
+            format_rate(self.bandwidth.limit()),
+            self.requests.limit()
+        )
+    }
+}
+
+/// A bandwidth limit for display; 0 is unlimited
+pub fn format_rate(limit: u64) -> String {
+    if limit == 0 {
+        "unlimited".to_string()
+    } else {
+        format!("{}/s", format_size(limit as i64))
+    }
+}
+
+/// Parses a bytes-per-second limit such as `10MB`, `512K/s`, or `off` for unlimited
+pub fn parse_rate(value: &str) -> Option<u64> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "0" | "off" | "none" | "unlimited" => Some(0),
+        value => parse_size(value.strip_suffix("/s").unwrap_or(value))
+            .ok()
+            .map(|size| size as u64),
+    }
+}
+
+/// Changes typed into the limits prompt, e.g. `global=10MB job=2MB requests=4`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LimitChanges {
+    pub global: Option<u64>,
+    pub job: Option<u64>,
+    pub requests: Option<usize>,
+}
+
+impl LimitChanges {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut changes = Self::default();
+        for word in input.split_whitespace() {
+            let (name, value) = word
+                .split_once('=')
+                .ok_or_else(|| format!("expected name=value, got '{}'", word))?;
+            let rate = || parse_rate(value).ok_or_else(|| format!("'{}' is not a rate", value));
+            match name {
+                "global" => changes.global = Some(rate()?),
+                "job" => changes.job = Some(rate()?),
+                "requests" => {
+                    changes.requests = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|requests| *requests > 0)
+                            .ok_or_else(|| format!("'{}' is not a number above 0", value))?,
+                    )
+                }
+                _ => return Err(format!("unknown limit '{}'", name)),
+            }
+        }
+        Ok(changes)
+    }
+}
@@ -0,0 +1,88 @@
+use crate::throttle::{Bandwidth, LimitChanges, RequestLimit, format_rate, parse_rate};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_bandwidth_reserve() {
+        let bandwidth = Bandwidth::new(1000);
+        let now = Instant::now();
+        assert_eq!(bandwidth.reserve(500, now), Duration::from_millis(500));
+        // Queued behind the bytes already booked
+        assert_eq!(bandwidth.reserve(500, now), Duration::from_secs(1));
+        // Idle time is not saved up for later bursts
+        let later = now + Duration::from_secs(10);
+        assert_eq!(bandwidth.reserve(250, later), Duration::from_millis(250));
+
+        bandwidth.set_limit(0);
+        assert_eq!(bandwidth.reserve(1 << 30, now), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_request_limit() {
+        let limit = RequestLimit::new(2);
+        let first = limit.try_acquire();
+        let second = limit.try_acquire();
+        assert!(first.is_some() && second.is_some());
+        assert!(limit.try_acquire().is_none());
+
+        drop(first);
+        assert!(limit.try_acquire().is_some());
+
+        // Lowering the limit holds new requests back until enough finish
+        limit.set_limit(1);
+        assert!(limit.try_acquire().is_none());
+        drop(second);
+        assert!(limit.try_acquire().is_some());
+
+        limit.set_limit(0);
+        assert_eq!(limit.limit(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_request_limit_wakes_waiters() {
+        let limit = std::sync::Arc::new(RequestLimit::new(1));
+        let held = limit.try_acquire();
+        let waiter = {
+            let limit = limit.clone();
+            tokio::spawn(async move {
+                let _permit = limit.acquire().await;
+            })
+        };
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+        drop(held);
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_parse_rates_and_limits() {
+        assert_eq!(parse_rate("off"), Some(0));
+        assert_eq!(parse_rate("2MB/s"), Some(2 * 1024 * 1024));
+        assert_eq!(
+            parse_rate(&format_rate(10 * 1024 * 1024)),
+            Some(10 * 1024 * 1024)
+        );
+        assert_eq!(parse_rate(&format_rate(0)), Some(0));
+        assert_eq!(parse_rate("1GiB/s"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse_rate("fast"), None);
+
+        assert_eq!(
+            LimitChanges::parse("global=10MB requests=4 job=off"),
+            Ok(LimitChanges {
+                global: Some(10 * 1024 * 1024),
+                job: Some(0),
+                requests: Some(4),
+            })
+        );
+        assert_eq!(LimitChanges::parse(""), Ok(LimitChanges::default()));
+        assert!(LimitChanges::parse("requests=0").is_err());
+        assert!(LimitChanges::parse("global").is_err());
+        assert!(LimitChanges::parse("speed=1MB").is_err());
+    }
+}
@@ -2,15 +2,23 @@ use crate::app::{App, AppMode};
 #[cfg(feature = "logging")]
 use crate::ui::components::render_logger;
 use crate::ui::components::{
-    render_footer, render_header, render_notification_area, render_search_bar,
+    render_filter_bar, render_footer, render_header, render_notification_area,
+    render_safety_banner, render_search_bar,
 };
-use crate::ui::list::{render_bucket_list, render_object_list, render_preview};
+use crate::ui::list::{
+    render_archive, render_bucket_admin, render_bucket_list, render_download_plan,
+    render_global_search_results, render_grep_results, render_jobs, render_lifecycle,
+    render_metadata, render_metadata_changes, render_object_info, render_object_list,
+    render_policy, render_preview, render_rename_preview, render_share, render_sync_plan,
+    render_tags, render_usage, render_verify,
+};
+use crate::usage::Breakdown;
+use ratatui::Frame;
 use ratatui::buffer::Buffer;
 use ratatui::layout::{Constraint, Layout, Rect};
 use ratatui::style::palette::tailwind::{BLUE, SLATE};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::widgets::Widget;
-use ratatui::Frame;
 
 mod components;
 mod list;
@@ -41,14 +49,21 @@ impl Widget for &mut App {
         #[cfg(not(feature = "logging"))]
         let [main_app_area] = Layout::horizontal([Constraint::Fill(1)]).areas(area);
 
+        let banner_height = match self.state.safety.banner() {
+            Some(_) if self.state.safety.is_confirming() => 2,
+            Some(_) => 1,
+            None => 0,
+        };
         let [
             header_area,
+            banner_area,
             notification_area,
             search_area,
             main_area,
             footer_area,
         ] = Layout::vertical([
             Constraint::Length(1),
+            Constraint::Length(banner_height),
             Constraint::Length(4),
             Constraint::Length(1),
             Constraint::Fill(1),
@@ -60,18 +75,34 @@ impl Widget for &mut App {
             mode,
             s3_bucket,
             s3_object,
+            global_search,
+            grep,
+            rename,
+            tags,
+            metadata,
+            share,
+            download,
+            sync,
+            verify,
+            jobs,
+            bucket_admin,
+            policy,
+            lifecycle,
+            usage,
+            archive,
             ..
         } = &self.state;
 
-        render_header(header_area, buf);
+        render_header(self.state.safety.safety.read_only, header_area, buf);
+        render_safety_banner(&self.state.safety, banner_area, buf);
         render_notification_area(&self.state, notification_area, buf);
 
         match self.state.mode {
             AppMode::BucketList => {
                 let helper_text = if s3_bucket.search_bar.active {
-                    "Type to search buckets  Enter: Apply  Esc: Cancel  Backspace: Delete"
+                    "Type to search buckets  Enter: Apply  Esc: Cancel  Backspace: Delete  Ctrl+r: Toggle Ranking  Ctrl+t: Cycle Mode"
                 } else {
-                    "j/k/↑/↓: Navigate  Enter: Select  Space: Load More  /: Search  g/G: Top/Bottom  r: Refresh  c: Clear Search  q: Quit"
+                    "j/k/↑/↓: Navigate  Enter: Select  Space: Load More  /: Search  S: Search All Buckets  a: Create Bucket  D: Delete Bucket  P: Policy  C: Lifecycle  U: Usage  g/G: Top/Bottom  r: Refresh  c: Clear Search  q: Quit"
                 };
                 render_search_bar(mode, &s3_bucket.search_bar, search_area, buf);
                 render_bucket_list(self, main_area, buf);
@@ -79,35 +110,212 @@ impl Widget for &mut App {
             }
             AppMode::ObjectList => {
                 let helper_text = match (s3_object.search_bar.active, s3_object.preview_object) {
+                    _ if s3_object.filter_bar.active => {
+                        "Filter e.g. size>1G modified<2024-01-01 class=STANDARD key~*.csv  Enter: Apply  Esc: Cancel  Backspace: Delete"
+                    }
                     (false, true) => {
-                        "j/k/↑/↓: Scroll  Ctrl+d/u: Half Page  Ctrl+f/b: Full Page  g/G: Top/Bottom  d/s/w: Download  r: Refresh  Esc: Back  q: Quit"
+                        "j/k/↑/↓: Scroll  Ctrl+d/u: Half Page  Ctrl+f/b: Full Page  g/G: Top/Bottom  d/s/w: Download  e: Edit  O: Open With  u: Share  r: Refresh  Esc: Back  q: Quit"
                     }
                     (true, false) => {
-                        "Type to filter by prefix  Enter: Apply  Esc: Cancel  Backspace: Delete"
+                        "Type to filter by prefix  Enter: Apply  Esc: Cancel  Backspace: Delete  Ctrl+r: Toggle Ranking  Ctrl+t: Cycle Mode"
                     }
                     (false, false) => {
-                        "j/k/↑/↓: Navigate  Enter: Preview  Space: Load More  /: Filter  d/s/w: Download  r: Refresh  c: Clear Filter  Esc: Back  q: Quit"
+                        "j/k/↑/↓: Navigate  Enter: Preview  Space: Load More  /: Filter  ?: Metadata Filter  Ctrl+g: Grep  U: Usage  d/s/w: Download (marked: recursive)  W: Download Folder  Y: Sync  V: Verify  A: Restore/Storage Class  R/F: Rename Object/Folder  e: Edit  O: Open With  u: Share  i: Info  T: Tags  H: Metadata  m: Mark  y/x: Yank Copy/Move  p: Paste  D: Delete  J: Jobs  r: Refresh  c: Clear Filter  Esc: Back  q: Quit"
                     }
                     // both states above cannot be true at the same time
                     _ => "",
                 };
 
-                render_search_bar(mode, &s3_object.search_bar, search_area, buf);
-                if s3_object.preview_object == true {
-                    let [list_area, preview_content_area] =
+                let show_search =
+                    s3_object.search_bar.active || !s3_object.search_bar.query.is_empty();
+                let show_filter =
+                    s3_object.filter_bar.active || !s3_object.filter_bar.query.is_empty();
+                if show_search && show_filter {
+                    let [search_half, filter_half] =
                         Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)])
-                            .areas(main_area);
+                            .areas(search_area);
+                    render_search_bar(mode, &s3_object.search_bar, search_half, buf);
+                    render_filter_bar(&s3_object.filter_bar, filter_half, buf);
+                } else if show_filter {
+                    render_filter_bar(&s3_object.filter_bar, search_area, buf);
+                } else {
+                    render_search_bar(mode, &s3_object.search_bar, search_area, buf);
+                }
+                let [list_area, preview_content_area] =
+                    Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(main_area);
 
-                    render_object_list(self, list_area, buf);
+                let show_info = s3_object.object_info.is_some() && !s3_object.preview_object;
+                render_object_list(self, list_area, buf);
+                if show_info {
+                    render_object_info(self, preview_content_area, buf);
+                } else {
                     render_preview(self, preview_content_area, buf);
+                }
+                render_footer(footer_area, buf, helper_text);
+            }
+            AppMode::GlobalSearch => {
+                let helper_text = if global_search.prompt.active {
+                    "Type a key prefix, or a pattern with a sigil (' = ~ %)  Enter: Search  Esc: Cancel  Ctrl+t: Cycle Mode"
                 } else {
-                    let [list_area, preview_content_area] =
+                    "j/k/↑/↓: Navigate  Enter: Open in Bucket  /: New Search  g/G: Top/Bottom  Esc: Back  q: Quit"
+                };
+                render_search_bar(mode, &global_search.prompt, search_area, buf);
+                render_global_search_results(self, main_area, buf);
+                render_footer(footer_area, buf, helper_text);
+            }
+            AppMode::Grep => {
+                let helper_text = if grep.prompt.active {
+                    "Type a regex, optionally followed by ext=log,csv and max=10M  Enter: Grep  Esc: Cancel"
+                } else {
+                    "j/k/↑/↓: Navigate  Enter: Open at Line  /: New Grep  g/G: Top/Bottom  Esc: Back  q: Quit"
+                };
+                render_search_bar(mode, &grep.prompt, search_area, buf);
+                render_grep_results(self, main_area, buf);
+                render_footer(footer_area, buf, helper_text);
+            }
+            AppMode::Rename => {
+                let helper_text = if rename.prompt.active {
+                    "Type the new name  Enter: Preview  Esc: Cancel  Backspace: Delete"
+                } else {
+                    "j/k/↑/↓: Navigate  Enter: Rename  Esc: Edit Name  g/G: Top/Bottom  q: Quit"
+                };
+                render_search_bar(mode, &rename.prompt, search_area, buf);
+                render_rename_preview(self, main_area, buf);
+                render_footer(footer_area, buf, helper_text);
+            }
+            AppMode::Tags => {
+                let helper_text = if tags.prompt.active {
+                    "Type key=value (or -key to remove in bulk mode)  Enter: Apply  Esc: Cancel"
+                } else {
+                    "j/k/↑/↓: Navigate  a: Add  Enter: Edit  D: Delete  Ctrl+s: Save  Esc: Close  q: Quit"
+                };
+                render_search_bar(mode, &tags.prompt, search_area, buf);
+                let [list_area, tags_area] =
+                    Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(main_area);
+                render_object_list(self, list_area, buf);
+                render_tags(self, tags_area, buf);
+                render_footer(footer_area, buf, helper_text);
+            }
+            AppMode::Metadata => {
+                let helper_text = if metadata.prompt.active {
+                    "Type Name: value, e.g. Content-Type: text/plain or x-amz-meta-owner: ops  Enter: Apply  Esc: Cancel"
+                } else if metadata.confirming {
+                    "j/k/↑/↓: Navigate  Enter: Apply Changes  Esc: Back to Editor  q: Quit"
+                } else {
+                    "j/k/↑/↓: Navigate  a: Add  Enter: Edit  D: Delete  Ctrl+s: Review Changes  Esc: Close  q: Quit"
+                };
+                render_search_bar(mode, &metadata.prompt, search_area, buf);
+                if metadata.confirming {
+                    render_metadata_changes(self, main_area, buf);
+                } else {
+                    let [list_area, fields_area] =
                         Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)])
                             .areas(main_area);
-
                     render_object_list(self, list_area, buf);
-                    render_preview(self, preview_content_area, buf);
+                    render_metadata(self, fields_area, buf);
+                }
+                render_footer(footer_area, buf, helper_text);
+            }
+            AppMode::Share => {
+                let helper_text = if share.prompt.active {
+                    share.prompt_help()
+                } else {
+                    "j/k/↑/↓: Navigate  Enter: Copy Link  Esc/u: Close  q: Quit"
+                };
+                render_search_bar(mode, &share.prompt, search_area, buf);
+                render_object_list(self, main_area, buf);
+                render_share(self, main_area, buf);
+                render_footer(footer_area, buf, helper_text);
+            }
+            AppMode::Download => {
+                let helper_text = if download.prompt.active {
+                    "Type the local directory (or a file path for one object)  Tab: Complete  Enter: Download  Esc: Cancel"
+                } else {
+                    "j/k/↑/↓: Navigate  h/l: Skip/Overwrite/Keep Both Existing  Enter: Download  Esc: Edit Directory  q: Quit"
+                };
+                render_search_bar(mode, &download.prompt, search_area, buf);
+                render_download_plan(self, main_area, buf);
+                render_footer(footer_area, buf, helper_text);
+            }
+            AppMode::BucketAdmin => {
+                let helper_text = if bucket_admin.prompt.active {
+                    bucket_admin.prompt_help()
+                } else if bucket_admin.picking_region {
+                    "j/k/↑/↓: Navigate  Enter: Pick Region  Esc: Back".to_string()
+                } else {
+                    "j/k/↑/↓: Navigate  Enter: Change/Confirm  Esc: Close  q: Quit".to_string()
+                };
+                render_search_bar(mode, &bucket_admin.prompt, search_area, buf);
+                render_bucket_list(self, main_area, buf);
+                render_bucket_admin(self, main_area, buf);
+                render_footer(footer_area, buf, &helper_text);
+            }
+            AppMode::Policy => {
+                let helper_text = if policy.pending.is_some() {
+                    "j/k/↑/↓: Scroll  Enter: Save Policy  e: Edit Again  Esc: Discard  q: Quit"
+                } else {
+                    "j/k/↑/↓: Scroll  e: Edit in $EDITOR  g/G: Top/Bottom  Esc: Back  q: Quit"
+                };
+                render_policy(self, main_area, buf);
+                render_footer(footer_area, buf, helper_text);
+            }
+            AppMode::Lifecycle => {
+                let helper_text = if lifecycle.prompt.active {
+                    lifecycle.prompt_help()
+                } else if lifecycle.editing {
+                    "j/k/↑/↓: Navigate  Enter: Change/Save Rule  Esc: Back to Rules  q: Quit"
+                } else {
+                    "j/k/↑/↓: Navigate  a: Add Rule  Enter: Edit Rule  D: Remove Rule  e: Edit as JSON  Ctrl+s: Save  Esc: Back  q: Quit"
+                };
+                render_search_bar(mode, &lifecycle.prompt, search_area, buf);
+                render_lifecycle(self, main_area, buf);
+                render_footer(footer_area, buf, helper_text);
+            }
+            AppMode::Archive => {
+                let helper_text = if archive.prompt.active {
+                    "Type the number of days to keep the restored copy  Enter: Apply  Esc: Cancel"
+                } else {
+                    "j/k/↑/↓: Navigate  Enter: Change/Apply  Esc: Close  q: Quit"
+                };
+                render_search_bar(mode, &archive.prompt, search_area, buf);
+                render_object_list(self, main_area, buf);
+                render_archive(self, main_area, buf);
+                render_footer(footer_area, buf, helper_text);
+            }
+            AppMode::Usage => {
+                let helper_text = if usage.breakdown == Breakdown::Prefix {
+                    "j/k/↑/↓: Navigate  Enter: Open Sub-prefix  h/l: By Storage Class  t: Sort  r: Recompute  Esc: Back  q: Quit"
+                } else {
+                    "j/k/↑/↓: Navigate  h/l: By Sub-prefix  t: Sort  r: Recompute  Esc: Back  q: Quit"
+                };
+                render_usage(self, main_area, buf);
+                render_footer(footer_area, buf, helper_text);
+            }
+            AppMode::Verify => {
+                render_search_bar(mode, &verify.prompt, search_area, buf);
+                render_verify(self, main_area, buf);
+                render_footer(footer_area, buf, "Enter: Verify  Esc: Back");
+            }
+            AppMode::Sync => {
+                let helper_text = if sync.prompt.active {
+                    "up|down DIR [--delete] [--dry-run] [--exclude GLOB] [--include GLOB]  Enter: Plan  Esc: Cancel"
+                } else {
+                    "j/k/↑/↓: Navigate  Enter: Run Sync  Esc: Edit  g/G: Top/Bottom  q: Quit"
+                };
+                render_search_bar(mode, &sync.prompt, search_area, buf);
+                render_sync_plan(self, main_area, buf);
+                render_footer(footer_area, buf, helper_text);
+            }
+            AppMode::Jobs => {
+                let helper_text = if jobs.limits_prompt.active {
+                    "global=RATE (all transfers)  job=RATE (selected job)  requests=N, e.g. global=10MB or job=off  Enter: Apply  Esc: Cancel"
+                } else {
+                    "j/k/↑/↓: Navigate  p: Pause/Resume  c: Cancel (at the next chunk, part or object)  r: Retry  L: Limits  g/G: Top/Bottom  J/Esc: Close Jobs  q: Quit"
+                };
+                if jobs.limits_prompt.active {
+                    render_search_bar(mode, &jobs.limits_prompt, search_area, buf);
                 }
+                render_jobs(self, main_area, buf);
                 render_footer(footer_area, buf, helper_text);
             }
         }
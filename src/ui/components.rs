@@ -1,20 +1,41 @@
-use crate::app::state::AppState;
 use crate::app::AppMode;
-use crate::search::SearchBar;
+use crate::app::state::AppState;
+use crate::app::state::safety::SafetyState;
+use crate::search::{SearchBar, SearchMode};
 use ratatui::buffer::Buffer;
-use ratatui::layout::{Position, Rect};
-use ratatui::style::palette::tailwind::SLATE;
-use ratatui::style::{Style, Stylize};
+use ratatui::layout::{Constraint, Layout, Position, Rect};
+use ratatui::style::palette::tailwind::{RED, SLATE};
+use ratatui::style::{Color, Style, Stylize};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 #[cfg(feature = "logging")]
 use tui_logger::{LogFormatter, TuiLoggerLevelOutput, TuiLoggerWidget};
 
 /// Render the header
-pub fn render_header(area: Rect, buf: &mut Buffer) {
-    Paragraph::new("AWS S3 Browser")
+pub fn render_header(read_only: bool, area: Rect, buf: &mut Buffer) {
+    let title = if read_only {
+        "AWS S3 Browser [READ-ONLY]"
+    } else {
+        "AWS S3 Browser"
+    };
+    Paragraph::new(title).bold().centered().render(area, buf);
+}
+
+/// Render the warning for a refused action, or the bucket name prompt of a protected bucket
+pub fn render_safety_banner(safety: &SafetyState, area: Rect, buf: &mut Buffer) {
+    let Some(banner) = safety.banner() else {
+        return;
+    };
+    let [message_area, input_area] =
+        Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+    Paragraph::new(banner)
+        .style(Style::default().bg(RED.c800).fg(Color::White))
         .bold()
-        .centered()
-        .render(area, buf);
+        .render(message_area, buf);
+
+    if safety.is_confirming() {
+        let text = format!("Bucket name: {}_", safety.prompt.query);
+        render_input_line(&text, safety.prompt.error.as_deref(), input_area, buf);
+    }
 }
 
 /// Render the notification area
@@ -38,40 +59,126 @@ pub fn render_search_bar(app_mode: &AppMode, search_bar: &SearchBar, area: Rect,
         return;
     }
 
+    let (mode, _) = search_bar.mode_and_pattern();
     let search_text = if search_bar.active {
         match app_mode {
-            AppMode::BucketList => format!("Search buckets: {}_", search_bar.query),
+            AppMode::BucketList => format!("Search buckets ({}): {}_", mode, search_bar.query),
             AppMode::ObjectList => {
-                format!("Filter by prefix: {}_", search_bar.query)
+                format!("Filter by prefix ({}): {}_", mode, search_bar.query)
             }
+            AppMode::GlobalSearch => format!(
+                "Search all buckets ({}): {}_",
+                global_search_mode_label(mode),
+                search_bar.query
+            ),
+            AppMode::Grep => format!("Grep regex [ext=..] [max=..]: {}_", search_bar.query),
+            AppMode::Jobs => format!("Limits: {}_", search_bar.query),
+            AppMode::Rename => format!("Rename to: {}_", search_bar.query),
+            AppMode::Tags => format!("Tag (key=value): {}_", search_bar.query),
+            AppMode::Metadata => format!("Header (Name: value): {}_", search_bar.query),
+            AppMode::Share => format!("Share: {}_", search_bar.query),
+            AppMode::Download => format!("Download to: {}_", search_bar.query),
+            AppMode::Sync => format!("Sync: {}_", search_bar.query),
+            AppMode::Verify => format!("Verify file: {}_", search_bar.query),
+            AppMode::BucketAdmin => format!("Bucket: {}_", search_bar.query),
+            AppMode::Policy => format!("Policy: {}_", search_bar.query),
+            AppMode::Lifecycle => format!("Lifecycle: {}_", search_bar.query),
+            AppMode::Usage => format!("Usage: {}_", search_bar.query),
+            AppMode::Archive => format!("Restore days: {}_", search_bar.query),
         }
     } else {
         match app_mode {
-            AppMode::BucketList => format!("Search buckets: {}", search_bar.query),
-            AppMode::ObjectList => format!("Prefix: {}", search_bar.query),
+            AppMode::BucketList => format!("Search buckets ({}): {}", mode, search_bar.query),
+            AppMode::ObjectList => format!("Prefix ({}): {}", mode, search_bar.query),
+            AppMode::GlobalSearch => format!(
+                "Search all buckets ({}): {}",
+                global_search_mode_label(mode),
+                search_bar.query
+            ),
+            AppMode::Grep => format!("Grep: {}", search_bar.query),
+            AppMode::Jobs => format!("Limits: {}", search_bar.query),
+            AppMode::Rename => format!("Rename to: {}", search_bar.query),
+            AppMode::Tags => format!("Tag (key=value): {}", search_bar.query),
+            AppMode::Metadata => format!("Header (Name: value): {}", search_bar.query),
+            AppMode::Share => format!("Share: {}", search_bar.query),
+            AppMode::Download => format!("Download to: {}", search_bar.query),
+            AppMode::Sync => format!("Sync: {}", search_bar.query),
+            AppMode::Verify => format!("Verify file: {}", search_bar.query),
+            AppMode::BucketAdmin => format!("Bucket: {}", search_bar.query),
+            AppMode::Policy => format!("Policy: {}", search_bar.query),
+            AppMode::Lifecycle => format!("Lifecycle: {}", search_bar.query),
+            AppMode::Usage => format!("Usage: {}", search_bar.query),
+            AppMode::Archive => format!("Restore days: {}", search_bar.query),
         }
     };
 
+    // Streamed results stay in listing order, so ranking only applies to the bucket and object lists
+    let ranked_mode = matches!(app_mode, AppMode::BucketList | AppMode::ObjectList);
+    let search_text = if search_bar.rank_by_score && ranked_mode {
+        format!("{}  [ranked]", search_text)
+    } else {
+        search_text
+    };
+
+    render_input_line(&search_text, search_bar.error.as_deref(), area, buf);
+}
+
+/// Global search treats a fuzzy query as a plain key prefix
+fn global_search_mode_label(mode: SearchMode) -> String {
+    match mode {
+        SearchMode::Fuzzy => "prefix".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Render the metadata filter expression input
+pub fn render_filter_bar(filter_bar: &SearchBar, area: Rect, buf: &mut Buffer) {
+    if !filter_bar.active && filter_bar.query.is_empty() {
+        return;
+    }
+
+    let filter_text = if filter_bar.active {
+        format!("Filter: {}_", filter_bar.query)
+    } else {
+        format!("Filter: {}", filter_bar.query)
+    };
+
+    render_input_line(&filter_text, filter_bar.error.as_deref(), area, buf);
+}
+
+/// Draws a single-line input with an optional inline error after the text
+fn render_input_line(text: &str, error: Option<&str>, area: Rect, buf: &mut Buffer) {
     let block = Block::default()
         .borders(Borders::NONE)
         .style(Style::default().bg(SLATE.c800));
 
-    let search_area = Rect {
+    let input_area = Rect {
         x: area.x,
         y: area.y,
         width: area.width,
         height: 1,
     };
 
-    block.render(search_area, buf);
+    block.render(input_area, buf);
 
     let text_x = area.x + 1;
-    for (i, c) in search_text.chars().enumerate() {
+    for (i, c) in text.chars().enumerate() {
         if (text_x + i as u16) < area.x + area.width {
             buf.cell_mut(Position::new(text_x + i as u16, area.y))
                 .map(|cell| cell.set_char(c).set_style(Style::default().fg(SLATE.c100)));
         }
     }
+
+    // Report invalid input inline, after the text
+    if let Some(error) = error {
+        let error_x = text_x + text.chars().count() as u16 + 2;
+        for (i, c) in error.chars().enumerate() {
+            if (error_x + i as u16) < area.x + area.width {
+                buf.cell_mut(Position::new(error_x + i as u16, area.y))
+                    .map(|cell| cell.set_char(c).set_style(Style::default().fg(Color::Red)));
+            }
+        }
+    }
 }
 
 /// Render the footer with context-aware help based on app state
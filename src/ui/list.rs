@@ -1,10 +1,20 @@
 use super::{ALT_ROW_BG_COLOR, NORMAL_ROW_BG, SELECTED_STYLE, TEXT_FG_COLOR};
 use crate::app::App;
 use crate::app::state::LoadingState;
+use crate::app::state::jobs::{JobStatus, skipped_note};
+use crate::app::state::lifecycle::LifecycleState;
+use crate::app::state::policy::PolicyLineKind;
+use crate::archive::is_archived;
+use crate::throttle::format_rate;
+use crate::usage::UsageSort;
+use crate::util::{format_eta, format_size};
 use ratatui::buffer::Buffer;
-use ratatui::layout::Rect;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::Style;
-use ratatui::widgets::{Block, BorderType, Borders, Paragraph, Widget};
+use ratatui::text::Line;
+use ratatui::widgets::{
+    Bar, BarChart, BarGroup, Block, BorderType, Borders, Clear, Paragraph, Widget, Wrap,
+};
 use render::render_list;
 
 mod render;
@@ -29,8 +39,9 @@ pub fn render_bucket_list(app: &mut App, area: Rect, buf: &mut Buffer) {
 }
 
 pub fn render_object_list(app: &mut App, area: Rect, buf: &mut Buffer) {
+    let s3_object = &mut app.state.s3_object;
     render_list(
-        &mut app.state.s3_object.object_list,
+        &mut s3_object.object_list,
         area,
         buf,
         NORMAL_ROW_BG,
@@ -39,22 +50,244 @@ pub fn render_object_list(app: &mut App, area: Rect, buf: &mut Buffer) {
         Style::default().fg(TEXT_FG_COLOR),
         |object, _| {
             let key = object.key().unwrap_or("Unknown");
-            let size = object.size().unwrap_or(0);
-            let size_str = if size < 1024 {
-                format!("{}B", size)
-            } else if size < 1024 * 1024 {
-                format!("{:.2}KB", size as f64 / 1024.0)
-            } else if size < 1024 * 1024 * 1024 {
-                format!("{:.2}MB", size as f64 / (1024.0 * 1024.0))
+            let size_str = format_size(object.size().unwrap_or(0));
+            let mark = if s3_object.marked.contains(key) {
+                "* "
             } else {
-                format!("{:.2}GB", size as f64 / (1024.0 * 1024.0 * 1024.0))
+                ""
             };
+            let class = object.storage_class().map(|class| class.as_str());
+            if is_archived(class) {
+                return format!(
+                    "{}{} ({}) [{}]",
+                    mark,
+                    key,
+                    size_str,
+                    class.unwrap_or_default()
+                );
+            }
+
+            format!("{}{} ({})", mark, key, size_str)
+        },
+    );
+}
+
+pub fn render_global_search_results(app: &mut App, area: Rect, buf: &mut Buffer) {
+    render_list(
+        &mut app.state.global_search.results,
+        area,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |hit, _| format!("{}/{} ({})", hit.bucket, hit.key, format_size(hit.size)),
+    );
+}
+
+pub fn render_grep_results(app: &mut App, area: Rect, buf: &mut Buffer) {
+    render_list(
+        &mut app.state.grep.results,
+        area,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |hit, _| {
+            let mut lines = vec![format!("{}:{}", hit.key, hit.line_number)];
+            lines.extend(hit.before.iter().map(|line| format!("    {}", line)));
+            lines.push(format!("  > {}", hit.line));
+            lines.extend(hit.after.iter().map(|line| format!("    {}", line)));
+            lines.join("\n")
+        },
+    );
+}
 
-            format!("{} ({})", key, size_str)
+pub fn render_jobs(app: &mut App, area: Rect, buf: &mut Buffer) {
+    render_list(
+        &mut app.state.jobs.jobs,
+        area,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |job, _| {
+            let status = if job.is_cancelling() {
+                "Cancelling".to_string()
+            } else {
+                job.status.to_string()
+            };
+            let mut line = format!(
+                "[{}] {}  {}/{} done{}, {} failed",
+                status,
+                job.description,
+                job.done,
+                job.total,
+                skipped_note(job.skipped),
+                job.failures.len()
+            );
+            if job.bytes_done > 0 || job.bytes_total.is_some() {
+                line.push_str(&format!("  {}", format_size(job.bytes_done as i64)));
+                if let Some(total) = job.bytes_total {
+                    line.push_str(&format!(" of {}", format_size(total as i64)));
+                }
+            }
+            if let Some(rate) = job.rate().filter(|_| job.status == JobStatus::Running) {
+                line.push_str(&format!("  {}/s", format_size(rate as i64)));
+            }
+            if let Some(eta) = job.eta() {
+                line.push_str(&format!("  ETA {}", format_eta(eta)));
+            }
+            if job.bandwidth_limit() > 0 {
+                line.push_str(&format!("  cap {}", format_rate(job.bandwidth_limit())));
+            }
+            if job.retries > 0 {
+                line.push_str(&format!("  retries: {}", job.retries));
+            }
+            line
         },
     );
 }
 
+pub fn render_rename_preview(app: &mut App, area: Rect, buf: &mut Buffer) {
+    render_list(
+        &mut app.state.rename.preview,
+        area,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |entry, _| {
+            if entry.conflict {
+                format!("{} → {}  [exists, skipped]", entry.old_key, entry.new_key)
+            } else {
+                format!("{} → {}", entry.old_key, entry.new_key)
+            }
+        },
+    );
+}
+
+pub fn render_download_plan(app: &mut App, area: Rect, buf: &mut Buffer) {
+    render_list(
+        &mut app.state.download.plan,
+        area,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |entry, _| match &entry.path {
+            Some(path) if entry.exists => format!("{} → {}  [exists]", entry.key, path.display()),
+            Some(path) => format!("{} → {}", entry.key, path.display()),
+            None => format!("{}  [unsafe path, skipped]", entry.key),
+        },
+    );
+}
+
+pub fn render_sync_plan(app: &mut App, area: Rect, buf: &mut Buffer) {
+    render_list(
+        &mut app.state.sync.plan,
+        area,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |action, _| {
+            format!(
+                "{:12} {} ({})",
+                action.kind.to_string(),
+                action.relative,
+                format_size(action.size as i64)
+            )
+        },
+    );
+}
+
+pub fn render_tags(app: &mut App, area: Rect, buf: &mut Buffer) {
+    render_list(
+        &mut app.state.tags.tags,
+        area,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |entry, _| match &entry.value {
+            Some(value) => format!("{} = {}", entry.key, value),
+            None => format!("{}  [remove]", entry.key),
+        },
+    );
+}
+
+pub fn render_metadata(app: &mut App, area: Rect, buf: &mut Buffer) {
+    render_list(
+        &mut app.state.metadata.fields,
+        area,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |entry, _| match &entry.value {
+            Some(value) => format!("{}: {}", entry.name, value),
+            None => format!("{}  [remove]", entry.name),
+        },
+    );
+}
+
+pub fn render_metadata_changes(app: &mut App, area: Rect, buf: &mut Buffer) {
+    render_list(
+        &mut app.state.metadata.changes,
+        area,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |change, _| {
+            format!(
+                "{}\n    {}: {} → {}",
+                change.key,
+                change.name,
+                change.before.as_deref().unwrap_or("(none)"),
+                change.after.as_deref().unwrap_or("(removed)")
+            )
+        },
+    );
+}
+
+pub fn render_object_info(app: &App, area: Rect, buf: &mut Buffer) {
+    let Some(info) = &app.state.s3_object.object_info else {
+        return;
+    };
+
+    let block = Block::default()
+        .title(format!(" Info: {} ", info.key))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded);
+    let name_width = info
+        .fields
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    let text: Vec<String> = info
+        .fields
+        .iter()
+        .map(|(name, value)| format!("{:width$}  {}", name, value, width = name_width))
+        .collect();
+
+    Paragraph::new(text.join("\n"))
+        .style(Style::default().fg(TEXT_FG_COLOR))
+        .wrap(Wrap { trim: false })
+        .block(block)
+        .render(area, buf);
+}
+
 pub fn render_preview(app: &mut App, area: Rect, buf: &mut Buffer) {
     let object_name_for_title = app
         .state
@@ -113,12 +346,11 @@ pub fn render_preview(app: &mut App, area: Rect, buf: &mut Buffer) {
             app.state.s3_object.preview_scroll_offset = display_lines.len().saturating_sub(1);
         }
 
-        let start_line_idx = app.state.s3_object.preview_scroll_offset.min(
-            display_lines
-                .len()
-                .saturating_sub(visible_lines_count)
-                .max(0),
-        );
+        let start_line_idx = app
+            .state
+            .s3_object
+            .preview_scroll_offset
+            .min(display_lines.len().saturating_sub(visible_lines_count));
 
         let end_line_idx = (start_line_idx + visible_lines_count).min(display_lines.len());
 
@@ -154,12 +386,12 @@ pub fn render_preview(app: &mut App, area: Rect, buf: &mut Buffer) {
 
                 let scrollbar_x = preview_inner_drawing_area.x
                     + preview_inner_drawing_area.width.saturating_sub(1);
-                if scrollbar_x >= preview_inner_drawing_area.x {
-                    buf.cell_mut((scrollbar_x, preview_inner_drawing_area.y + scroll_thumb_pos))
-                        .map(|cell| {
-                            cell.set_char('█')
-                                .set_style(Style::default().fg(super::BLUE.c500));
-                        });
+                if scrollbar_x >= preview_inner_drawing_area.x
+                    && let Some(cell) =
+                        buf.cell_mut((scrollbar_x, preview_inner_drawing_area.y + scroll_thumb_pos))
+                {
+                    cell.set_char('█')
+                        .set_style(Style::default().fg(super::BLUE.c500));
                 }
             }
         }
@@ -177,3 +409,269 @@ pub fn render_preview(app: &mut App, area: Rect, buf: &mut Buffer) {
             .render(preview_inner_drawing_area, buf);
     }
 }
+
+/// The share options as a modal over `area`, with the last generated link below them
+pub fn render_verify(app: &mut App, area: Rect, buf: &mut Buffer) {
+    let verify = &app.state.verify;
+    let block = Block::default()
+        .title(format!(" Verify s3://{}/{} ", verify.bucket, verify.key))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded);
+    Paragraph::new(
+        verify
+            .result
+            .as_deref()
+            .unwrap_or("Enter the local copy of the object to compare their sizes and checksums"),
+    )
+    .style(Style::default().fg(TEXT_FG_COLOR).bg(NORMAL_ROW_BG))
+    .wrap(Wrap { trim: false })
+    .block(block)
+    .render(area, buf);
+}
+
+pub fn render_bucket_admin(app: &mut App, area: Rect, buf: &mut Buffer) {
+    let width = area.width.saturating_sub(4).min(80);
+    let height = area.height.saturating_sub(2).min(14);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    Clear.render(popup, buf);
+
+    let admin = &mut app.state.bucket_admin;
+    if admin.picking_region {
+        render_list(
+            &mut admin.regions,
+            popup,
+            buf,
+            NORMAL_ROW_BG,
+            ALT_ROW_BG_COLOR,
+            SELECTED_STYLE,
+            Style::default().fg(TEXT_FG_COLOR),
+            |region, _| region.to_string(),
+        );
+        return;
+    }
+
+    let labels: Vec<String> = admin
+        .fields
+        .items
+        .iter()
+        .map(|field| admin.label(*field))
+        .collect();
+    render_list(
+        &mut admin.fields,
+        popup,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |_, index| labels[index].clone(),
+    );
+}
+
+pub fn render_archive(app: &mut App, area: Rect, buf: &mut Buffer) {
+    let width = area.width.saturating_sub(4).min(80);
+    let height = area.height.saturating_sub(2).min(10);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    Clear.render(popup, buf);
+
+    let archive = &mut app.state.archive;
+    let labels: Vec<String> = archive
+        .fields
+        .items
+        .iter()
+        .map(|field| archive.label(*field))
+        .collect();
+    render_list(
+        &mut archive.fields,
+        popup,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |_, index| labels[index].clone(),
+    );
+}
+
+pub fn render_policy(app: &mut App, area: Rect, buf: &mut Buffer) {
+    render_list(
+        &mut app.state.policy.lines,
+        area,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |line, _| {
+            let marker = match line.kind {
+                PolicyLineKind::Warning | PolicyLineKind::Wildcard => "⚠ ",
+                PolicyLineKind::Text => "  ",
+                PolicyLineKind::Added => "+ ",
+                PolicyLineKind::Removed => "- ",
+            };
+            format!("{}{}", marker, line.text)
+        },
+    );
+}
+
+pub fn render_lifecycle(app: &mut App, area: Rect, buf: &mut Buffer) {
+    let lifecycle = &mut app.state.lifecycle;
+    render_list(
+        &mut lifecycle.rules,
+        area,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |rule, _| LifecycleState::rule_line(rule),
+    );
+    if !lifecycle.editing {
+        return;
+    }
+
+    let width = area.width.saturating_sub(4).min(100);
+    let height = area.height.saturating_sub(2).min(15);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    Clear.render(popup, buf);
+    let labels: Vec<String> = lifecycle
+        .fields
+        .items
+        .iter()
+        .map(|field| lifecycle.label(*field))
+        .collect();
+    render_list(
+        &mut lifecycle.fields,
+        popup,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |_, index| labels[index].clone(),
+    );
+}
+
+/// Labels longer than this are cut in the bar chart
+const BAR_LABEL_WIDTH: usize = 24;
+
+pub fn render_usage(app: &mut App, area: Rect, buf: &mut Buffer) {
+    let [table_area, chart_area] =
+        Layout::vertical([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(area);
+    let usage = &mut app.state.usage;
+    let total_bytes = usage.usage.total.bytes.max(1);
+    render_list(
+        &mut usage.rows,
+        table_area,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |row, _| {
+            format!(
+                "{:<40} {:>10} objects {:>10} {:>5.1}%",
+                row.name,
+                row.totals.objects,
+                format_size(row.totals.bytes as i64),
+                row.totals.bytes as f64 * 100.0 / total_bytes as f64
+            )
+        },
+    );
+
+    // One bar per row, in the table's order, as many as fit
+    let by_count = usage.sort == UsageSort::Objects;
+    let bars: Vec<Bar> = usage
+        .rows
+        .items
+        .iter()
+        .take(chart_area.height.saturating_sub(2) as usize)
+        .map(|row| {
+            let label: String = row.name.chars().take(BAR_LABEL_WIDTH).collect();
+            let (value, text) = if by_count {
+                (row.totals.objects, row.totals.objects.to_string())
+            } else {
+                (row.totals.bytes, format_size(row.totals.bytes as i64))
+            };
+            Bar::default()
+                .label(Line::from(label))
+                .value(value)
+                .text_value(text)
+        })
+        .collect();
+    let block = Block::default()
+        .title(if by_count { " Objects " } else { " Size " })
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded);
+    BarChart::default()
+        .block(block)
+        .direction(Direction::Horizontal)
+        .bar_width(1)
+        .bar_gap(0)
+        .bar_style(Style::default().fg(TEXT_FG_COLOR))
+        .style(Style::default().bg(NORMAL_ROW_BG))
+        .data(BarGroup::default().bars(&bars))
+        .render(chart_area, buf);
+}
+
+pub fn render_share(app: &mut App, area: Rect, buf: &mut Buffer) {
+    let width = area.width.saturating_sub(4).min(100);
+    let height = area.height.saturating_sub(2).min(15);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    Clear.render(popup, buf);
+
+    let [options_area, url_area] =
+        Layout::vertical([Constraint::Length(10), Constraint::Fill(1)]).areas(popup);
+    let share = &mut app.state.share;
+    let labels: Vec<String> = share
+        .options
+        .items
+        .iter()
+        .map(|option| share.label(*option))
+        .collect();
+    render_list(
+        &mut share.options,
+        options_area,
+        buf,
+        NORMAL_ROW_BG,
+        ALT_ROW_BG_COLOR,
+        SELECTED_STYLE,
+        Style::default().fg(TEXT_FG_COLOR),
+        |_, index| labels[index].clone(),
+    );
+
+    let block = Block::default()
+        .title(" Link ")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded);
+    Paragraph::new(
+        share
+            .url
+            .as_deref()
+            .unwrap_or("Enter: Copy the selected link"),
+    )
+    .style(Style::default().fg(TEXT_FG_COLOR).bg(NORMAL_ROW_BG))
+    .wrap(Wrap { trim: false })
+    .block(block)
+    .render(url_area, buf);
+}
@@ -4,9 +4,12 @@ use ratatui::{
     layout::{Alignment, Constraint, Layout, Rect},
     style::{Color, Style, Stylize},
     text::{Line, Text},
-    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph, StatefulWidget, Widget},
+    widgets::{
+        Block, BorderType, Borders, List, ListItem, ListState, Paragraph, StatefulWidget, Widget,
+    },
 };
 
+#[allow(clippy::too_many_arguments)]
 pub fn render_list<T, F>(
     list: &mut ScrollableList<T>,
     area: Rect,
@@ -98,7 +101,16 @@ pub fn render_list<T, F>(
         .highlight_symbol(highlight_symbol)
         .repeat_highlight_symbol(false);
 
-    StatefulWidget::render(list_widget, list_area, buf, &mut list.state);
+    // `list.state` tracks an index into `items`, but the widget needs the row in the filtered view
+    let selected_row = list
+        .state
+        .selected()
+        .and_then(|i| list.filtered_indices.iter().position(|&idx| idx == i));
+    let mut view_state = ListState::default()
+        .with_offset(list.state.offset())
+        .with_selected(selected_row);
+    StatefulWidget::render(list_widget, list_area, buf, &mut view_state);
+    *list.state.offset_mut() = view_state.offset();
 
     let status_text = if list.loading_more {
         "Loading more items..."
@@ -110,7 +122,12 @@ pub fn render_list<T, F>(
         "End of list reached"
     };
 
-    Paragraph::new(status_text)
+    let mut info_lines = vec![Line::from(status_text)];
+    if let Some(summary) = &list.summary {
+        info_lines.push(Line::from(summary.as_str()));
+    }
+
+    Paragraph::new(info_lines)
         .bold()
         .alignment(Alignment::Center)
         .render(info_area, buf);
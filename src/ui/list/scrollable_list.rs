@@ -10,6 +10,8 @@ pub struct ScrollableList<T> {
     pub title: String,
     pub loading_more: bool,
     pub has_more: bool,
+    /// Extra status line shown under the list, e.g. totals for a filter
+    pub summary: Option<String>,
 }
 
 impl<T> Default for ScrollableList<T> {
@@ -21,6 +23,7 @@ impl<T> Default for ScrollableList<T> {
             title: String::new(),
             loading_more: false,
             has_more: true,
+            summary: None,
         }
     }
 }
@@ -34,6 +37,7 @@ impl<T> ScrollableList<T> {
             title: title.into(),
             loading_more: false,
             has_more: true,
+            summary: None,
         }
     }
 
@@ -136,23 +140,50 @@ impl<T> ScrollableList<T> {
     where
         F: Fn(&T) -> String,
     {
-        self.filtered_indices.clear();
+        let mut scored: Vec<(usize, i64)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, item)| search_bar.score(&item_to_string(item)).map(|s| (idx, s)))
+            .collect();
+
+        let ranked = search_bar.rank_by_score && !search_bar.query.is_empty();
+        if ranked {
+            // Stable sort keeps the original order for items with equal scores
+            scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        }
+
+        self.filtered_indices = scored.into_iter().map(|(idx, _)| idx).collect();
 
-        for (idx, item) in self.items.iter().enumerate() {
-            let item_str = item_to_string(item);
-            if search_bar.matches(&item_str) {
-                self.filtered_indices.push(idx);
+        if ranked {
+            // Put the best match under the cursor
+            self.first();
+            if self.filtered_indices.is_empty() {
+                self.state.select(None);
+            }
+        } else if let Some(selected) = self.state.selected()
+            && !self.filtered_indices.contains(&selected)
+        {
+            if !self.filtered_indices.is_empty() {
+                self.state.select(Some(self.filtered_indices[0]));
+            } else {
+                self.state.select(None);
             }
         }
+    }
 
-        if let Some(selected) = self.state.selected() {
-            if !self.filtered_indices.contains(&selected) {
-                if !self.filtered_indices.is_empty() {
-                    self.state.select(Some(self.filtered_indices[0]));
-                } else {
-                    self.state.select(None);
-                }
-            }
+    /// Narrows the current filtered view to items that also satisfy `predicate`
+    pub fn retain_filtered<F>(&mut self, predicate: F)
+    where
+        F: Fn(&T) -> bool,
+    {
+        let items = &self.items;
+        self.filtered_indices.retain(|&idx| predicate(&items[idx]));
+
+        if let Some(selected) = self.state.selected()
+            && !self.filtered_indices.contains(&selected)
+        {
+            self.state.select(self.filtered_indices.first().copied());
         }
     }
 
@@ -0,0 +1,141 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// The row for objects directly under the prefix rather than in a sub-prefix
+pub const DIRECT_OBJECTS: &str = "(objects here)";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Totals {
+    pub objects: u64,
+    pub bytes: u64,
+}
+
+impl Totals {
+    fn merge(&mut self, other: Totals) {
+        self.objects += other.objects;
+        self.bytes += other.bytes;
+    }
+}
+
+/// Object count and size under a prefix, broken down by storage class and sub-prefix
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub total: Totals,
+    pub by_class: HashMap<String, Totals>,
+    pub by_prefix: HashMap<String, Totals>,
+}
+
+impl Usage {
+    /// Counts an object listed under `prefix`; objects without a class are STANDARD
+    pub fn add(&mut self, prefix: &str, key: &str, size: u64, storage_class: Option<&str>) {
+        let totals = Totals {
+            objects: 1,
+            bytes: size,
+        };
+        let rest = key.strip_prefix(prefix).unwrap_or(key);
+        let sub_prefix = match rest.find('/') {
+            Some(end) => &rest[..=end],
+            None => DIRECT_OBJECTS,
+        };
+        self.total.merge(totals);
+        self.by_class
+            .entry(storage_class.unwrap_or("STANDARD").to_string())
+            .or_default()
+            .merge(totals);
+        self.by_prefix
+            .entry(sub_prefix.to_string())
+            .or_default()
+            .merge(totals);
+    }
+
+    pub fn merge(&mut self, other: &Usage) {
+        self.total.merge(other.total);
+        for (class, totals) in &other.by_class {
+            self.by_class
+                .entry(class.clone())
+                .or_default()
+                .merge(*totals);
+        }
+        for (prefix, totals) in &other.by_prefix {
+            self.by_prefix
+                .entry(prefix.clone())
+                .or_default()
+                .merge(*totals);
+        }
+    }
+
+    pub fn rows(&self, breakdown: Breakdown, sort: UsageSort) -> Vec<UsageRow> {
+        let groups = match breakdown {
+            Breakdown::Prefix => &self.by_prefix,
+            Breakdown::StorageClass => &self.by_class,
+        };
+        let mut rows: Vec<UsageRow> = groups
+            .iter()
+            .map(|(name, totals)| UsageRow {
+                name: name.clone(),
+                totals: *totals,
+            })
+            .collect();
+        rows.sort_by(|a, b| a.name.cmp(&b.name));
+        match sort {
+            UsageSort::Bytes => rows.sort_by_key(|row| Reverse(row.totals.bytes)),
+            UsageSort::Objects => rows.sort_by_key(|row| Reverse(row.totals.objects)),
+            UsageSort::Name => {}
+        }
+        rows
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageRow {
+    /// A sub-prefix relative to the scanned prefix, or a storage class
+    pub name: String,
+    pub totals: Totals,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakdown {
+    Prefix,
+    StorageClass,
+}
+
+impl Breakdown {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Prefix => Self::StorageClass,
+            Self::StorageClass => Self::Prefix,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Prefix => "sub-prefix",
+            Self::StorageClass => "storage class",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageSort {
+    Bytes,
+    Objects,
+    Name,
+}
+
+impl UsageSort {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Bytes => Self::Objects,
+            Self::Objects => Self::Name,
+            Self::Name => Self::Bytes,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Bytes => "size",
+            Self::Objects => "count",
+            Self::Name => "name",
+        }
+    }
+}
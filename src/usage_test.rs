@@ -0,0 +1,67 @@
+use crate::usage::{Breakdown, DIRECT_OBJECTS, Totals, Usage, UsageSort};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage() -> Usage {
+        let mut usage = Usage::default();
+        usage.add("logs/", "logs/2024/a.log", 100, None);
+        usage.add("logs/", "logs/2024/b.log", 300, Some("GLACIER"));
+        usage.add("logs/", "logs/2025/c.log", 50, Some("STANDARD"));
+        usage.add("logs/", "logs/2025/d.log", 50, Some("STANDARD"));
+        usage.add("logs/", "logs/2025/e.log", 50, Some("STANDARD"));
+        usage.add("logs/", "logs/index.html", 10, None);
+        usage
+    }
+
+    #[test]
+    fn test_usage_totals() {
+        let usage = usage();
+        assert_eq!(
+            usage.total,
+            Totals {
+                objects: 6,
+                bytes: 560
+            }
+        );
+        assert_eq!(usage.by_class["STANDARD"].objects, 5);
+        assert_eq!(usage.by_class["GLACIER"].bytes, 300);
+        assert_eq!(usage.by_prefix["2024/"].bytes, 400);
+        assert_eq!(usage.by_prefix[DIRECT_OBJECTS].objects, 1);
+    }
+
+    #[test]
+    fn test_usage_rows_sorted() {
+        let usage = usage();
+        let names = |sort| {
+            usage
+                .rows(Breakdown::Prefix, sort)
+                .into_iter()
+                .map(|row| row.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(UsageSort::Bytes), ["2024/", "2025/", DIRECT_OBJECTS]);
+        assert_eq!(
+            names(UsageSort::Objects),
+            ["2025/", "2024/", DIRECT_OBJECTS]
+        );
+        assert_eq!(names(UsageSort::Name), [DIRECT_OBJECTS, "2024/", "2025/"]);
+        assert_eq!(
+            usage.rows(Breakdown::StorageClass, UsageSort::Name).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_usage_merge() {
+        let mut merged = Usage::default();
+        let mut page = Usage::default();
+        page.add("", "a/1", 1, None);
+        merged.merge(&page);
+        merged.merge(&page);
+        assert_eq!(merged.total.objects, 2);
+        assert_eq!(merged.by_prefix["a/"].bytes, 2);
+        assert_eq!(merged.by_class["STANDARD"].objects, 2);
+    }
+}
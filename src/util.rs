@@ -1,37 +1,348 @@
+use flate2::read::MultiGzDecoder;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 pub fn create_unique_filepath(dir: &Path, filename: &str) -> PathBuf {
-    let mut path = dir.join(filename);
+    let mut counter = 0;
+    loop {
+        let path = dir.join(numbered_filename(filename, counter));
+        if !path.exists() {
+            return path;
+        }
+        counter += 1;
+    }
+}
+
+/// `name.ext` for 0, then `name (1).ext`, `name (2).ext` and so on
+fn numbered_filename(filename: &str, counter: usize) -> String {
+    if counter == 0 {
+        return filename.to_string();
+    }
+    let path = Path::new(filename);
+    match path.file_stem().and_then(|s| s.to_str()) {
+        Some(stem) => match path.extension().and_then(|e| e.to_str()) {
+            Some(extension) => format!("{} ({}).{}", stem, counter, extension),
+            None => format!("{} ({})", stem, counter),
+        },
+        None => format!("{} ({})", filename, counter),
+    }
+}
 
-    if !path.exists() {
-        return path;
+/// Writes `contents` to a new file in `dir` that only the current user can read
+///
+/// The file is created exclusively, so an existing file or symlink under the same
+/// name is never followed or reused; the next free numbered name is taken instead.
+pub fn create_private_file(
+    dir: &Path,
+    filename: &str,
+    contents: &[u8],
+) -> std::io::Result<PathBuf> {
+    for counter in 0..1000 {
+        let path = dir.join(numbered_filename(filename, counter));
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        match options.open(&path) {
+            Ok(mut file) => {
+                file.write_all(contents)?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
     }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::AlreadyExists,
+        format!("no free file name for {} in {}", filename, dir.display()),
+    ))
+}
 
-    let (stem, extension) = match path.file_stem().and_then(|s| s.to_str()) {
-        Some(s) => (
-            s.to_string(),
-            path.extension()
-                .and_then(|e| e.to_str())
-                .unwrap_or("")
-                .to_string(),
-        ),
-        None => (filename.to_string(), String::new()),
+/// Formats a byte count using the same units as the object list
+pub fn format_size(size: i64) -> String {
+    if size < 1024 {
+        format!("{}B", size)
+    } else if size < 1024 * 1024 {
+        format!("{:.2}KB", size as f64 / 1024.0)
+    } else if size < 1024 * 1024 * 1024 {
+        format!("{:.2}MB", size as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.2}GB", size as f64 / (1024.0 * 1024.0 * 1024.0))
+    }
+}
+
+/// Parses sizes like `512`, `10K`, `1.5MB`, `2 GiB` or `1T` in the binary units of
+/// `format_size`, so anything it prints reads back in
+pub fn parse_size(value: &str) -> Result<i64, String> {
+    let value = value.trim();
+    let unit_start = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(unit_start);
+    let number: f64 = number
+        .trim()
+        .parse()
+        .ok()
+        .filter(|number: &f64| number.is_finite() && *number >= 0.0)
+        .ok_or_else(|| format!("invalid size '{}'", value))?;
+
+    let unit = unit.to_ascii_uppercase();
+    let multiplier: i64 = match unit.strip_suffix('B').unwrap_or(&unit) {
+        "" => 1,
+        "K" | "KI" => 1024,
+        "M" | "MI" => 1024 * 1024,
+        "G" | "GI" => 1024 * 1024 * 1024,
+        "T" | "TI" => 1024 * 1024 * 1024 * 1024,
+        _ => return Err(format!("invalid size unit in '{}'", value)),
     };
 
-    let mut counter = 1;
-    loop {
-        let new_filename = if extension.is_empty() {
-            format!("{} ({})", stem, counter)
-        } else {
-            format!("{} ({}).{}", stem, counter, extension)
-        };
+    Ok((number * multiplier as f64).round() as i64)
+}
 
-        path = dir.join(&new_filename);
-        if !path.exists() {
-            break;
+/// Decompresses gzip data (detected by its magic bytes), returning other data unchanged
+pub fn decompress_if_gzip(bytes: Vec<u8>) -> Vec<u8> {
+    decompress_if_gzip_within(bytes, u64::MAX).unwrap_or_default()
+}
+
+/// Like `decompress_if_gzip`, but gives up with `None` once the data grows past `max` bytes
+pub fn decompress_if_gzip_within(bytes: Vec<u8>, max: u64) -> Option<Vec<u8>> {
+    if !bytes.starts_with(&[0x1f, 0x8b]) {
+        return Some(bytes);
+    }
+
+    let mut decompressed = Vec::new();
+    let mut decoder = MultiGzDecoder::new(bytes.as_slice()).take(max.saturating_add(1));
+    match decoder.read_to_end(&mut decompressed) {
+        Ok(_) if decompressed.len() as u64 > max => None,
+        Ok(_) => Some(decompressed),
+        // Not actually gzip, or truncated; leave it for the caller to treat as binary
+        Err(_) => Some(bytes),
+    }
+}
+
+/// Percent-encodes everything except unreserved characters (and `/` when `keep_slash` is set),
+/// as needed for `x-amz-copy-source` and tagging query strings
+pub fn percent_encode(value: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
         }
-        counter += 1;
     }
+    encoded
+}
+
+/// Standard base64 with padding, as expected by OSC 52
+pub fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// The escape sequence that asks the terminal to put `text` on the system clipboard.
+/// Inside tmux it is wrapped in a passthrough so it reaches the outer terminal.
+pub fn osc52_sequence(text: &str, in_tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", base64_encode(text.as_bytes()));
+    if in_tmux {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+/// Copies `text` to the clipboard of the terminal the app runs in, which works over SSH
+pub fn copy_to_clipboard(text: &str) -> std::io::Result<()> {
+    let in_tmux = std::env::var_os("TMUX").is_some();
+    let mut stdout = std::io::stdout();
+    stdout.write_all(osc52_sequence(text, in_tmux).as_bytes())?;
+    stdout.flush()
+}
+
+/// Parses a duration such as `90s`, `15m`, `12h`, `7d` or `1w`
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let unit = value.chars().last()?;
+    let count: u64 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    let seconds = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(count.checked_mul(seconds)?))
+}
+
+/// Formats a duration in the largest unit that divides it, the inverse of `parse_duration`
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let units = [
+        (7 * 24 * 60 * 60, 'w'),
+        (24 * 60 * 60, 'd'),
+        (60 * 60, 'h'),
+        (60, 'm'),
+    ];
+    for (size, unit) in units {
+        if seconds > 0 && seconds.is_multiple_of(size) {
+            return format!("{}{}", seconds / size, unit);
+        }
+    }
+    format!("{}s", seconds)
+}
 
-    path
+/// Formats a time left to wait with its two largest units, e.g. `1h 05m` or `3m 20s`
+pub fn format_eta(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
+
+/// Where an object with the given key, relative to the downloaded folder, goes under `dir`.
+/// Returns `None` for keys that would escape `dir` (`..` segments) or name no file.
+pub fn local_path_for(dir: &Path, relative_key: &str) -> Option<PathBuf> {
+    let mut path = dir.to_path_buf();
+    let mut segments = 0;
+    for segment in relative_key
+        .split('/')
+        .filter(|s| !s.is_empty() && *s != ".")
+    {
+        if segment == ".." || segment.contains('\\') {
+            return None;
+        }
+        path.push(segment);
+        segments += 1;
+    }
+    (segments > 0 && !relative_key.ends_with('/')).then_some(path)
+}
+
+/// Splits a command line into words the way a POSIX shell does, honouring single and double
+/// quotes and backslash escapes, but without expanding anything
+pub fn split_shell_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\\' => {
+                let escaped = chars.next().ok_or("trailing backslash")?;
+                word.get_or_insert_default().push(escaped);
+            }
+            '\'' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next().ok_or("unterminated single quote")? {
+                        '\'' => break,
+                        c => word.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next().ok_or("unterminated double quote")? {
+                        '"' => break,
+                        // Inside double quotes a backslash only escapes these
+                        '\\' => match chars.next().ok_or("unterminated double quote")? {
+                            c @ ('"' | '\\' | '$' | '`') => word.push(c),
+                            c => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                        },
+                        c => word.push(c),
+                    }
+                }
+            }
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Expands a leading `~` to the home directory
+pub fn expand_tilde(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Completes the last segment of a directory path typed in a prompt. Returns the new input
+/// and, when several directories still match, their names.
+pub fn complete_dir_path(input: &str) -> (String, Vec<String>) {
+    let (parent, partial) = match input.rfind('/') {
+        Some(end) => input.split_at(end + 1),
+        None => ("", input),
+    };
+    let dir = if parent.is_empty() {
+        PathBuf::from(".")
+    } else {
+        expand_tilde(parent)
+    };
+
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        // Hidden directories only when asked for
+        .filter(|name| {
+            name.starts_with(partial) && (partial.starts_with('.') || !name.starts_with('.'))
+        })
+        .collect();
+    names.sort();
+
+    match names.as_slice() {
+        [] => (input.to_string(), Vec::new()),
+        [only] => (format!("{}{}/", parent, only), Vec::new()),
+        _ => {
+            let common = common_prefix(&names);
+            (format!("{}{}", parent, common), names)
+        }
+    }
+}
+
+fn common_prefix(names: &[String]) -> String {
+    let Some(first) = names.first() else {
+        return String::new();
+    };
+    let mut end = first.len();
+    for name in &names[1..] {
+        end = first
+            .char_indices()
+            .zip(name.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(end.min(name.len()), |((index, _), _)| index.min(end));
+    }
+    first[..end].to_string()
 }
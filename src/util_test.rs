@@ -0,0 +1,205 @@
+use crate::util::{
+    base64_encode, complete_dir_path, create_private_file, decompress_if_gzip, expand_tilde,
+    format_duration, format_eta, format_size, local_path_for, osc52_sequence, parse_duration,
+    parse_size, percent_encode, split_shell_words,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use std::path::Path;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512B");
+        assert_eq!(format_size(2048), "2.00KB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.00MB");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024), "5.00GB");
+    }
+
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!(
+            percent_encode("logs/2024/a b+c.txt", true),
+            "logs/2024/a%20b%2Bc.txt"
+        );
+        assert_eq!(percent_encode("a/b", false), "a%2Fb");
+        assert_eq!(percent_encode("é", true), "%C3%A9");
+    }
+
+    #[test]
+    fn test_decompress_if_gzip() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"hello\nworld\n").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert_eq!(decompress_if_gzip(compressed), b"hello\nworld\n");
+        assert_eq!(decompress_if_gzip(b"plain".to_vec()), b"plain");
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"s3://b/k?x=1"), "czM6Ly9iL2s/eD0x");
+    }
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(osc52_sequence("foo", false), "\x1b]52;c;Zm9v\x07");
+        assert_eq!(
+            osc52_sequence("foo", true),
+            "\x1bPtmux;\x1b\x1b]52;c;Zm9v\x07\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_parse_and_format_duration() {
+        assert_eq!(parse_duration("90s"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration(" 15m "), Some(Duration::from_secs(900)));
+        assert_eq!(parse_duration("7d"), Some(Duration::from_secs(604_800)));
+        assert_eq!(parse_duration("1w"), Some(Duration::from_secs(604_800)));
+        assert_eq!(parse_duration("12"), None);
+        assert_eq!(parse_duration("h"), None);
+
+        assert_eq!(format_duration(Duration::from_secs(3600)), "1h");
+        assert_eq!(format_duration(Duration::from_secs(90)), "90s");
+        assert_eq!(format_duration(Duration::from_secs(604_800)), "1w");
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("64K"), Ok(64 * 1024));
+        assert_eq!(parse_size("1.5MB"), Ok(1024 * 1024 * 3 / 2));
+        assert_eq!(parse_size("2 gb"), Ok(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("3GiB"), Ok(3 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("1T"), Ok(1024_i64.pow(4)));
+        // What format_size prints reads back in
+        assert_eq!(parse_size("10.00MB"), Ok(10 * 1024 * 1024));
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("-1K").is_err());
+        assert_eq!(
+            parse_size("10XB"),
+            Err("invalid size unit in '10XB'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_format_eta() {
+        assert_eq!(format_eta(Duration::from_secs(45)), "45s");
+        assert_eq!(format_eta(Duration::from_secs(200)), "3m 20s");
+        assert_eq!(format_eta(Duration::from_secs(3900)), "1h 05m");
+    }
+
+    #[test]
+    fn test_local_path_for() {
+        let dir = Path::new("/tmp/dl");
+        assert_eq!(
+            local_path_for(dir, "logs/2024/app.log"),
+            Some(dir.join("logs").join("2024").join("app.log"))
+        );
+        assert_eq!(
+            local_path_for(dir, "a//./b.txt"),
+            Some(dir.join("a").join("b.txt"))
+        );
+        assert_eq!(local_path_for(dir, "../etc/passwd"), None);
+        assert_eq!(local_path_for(dir, "a/../../b"), None);
+        assert_eq!(local_path_for(dir, "folder/"), None);
+        assert_eq!(local_path_for(dir, ""), None);
+    }
+
+    #[test]
+    fn test_expand_tilde() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_tilde("~/data"), home.join("data"));
+        assert_eq!(expand_tilde("~"), home);
+        assert_eq!(expand_tilde("~other/x"), Path::new("~other/x"));
+        assert_eq!(expand_tilde("/abs"), Path::new("/abs"));
+    }
+
+    #[test]
+    fn test_complete_dir_path() {
+        let root = std::env::temp_dir().join(format!("complete_dir_path_{}", std::process::id()));
+        for dir in ["reports-2023", "reports-2024", "raw", ".hidden"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join("readme.txt"), "").unwrap();
+        let base = format!("{}/", root.display());
+
+        assert_eq!(
+            complete_dir_path(&format!("{}ra", base)),
+            (format!("{}raw/", base), vec![])
+        );
+        assert_eq!(
+            complete_dir_path(&format!("{}rep", base)),
+            (
+                format!("{}reports-202", base),
+                vec!["reports-2023".to_string(), "reports-2024".to_string()]
+            )
+        );
+        // Files are not offered, and hidden directories only with a leading dot
+        assert_eq!(complete_dir_path(&format!("{}rea", base)).1.len(), 0);
+        assert_eq!(complete_dir_path(&format!("{}r", base)).1.len(), 3);
+        assert_eq!(
+            complete_dir_path(&format!("{}.h", base)).0,
+            format!("{}.hidden/", base)
+        );
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_create_private_file() {
+        let root = std::env::temp_dir().join(format!("private_file_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("notes.txt"), "theirs").unwrap();
+
+        // An existing file is left alone and the next numbered name is used
+        let path = create_private_file(&root, "notes.txt", b"mine").unwrap();
+        assert_eq!(path, root.join("notes (1).txt"));
+        assert_eq!(std::fs::read(root.join("notes.txt")).unwrap(), b"theirs");
+        assert_eq!(std::fs::read(&path).unwrap(), b"mine");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+
+            // A dangling symlink is not followed either
+            std::os::unix::fs::symlink(root.join("target"), root.join("link.json")).unwrap();
+            let path = create_private_file(&root, "link.json", b"{}").unwrap();
+            assert_eq!(path, root.join("link (1).json"));
+            assert!(!root.join("target").exists());
+        }
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_split_shell_words() {
+        let words = |line: &str| split_shell_words(line).unwrap();
+        assert_eq!(words("  code   --wait "), vec!["code", "--wait"]);
+        assert_eq!(
+            words(r#"open -a "Visual Studio Code""#),
+            vec!["open", "-a", "Visual Studio Code"]
+        );
+        assert_eq!(
+            words(r#"jq '.[] | .name' "a\"b" c\ d"#),
+            vec!["jq", ".[] | .name", "a\"b", "c d"]
+        );
+        assert_eq!(words(r#"x"y"'z' "" "#), vec!["xyz", ""]);
+        assert_eq!(words(r#""a\nb""#), vec!["a\\nb"]);
+        assert!(words("").is_empty());
+        assert!(split_shell_words("vim 'a").is_err());
+        assert!(split_shell_words(r#"vim "a"#).is_err());
+        assert!(split_shell_words("vim \\").is_err());
+    }
+}